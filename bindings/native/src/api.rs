@@ -0,0 +1,580 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! General C API to the Stronghold client.
+//!
+//! Every function returns a [`StrongholdStatus`], results are written to out-parameters. Objects created by the
+//! library are opaque handles that have to be released with the respective `_free` function. Byte results are
+//! returned as [`StrongholdBuffer`], which has to be released with [`stronghold_buffer_free`].
+//!
+//! Procedures are passed as JSON serialized `StrongholdProcedure`, e.g.
+//! `{"GenerateKey":{"ty":"Ed25519","output":{"Generic":{"vault_path":[118],"record_path":[114]}}}}`.
+//! This way every procedure of the client is available without a dedicated function.
+
+use std::{ffi::CStr, os::raw::c_char, path::Path, ptr, slice, time::Duration};
+
+use iota_stronghold::{
+    procedures::{ProcedureError, StrongholdProcedure},
+    sync::{MergePolicy, SyncClientsConfig},
+    Client, ClientError, KeyProvider, Location, SnapshotPath, Stronghold,
+};
+use zeroize::Zeroize;
+
+/// Result of a call to the C API.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrongholdStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = 1,
+    /// An argument is malformed, e.g. a path that is not valid UTF-8.
+    InvalidArgument = 2,
+    /// The procedure buffer could not be deserialized.
+    InvalidProcedure = 3,
+    /// The requested client, record or store entry does not exist.
+    NotFound = 4,
+    /// The client has already been loaded.
+    AlreadyLoaded = 5,
+    /// The snapshot file is missing or could not be read or written.
+    Snapshot = 6,
+    /// The Stronghold instance is not bound to a snapshot file.
+    NoSnapshot = 7,
+    /// The execution of a procedure failed.
+    Procedure = 8,
+    /// The policy of a record does not permit the procedure.
+    Policy = 9,
+    /// Any other error of the client.
+    Client = 10,
+}
+
+/// Byte string allocated by the library.
+#[repr(C)]
+pub struct StrongholdBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl StrongholdBuffer {
+    fn new(data: Vec<u8>) -> Self {
+        let len = data.len();
+        let data = Box::into_raw(data.into_boxed_slice()) as *mut u8;
+        StrongholdBuffer { data, len }
+    }
+}
+
+/// Location of a record. If `is_counter` is set, the location is a counter location in the vault and
+/// `record_path` is ignored.
+#[repr(C)]
+pub struct StrongholdLocation {
+    pub vault_path: *const u8,
+    pub vault_path_len: usize,
+    pub record_path: *const u8,
+    pub record_path_len: usize,
+    pub is_counter: bool,
+    pub counter: usize,
+}
+
+/// Policy for records that exist in both clients of a sync.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrongholdMergePolicy {
+    KeepOld = 0,
+    Replace = 1,
+}
+
+impl From<StrongholdMergePolicy> for MergePolicy {
+    fn from(policy: StrongholdMergePolicy) -> Self {
+        match policy {
+            StrongholdMergePolicy::KeepOld => MergePolicy::KeepOld,
+            StrongholdMergePolicy::Replace => MergePolicy::Replace,
+        }
+    }
+}
+
+/// A Stronghold instance, optionally bound to a snapshot file.
+pub struct StrongholdHandle {
+    stronghold: Stronghold,
+    snapshot: Option<(SnapshotPath, KeyProvider)>,
+}
+
+/// A client of a [`StrongholdHandle`].
+pub struct StrongholdClient {
+    client: Client,
+}
+
+impl From<ClientError> for StrongholdStatus {
+    fn from(e: ClientError) -> Self {
+        match e {
+            ClientError::NoValuePresent(_) | ClientError::ClientDataNotPresent => StrongholdStatus::NotFound,
+            ClientError::ClientAlreadyLoaded(_) => StrongholdStatus::AlreadyLoaded,
+            ClientError::SnapshotFileMissing(_) => StrongholdStatus::Snapshot,
+            ClientError::Procedure(e) => e.into(),
+            _ => StrongholdStatus::Client,
+        }
+    }
+}
+
+impl From<ProcedureError> for StrongholdStatus {
+    fn from(e: ProcedureError) -> Self {
+        match e {
+            ProcedureError::Policy(_) | ProcedureError::Permission(_) => StrongholdStatus::Policy,
+            _ => StrongholdStatus::Procedure,
+        }
+    }
+}
+
+type ApiResult<T = ()> = Result<T, StrongholdStatus>;
+
+fn status(result: ApiResult) -> StrongholdStatus {
+    match result {
+        Ok(()) => StrongholdStatus::Ok,
+        Err(status) => status,
+    }
+}
+
+unsafe fn reference<'a, T>(ptr: *const T) -> ApiResult<&'a T> {
+    ptr.as_ref().ok_or(StrongholdStatus::NullPointer)
+}
+
+unsafe fn write_out<T>(out: *mut T, value: T) -> ApiResult {
+    if out.is_null() {
+        return Err(StrongholdStatus::NullPointer);
+    }
+    out.write(value);
+    Ok(())
+}
+
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> ApiResult<&'a [u8]> {
+    match data.is_null() {
+        true if len == 0 => Ok(&[]),
+        true => Err(StrongholdStatus::NullPointer),
+        false => Ok(slice::from_raw_parts(data, len)),
+    }
+}
+
+unsafe fn path<'a>(path: *const c_char) -> ApiResult<&'a Path> {
+    if path.is_null() {
+        return Err(StrongholdStatus::NullPointer);
+    }
+    CStr::from_ptr(path)
+        .to_str()
+        .map(Path::new)
+        .map_err(|_| StrongholdStatus::InvalidArgument)
+}
+
+unsafe fn location(location: *const StrongholdLocation) -> ApiResult<Location> {
+    let location = reference(location)?;
+    let vault_path = bytes(location.vault_path, location.vault_path_len)?;
+    if location.is_counter {
+        return Ok(Location::counter(vault_path, location.counter));
+    }
+    let record_path = bytes(location.record_path, location.record_path_len)?;
+    Ok(Location::generic(vault_path, record_path))
+}
+
+/// Returns a static, NUL terminated description of `status`.
+#[no_mangle]
+pub extern "C" fn stronghold_status_message(status: StrongholdStatus) -> *const c_char {
+    let message: &'static [u8] = match status {
+        StrongholdStatus::Ok => b"ok\0",
+        StrongholdStatus::NullPointer => b"null pointer argument\0",
+        StrongholdStatus::InvalidArgument => b"invalid argument\0",
+        StrongholdStatus::InvalidProcedure => b"invalid procedure\0",
+        StrongholdStatus::NotFound => b"not found\0",
+        StrongholdStatus::AlreadyLoaded => b"client has already been loaded\0",
+        StrongholdStatus::Snapshot => b"snapshot error\0",
+        StrongholdStatus::NoSnapshot => b"no snapshot file\0",
+        StrongholdStatus::Procedure => b"procedure failed\0",
+        StrongholdStatus::Policy => b"record policy violated\0",
+        StrongholdStatus::Client => b"client error\0",
+    };
+    message.as_ptr() as *const c_char
+}
+
+/// Releases a buffer returned by the library. The content is zeroed before it is freed.
+///
+/// # Safety
+/// `buffer` must be null or point to a [`StrongholdBuffer`] returned by the library.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_buffer_free(buffer: *mut StrongholdBuffer) {
+    let buffer = match buffer.as_mut() {
+        Some(buffer) if !buffer.data.is_null() => buffer,
+        _ => return,
+    };
+    let mut data = Box::from_raw(ptr::slice_from_raw_parts_mut(buffer.data, buffer.len));
+    data.zeroize();
+    buffer.data = ptr::null_mut();
+    buffer.len = 0;
+}
+
+/// Creates an in-memory Stronghold instance that is not bound to a snapshot file.
+///
+/// # Safety
+/// `out` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_new(out: *mut *mut StrongholdHandle) -> StrongholdStatus {
+    let handle = StrongholdHandle {
+        stronghold: Stronghold::default(),
+        snapshot: None,
+    };
+    status(write_out(out, Box::into_raw(Box::new(handle))))
+}
+
+/// Creates a Stronghold instance bound to the snapshot file at `snapshot_path`, encrypted with the Blake2b hash of
+/// `passphrase`. If the file exists, the snapshot is loaded.
+///
+/// # Safety
+/// `snapshot_path` must be a NUL terminated string, `passphrase` must point to `passphrase_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_open_snapshot(
+    snapshot_path: *const c_char,
+    passphrase: *const u8,
+    passphrase_len: usize,
+    out: *mut *mut StrongholdHandle,
+) -> StrongholdStatus {
+    status((|| {
+        let snapshot_path = SnapshotPath::from_path(path(snapshot_path)?);
+        let keyprovider = KeyProvider::with_passphrase_hashed_blake2b(bytes(passphrase, passphrase_len)?.to_vec())?;
+        let stronghold = Stronghold::default();
+        if snapshot_path.exists() {
+            stronghold
+                .load_snapshot(&keyprovider, &snapshot_path)
+                .map_err(|_| StrongholdStatus::Snapshot)?;
+        }
+        let handle = StrongholdHandle {
+            stronghold,
+            snapshot: Some((snapshot_path, keyprovider)),
+        };
+        write_out(out, Box::into_raw(Box::new(handle)))
+    })())
+}
+
+/// Releases a Stronghold instance. Uncommitted changes are lost.
+///
+/// # Safety
+/// `handle` must be null or a handle returned by the library that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_free(handle: *mut StrongholdHandle) {
+    if !handle.is_null() {
+        let _ = Box::from_raw(handle);
+    }
+}
+
+unsafe fn open_client<F>(
+    handle: *const StrongholdHandle,
+    client_path: *const u8,
+    client_path_len: usize,
+    out: *mut *mut StrongholdClient,
+    f: F,
+) -> StrongholdStatus
+where
+    F: FnOnce(&Stronghold, &[u8]) -> Result<Client, ClientError>,
+{
+    status((|| {
+        let handle = reference(handle)?;
+        let client = f(&handle.stronghold, bytes(client_path, client_path_len)?)?;
+        write_out(out, Box::into_raw(Box::new(StrongholdClient { client })))
+    })())
+}
+
+/// Creates a new client at `client_path`.
+///
+/// # Safety
+/// `handle` must be a valid handle, `client_path` must point to `client_path_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_create_client(
+    handle: *const StrongholdHandle,
+    client_path: *const u8,
+    client_path_len: usize,
+    out: *mut *mut StrongholdClient,
+) -> StrongholdStatus {
+    open_client(handle, client_path, client_path_len, out, |stronghold, path| {
+        stronghold.create_client(path)
+    })
+}
+
+/// Loads the client at `client_path` from the snapshot of the Stronghold instance.
+///
+/// # Safety
+/// `handle` must be a valid handle, `client_path` must point to `client_path_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_load_client(
+    handle: *const StrongholdHandle,
+    client_path: *const u8,
+    client_path_len: usize,
+    out: *mut *mut StrongholdClient,
+) -> StrongholdStatus {
+    open_client(handle, client_path, client_path_len, out, |stronghold, path| {
+        stronghold.load_client(path)
+    })
+}
+
+/// Returns the already loaded client at `client_path`.
+///
+/// # Safety
+/// `handle` must be a valid handle, `client_path` must point to `client_path_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_get_client(
+    handle: *const StrongholdHandle,
+    client_path: *const u8,
+    client_path_len: usize,
+    out: *mut *mut StrongholdClient,
+) -> StrongholdStatus {
+    open_client(handle, client_path, client_path_len, out, |stronghold, path| {
+        stronghold.get_client(path)
+    })
+}
+
+/// Writes the state of the client at `client_path` into the snapshot of the Stronghold instance, without
+/// persisting it.
+///
+/// # Safety
+/// `handle` must be a valid handle, `client_path` must point to `client_path_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_write_client(
+    handle: *const StrongholdHandle,
+    client_path: *const u8,
+    client_path_len: usize,
+) -> StrongholdStatus {
+    status((|| {
+        let handle = reference(handle)?;
+        handle.stronghold.write_client(bytes(client_path, client_path_len)?)?;
+        Ok(())
+    })())
+}
+
+/// Writes all loaded clients into the snapshot file the Stronghold instance is bound to.
+///
+/// # Safety
+/// `handle` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_commit(handle: *const StrongholdHandle) -> StrongholdStatus {
+    status((|| {
+        let handle = reference(handle)?;
+        let (snapshot_path, keyprovider) = handle.snapshot.as_ref().ok_or(StrongholdStatus::NoSnapshot)?;
+        handle
+            .stronghold
+            .commit_with_keyprovider(snapshot_path, keyprovider)
+            .map_err(|_| StrongholdStatus::Snapshot)
+    })())
+}
+
+/// Releases a client handle. The client stays loaded in its Stronghold instance.
+///
+/// # Safety
+/// `client` must be null or a handle returned by the library that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_client_free(client: *mut StrongholdClient) {
+    if !client.is_null() {
+        let _ = Box::from_raw(client);
+    }
+}
+
+/// Checks if the vault at `vault_path` exists.
+///
+/// # Safety
+/// `client` must be a valid handle, `vault_path` must point to `vault_path_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_client_vault_exists(
+    client: *const StrongholdClient,
+    vault_path: *const u8,
+    vault_path_len: usize,
+    out: *mut bool,
+) -> StrongholdStatus {
+    status((|| {
+        let exists = reference(client)?
+            .client
+            .vault_exists(bytes(vault_path, vault_path_len)?)?;
+        write_out(out, exists)
+    })())
+}
+
+/// Checks if a record exists at `location`.
+///
+/// # Safety
+/// `client` must be a valid handle, `location` must point to a valid [`StrongholdLocation`].
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_client_record_exists(
+    client: *const StrongholdClient,
+    location: *const StrongholdLocation,
+    out: *mut bool,
+) -> StrongholdStatus {
+    status((|| {
+        let exists = reference(client)?.client.record_exists(&self::location(location)?)?;
+        write_out(out, exists)
+    })())
+}
+
+/// Writes `secret` into the record at `location`.
+///
+/// # Safety
+/// `client` must be a valid handle, `location` must point to a valid [`StrongholdLocation`] and `secret` to
+/// `secret_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_client_write_secret(
+    client: *const StrongholdClient,
+    location: *const StrongholdLocation,
+    secret: *const u8,
+    secret_len: usize,
+) -> StrongholdStatus {
+    status((|| {
+        let client = &reference(client)?.client;
+        let location = self::location(location)?;
+        client
+            .vault(location.vault_path())
+            .write_secret(location, bytes(secret, secret_len)?.to_vec())?;
+        Ok(())
+    })())
+}
+
+/// Executes the JSON serialized `StrongholdProcedure` in `procedure` and writes its output into `out`.
+///
+/// # Safety
+/// `client` must be a valid handle, `procedure` must point to `procedure_len` bytes and `out` to a
+/// [`StrongholdBuffer`].
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_client_execute(
+    client: *const StrongholdClient,
+    procedure: *const u8,
+    procedure_len: usize,
+    out: *mut StrongholdBuffer,
+) -> StrongholdStatus {
+    status((|| {
+        let client = &reference(client)?.client;
+        let mut procedure_json = bytes(procedure, procedure_len)?.to_vec();
+        let procedure = serde_json::from_slice::<StrongholdProcedure>(&procedure_json);
+        procedure_json.zeroize();
+        let output = client
+            .execute_procedure(procedure.map_err(|_| StrongholdStatus::InvalidProcedure)?)
+            .map_err(StrongholdStatus::from)?;
+        write_out(out, StrongholdBuffer::new(output.into()))
+    })())
+}
+
+/// Copies the records of `other` into `client`. Records that exist in both clients are merged according to
+/// `merge_policy`.
+///
+/// # Safety
+/// `client` and `other` must be valid handles.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_client_sync_with(
+    client: *const StrongholdClient,
+    other: *const StrongholdClient,
+    merge_policy: StrongholdMergePolicy,
+) -> StrongholdStatus {
+    status((|| {
+        let config = SyncClientsConfig::new(merge_policy.into());
+        reference(client)?.client.sync_with(&reference(other)?.client, config)?;
+        Ok(())
+    })())
+}
+
+/// Copies the records of the vault at `source_path` into the vault at `target_path` of the same client.
+///
+/// # Safety
+/// `client` must be a valid handle, the paths must point to the given number of bytes.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_client_sync_vaults(
+    client: *const StrongholdClient,
+    source_path: *const u8,
+    source_path_len: usize,
+    target_path: *const u8,
+    target_path_len: usize,
+    merge_policy: StrongholdMergePolicy,
+) -> StrongholdStatus {
+    status((|| {
+        reference(client)?.client.sync_vaults(
+            bytes(source_path, source_path_len)?.to_vec(),
+            bytes(target_path, target_path_len)?.to_vec(),
+            None,
+            merge_policy.into(),
+        )?;
+        Ok(())
+    })())
+}
+
+/// Inserts `value` at `key` into the store of the client. If `lifetime_secs` is not `0`, the entry expires
+/// after the given number of seconds.
+///
+/// # Safety
+/// `client` must be a valid handle, `key` and `value` must point to the given number of bytes.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_client_store_insert(
+    client: *const StrongholdClient,
+    key: *const u8,
+    key_len: usize,
+    value: *const u8,
+    value_len: usize,
+    lifetime_secs: u64,
+) -> StrongholdStatus {
+    status((|| {
+        let lifetime = (lifetime_secs != 0).then(|| Duration::from_secs(lifetime_secs));
+        reference(client)?.client.store().insert(
+            bytes(key, key_len)?.to_vec(),
+            bytes(value, value_len)?.to_vec(),
+            lifetime,
+        )?;
+        Ok(())
+    })())
+}
+
+/// Writes the value at `key` in the store of the client into `out`. Returns [`StrongholdStatus::NotFound`] if
+/// there is no such entry.
+///
+/// # Safety
+/// `client` must be a valid handle, `key` must point to `key_len` bytes and `out` to a [`StrongholdBuffer`].
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_client_store_get(
+    client: *const StrongholdClient,
+    key: *const u8,
+    key_len: usize,
+    out: *mut StrongholdBuffer,
+) -> StrongholdStatus {
+    status((|| {
+        let value = reference(client)?
+            .client
+            .store()
+            .get(bytes(key, key_len)?)?
+            .ok_or(StrongholdStatus::NotFound)?;
+        write_out(out, StrongholdBuffer::new(value))
+    })())
+}
+
+/// Removes the entry at `key` from the store of the client. Returns [`StrongholdStatus::NotFound`] if there is
+/// no such entry.
+///
+/// # Safety
+/// `client` must be a valid handle, `key` must point to `key_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_client_store_delete(
+    client: *const StrongholdClient,
+    key: *const u8,
+    key_len: usize,
+) -> StrongholdStatus {
+    status((|| {
+        reference(client)?
+            .client
+            .store()
+            .delete(bytes(key, key_len)?)?
+            .ok_or(StrongholdStatus::NotFound)?;
+        Ok(())
+    })())
+}
+
+/// Checks if the store of the client contains an entry at `key`.
+///
+/// # Safety
+/// `client` must be a valid handle, `key` must point to `key_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn stronghold_client_store_contains(
+    client: *const StrongholdClient,
+    key: *const u8,
+    key_len: usize,
+    out: *mut bool,
+) -> StrongholdStatus {
+    status((|| {
+        let contains = reference(client)?.client.store().contains_key(bytes(key, key_len)?)?;
+        write_out(out, contains)
+    })())
+}
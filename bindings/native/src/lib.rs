@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 extern crate core;
 
+pub mod api;
 mod shared;
 mod wrapper;
 
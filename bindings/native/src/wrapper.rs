@@ -126,14 +126,10 @@ impl StrongholdWrapper {
             private_key,
         };
 
-        let procedure_result = match self.client.execute_procedure(public_key_procedure) {
-            Ok(res) => res,
-            Err(_err) => return Err(WrapperError::ExecuteProcedure(format!("{:?}", _err))),
-        };
-
-        let output: Vec<u8> = procedure_result.into();
-
-        Ok(output)
+        match self.client.execute_procedure(public_key_procedure) {
+            Ok(res) => Ok(res),
+            Err(_err) => Err(WrapperError::ExecuteProcedure(format!("{:?}", _err))),
+        }
     }
 
     pub fn write_vault<R>(&self, key_as_hash: R, record_path: String, data: Vec<u8>) -> Result<bool, WrapperError>
@@ -0,0 +1,393 @@
+#ifndef STRONGHOLD_NATIVE_H
+#define STRONGHOLD_NATIVE_H
+
+/* Generated with cbindgen by bindgen.sh, do not edit manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of a call to the C API.
+ */
+typedef enum StrongholdStatus {
+  /**
+   * The call succeeded.
+   */
+  STRONGHOLD_STATUS_OK = 0,
+  /**
+   * A required pointer argument was null.
+   */
+  STRONGHOLD_STATUS_NULL_POINTER = 1,
+  /**
+   * An argument is malformed, e.g. a path that is not valid UTF-8.
+   */
+  STRONGHOLD_STATUS_INVALID_ARGUMENT = 2,
+  /**
+   * The procedure buffer could not be deserialized.
+   */
+  STRONGHOLD_STATUS_INVALID_PROCEDURE = 3,
+  /**
+   * The requested client, record or store entry does not exist.
+   */
+  STRONGHOLD_STATUS_NOT_FOUND = 4,
+  /**
+   * The client has already been loaded.
+   */
+  STRONGHOLD_STATUS_ALREADY_LOADED = 5,
+  /**
+   * The snapshot file is missing or could not be read or written.
+   */
+  STRONGHOLD_STATUS_SNAPSHOT = 6,
+  /**
+   * The Stronghold instance is not bound to a snapshot file.
+   */
+  STRONGHOLD_STATUS_NO_SNAPSHOT = 7,
+  /**
+   * The execution of a procedure failed.
+   */
+  STRONGHOLD_STATUS_PROCEDURE = 8,
+  /**
+   * The policy of a record does not permit the procedure.
+   */
+  STRONGHOLD_STATUS_POLICY = 9,
+  /**
+   * Any other error of the client.
+   */
+  STRONGHOLD_STATUS_CLIENT = 10,
+} StrongholdStatus;
+
+/**
+ * Policy for records that exist in both clients of a sync.
+ */
+typedef enum StrongholdMergePolicy {
+  STRONGHOLD_MERGE_POLICY_KEEP_OLD = 0,
+  STRONGHOLD_MERGE_POLICY_REPLACE = 1,
+} StrongholdMergePolicy;
+
+/**
+ * A client of a [`StrongholdHandle`].
+ */
+typedef struct StrongholdClient StrongholdClient;
+
+/**
+ * A Stronghold instance, optionally bound to a snapshot file.
+ */
+typedef struct StrongholdHandle StrongholdHandle;
+
+typedef struct StrongholdWrapper StrongholdWrapper;
+
+/**
+ * Byte string allocated by the library.
+ */
+typedef struct StrongholdBuffer {
+  uint8_t *data;
+  uintptr_t len;
+} StrongholdBuffer;
+
+/**
+ * Location of a record. If `is_counter` is set, the location is a counter location in the vault and
+ * `record_path` is ignored.
+ */
+typedef struct StrongholdLocation {
+  const uint8_t *vault_path;
+  uintptr_t vault_path_len;
+  const uint8_t *record_path;
+  uintptr_t record_path_len;
+  bool is_counter;
+  uintptr_t counter;
+} StrongholdLocation;
+
+/**
+ * Returns a static, NUL terminated description of `status`.
+ */
+const char *stronghold_status_message(enum StrongholdStatus status);
+
+/**
+ * Releases a buffer returned by the library. The content is zeroed before it is freed.
+ *
+ * # Safety
+ * `buffer` must be null or point to a [`StrongholdBuffer`] returned by the library.
+ */
+void stronghold_buffer_free(struct StrongholdBuffer *buffer);
+
+/**
+ * Creates an in-memory Stronghold instance that is not bound to a snapshot file.
+ *
+ * # Safety
+ * `out` must be a valid pointer.
+ */
+enum StrongholdStatus stronghold_new(struct StrongholdHandle **out);
+
+/**
+ * Creates a Stronghold instance bound to the snapshot file at `snapshot_path`, encrypted with the Blake2b hash of
+ * `passphrase`. If the file exists, the snapshot is loaded.
+ *
+ * # Safety
+ * `snapshot_path` must be a NUL terminated string, `passphrase` must point to `passphrase_len` bytes.
+ */
+enum StrongholdStatus stronghold_open_snapshot(const char *snapshot_path,
+                                               const uint8_t *passphrase,
+                                               uintptr_t passphrase_len,
+                                               struct StrongholdHandle **out);
+
+/**
+ * Releases a Stronghold instance. Uncommitted changes are lost.
+ *
+ * # Safety
+ * `handle` must be null or a handle returned by the library that has not been freed.
+ */
+void stronghold_free(struct StrongholdHandle *handle);
+
+/**
+ * Creates a new client at `client_path`.
+ *
+ * # Safety
+ * `handle` must be a valid handle, `client_path` must point to `client_path_len` bytes.
+ */
+enum StrongholdStatus stronghold_create_client(const struct StrongholdHandle *handle,
+                                               const uint8_t *client_path,
+                                               uintptr_t client_path_len,
+                                               struct StrongholdClient **out);
+
+/**
+ * Loads the client at `client_path` from the snapshot of the Stronghold instance.
+ *
+ * # Safety
+ * `handle` must be a valid handle, `client_path` must point to `client_path_len` bytes.
+ */
+enum StrongholdStatus stronghold_load_client(const struct StrongholdHandle *handle,
+                                             const uint8_t *client_path,
+                                             uintptr_t client_path_len,
+                                             struct StrongholdClient **out);
+
+/**
+ * Returns the already loaded client at `client_path`.
+ *
+ * # Safety
+ * `handle` must be a valid handle, `client_path` must point to `client_path_len` bytes.
+ */
+enum StrongholdStatus stronghold_get_client(const struct StrongholdHandle *handle,
+                                            const uint8_t *client_path,
+                                            uintptr_t client_path_len,
+                                            struct StrongholdClient **out);
+
+/**
+ * Writes the state of the client at `client_path` into the snapshot of the Stronghold instance, without
+ * persisting it.
+ *
+ * # Safety
+ * `handle` must be a valid handle, `client_path` must point to `client_path_len` bytes.
+ */
+enum StrongholdStatus stronghold_write_client(const struct StrongholdHandle *handle,
+                                              const uint8_t *client_path,
+                                              uintptr_t client_path_len);
+
+/**
+ * Writes all loaded clients into the snapshot file the Stronghold instance is bound to.
+ *
+ * # Safety
+ * `handle` must be a valid handle.
+ */
+enum StrongholdStatus stronghold_commit(const struct StrongholdHandle *handle);
+
+/**
+ * Releases a client handle. The client stays loaded in its Stronghold instance.
+ *
+ * # Safety
+ * `client` must be null or a handle returned by the library that has not been freed.
+ */
+void stronghold_client_free(struct StrongholdClient *client);
+
+/**
+ * Checks if the vault at `vault_path` exists.
+ *
+ * # Safety
+ * `client` must be a valid handle, `vault_path` must point to `vault_path_len` bytes.
+ */
+enum StrongholdStatus stronghold_client_vault_exists(const struct StrongholdClient *client,
+                                                     const uint8_t *vault_path,
+                                                     uintptr_t vault_path_len,
+                                                     bool *out);
+
+/**
+ * Checks if a record exists at `location`.
+ *
+ * # Safety
+ * `client` must be a valid handle, `location` must point to a valid [`StrongholdLocation`].
+ */
+enum StrongholdStatus stronghold_client_record_exists(const struct StrongholdClient *client,
+                                                      const struct StrongholdLocation *location,
+                                                      bool *out);
+
+/**
+ * Writes `secret` into the record at `location`.
+ *
+ * # Safety
+ * `client` must be a valid handle, `location` must point to a valid [`StrongholdLocation`] and `secret` to
+ * `secret_len` bytes.
+ */
+enum StrongholdStatus stronghold_client_write_secret(const struct StrongholdClient *client,
+                                                     const struct StrongholdLocation *location,
+                                                     const uint8_t *secret,
+                                                     uintptr_t secret_len);
+
+/**
+ * Executes the JSON serialized `StrongholdProcedure` in `procedure` and writes its output into `out`.
+ *
+ * # Safety
+ * `client` must be a valid handle, `procedure` must point to `procedure_len` bytes and `out` to a
+ * [`StrongholdBuffer`].
+ */
+enum StrongholdStatus stronghold_client_execute(const struct StrongholdClient *client,
+                                                const uint8_t *procedure,
+                                                uintptr_t procedure_len,
+                                                struct StrongholdBuffer *out);
+
+/**
+ * Copies the records of `other` into `client`. Records that exist in both clients are merged according to
+ * `merge_policy`.
+ *
+ * # Safety
+ * `client` and `other` must be valid handles.
+ */
+enum StrongholdStatus stronghold_client_sync_with(const struct StrongholdClient *client,
+                                                  const struct StrongholdClient *other,
+                                                  enum StrongholdMergePolicy merge_policy);
+
+/**
+ * Copies the records of the vault at `source_path` into the vault at `target_path` of the same client.
+ *
+ * # Safety
+ * `client` must be a valid handle, the paths must point to the given number of bytes.
+ */
+enum StrongholdStatus stronghold_client_sync_vaults(const struct StrongholdClient *client,
+                                                    const uint8_t *source_path,
+                                                    uintptr_t source_path_len,
+                                                    const uint8_t *target_path,
+                                                    uintptr_t target_path_len,
+                                                    enum StrongholdMergePolicy merge_policy);
+
+/**
+ * Inserts `value` at `key` into the store of the client. If `lifetime_secs` is not `0`, the entry expires
+ * after the given number of seconds.
+ *
+ * # Safety
+ * `client` must be a valid handle, `key` and `value` must point to the given number of bytes.
+ */
+enum StrongholdStatus stronghold_client_store_insert(const struct StrongholdClient *client,
+                                                     const uint8_t *key,
+                                                     uintptr_t key_len,
+                                                     const uint8_t *value,
+                                                     uintptr_t value_len,
+                                                     uint64_t lifetime_secs);
+
+/**
+ * Writes the value at `key` in the store of the client into `out`. Returns [`StrongholdStatus::NotFound`] if
+ * there is no such entry.
+ *
+ * # Safety
+ * `client` must be a valid handle, `key` must point to `key_len` bytes and `out` to a [`StrongholdBuffer`].
+ */
+enum StrongholdStatus stronghold_client_store_get(const struct StrongholdClient *client,
+                                                  const uint8_t *key,
+                                                  uintptr_t key_len,
+                                                  struct StrongholdBuffer *out);
+
+/**
+ * Removes the entry at `key` from the store of the client. Returns [`StrongholdStatus::NotFound`] if there is
+ * no such entry.
+ *
+ * # Safety
+ * `client` must be a valid handle, `key` must point to `key_len` bytes.
+ */
+enum StrongholdStatus stronghold_client_store_delete(const struct StrongholdClient *client,
+                                                     const uint8_t *key,
+                                                     uintptr_t key_len);
+
+/**
+ * Checks if the store of the client contains an entry at `key`.
+ *
+ * # Safety
+ * `client` must be a valid handle, `key` must point to `key_len` bytes.
+ */
+enum StrongholdStatus stronghold_client_store_contains(const struct StrongholdClient *client,
+                                                       const uint8_t *key,
+                                                       uintptr_t key_len,
+                                                       bool *out);
+
+void stronghold_set_log_level(size_t log_level);
+
+/**
+ * # Safety
+ */
+const char *stronghold_get_last_error(void);
+
+/**
+ * # Safety
+ */
+void stronghold_destroy_error(char *s);
+
+/**
+ * # Safety
+ */
+struct StrongholdWrapper *stronghold_create(const char *snapshot_path_c, const char *key_c);
+
+/**
+ * # Safety
+ */
+struct StrongholdWrapper *stronghold_load(const char *snapshot_path_c, const char *key_c);
+
+/**
+ * # Safety
+ */
+void stronghold_destroy_stronghold(struct StrongholdWrapper *stronghold_ptr);
+
+/**
+ * # Safety
+ */
+void stronghold_destroy_data_pointer(uint8_t *ptr);
+
+/**
+ * # Safety
+ */
+uint8_t *stronghold_generate_ed25519_keypair(struct StrongholdWrapper *stronghold_ptr,
+                                             const char *key_c,
+                                             const char *record_path_c);
+
+/**
+ * # Safety
+ */
+bool stronghold_write_vault(struct StrongholdWrapper *stronghold_ptr,
+                            const char *key_c,
+                            const char *record_path_c,
+                            const unsigned char *data_c,
+                            size_t data_length);
+
+/**
+ * # Safety
+ */
+bool stronghold_generate_seed(struct StrongholdWrapper *stronghold_ptr, const char *key_c);
+
+/**
+ * # Safety
+ */
+bool stronghold_derive_seed(struct StrongholdWrapper *stronghold_ptr,
+                            const char *key_c,
+                            uint32_t address_index);
+
+/**
+ * # Safety
+ */
+uint8_t *stronghold_get_public_key(struct StrongholdWrapper *stronghold_ptr, const char *record_path_c);
+
+/**
+ * # Safety
+ */
+uint8_t *stronghold_sign(struct StrongholdWrapper *stronghold_ptr,
+                         const char *record_path_c,
+                         const unsigned char *data_c,
+                         size_t data_length);
+
+#endif /* STRONGHOLD_NATIVE_H */
@@ -0,0 +1,170 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+// Exercises the C API of the stronghold_native library. Expects the path of a not yet existing snapshot file as
+// first argument.
+
+#include <stdio.h>
+#include <string.h>
+
+#include "stronghold_native.h"
+
+#define CHECK(expr)                                                          \
+  do {                                                                       \
+    if (!(expr)) {                                                           \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #expr); \
+      exit(1);                                                               \
+    }                                                                        \
+  } while (0)
+
+#define CHECK_STATUS(expr, expected)                                                               \
+  do {                                                                                             \
+    StrongholdStatus status = (expr);                                                              \
+    if (status != (expected)) {                                                                    \
+      fprintf(stderr, "%s:%d: %s returned %d (%s)\n", __FILE__, __LINE__, #expr, status,           \
+              stronghold_status_message(status));                                                 \
+      exit(1);                                                                                     \
+    }                                                                                              \
+  } while (0)
+
+#define CHECK_OK(expr) CHECK_STATUS(expr, STRONGHOLD_STATUS_OK)
+
+#define BYTES(s) (const uint8_t *)(s), strlen(s)
+
+static const char *CLIENT_PATH = "client";
+static const char *PASSPHRASE = "passphrase";
+
+static const char *GENERATE_KEY =
+    "{\"GenerateKey\":{\"ty\":\"Ed25519\",\"output\":"
+    "{\"Generic\":{\"vault_path\":[118],\"record_path\":[107]}}}}";
+static const char *PUBLIC_KEY =
+    "{\"PublicKey\":{\"ty\":\"Ed25519\",\"private_key\":"
+    "{\"Generic\":{\"vault_path\":[118],\"record_path\":[107]}}}}";
+static const char *SIGN =
+    "{\"Ed25519Sign\":{\"msg\":[109,115,103],\"private_key\":"
+    "{\"Generic\":{\"vault_path\":[118],\"record_path\":[107]}}}}";
+
+static StrongholdLocation location(const char *vault_path, const char *record_path) {
+  StrongholdLocation location = {
+      .vault_path = (const uint8_t *)vault_path,
+      .vault_path_len = strlen(vault_path),
+      .record_path = (const uint8_t *)record_path,
+      .record_path_len = strlen(record_path),
+      .is_counter = false,
+      .counter = 0,
+  };
+  return location;
+}
+
+static void test_procedures(void) {
+  StrongholdHandle *stronghold = NULL;
+  StrongholdClient *client = NULL;
+  StrongholdBuffer output = {0};
+  bool exists = false;
+
+  CHECK_OK(stronghold_new(&stronghold));
+  CHECK_OK(stronghold_create_client(stronghold, BYTES(CLIENT_PATH), &client));
+
+  StrongholdLocation secret = location("vault", "secret");
+  CHECK_OK(stronghold_client_write_secret(client, &secret, BYTES("secret data")));
+  CHECK_OK(stronghold_client_record_exists(client, &secret, &exists));
+  CHECK(exists);
+  CHECK_OK(stronghold_client_vault_exists(client, BYTES("vault"), &exists));
+  CHECK(exists);
+
+  StrongholdLocation counter = {
+      .vault_path = (const uint8_t *)"vault", .vault_path_len = 5, .is_counter = true, .counter = 3};
+  CHECK_OK(stronghold_client_record_exists(client, &counter, &exists));
+  CHECK(!exists);
+
+  CHECK_OK(stronghold_client_execute(client, BYTES(GENERATE_KEY), &output));
+  CHECK(output.len == 0);
+  stronghold_buffer_free(&output);
+  CHECK_OK(stronghold_client_execute(client, BYTES(PUBLIC_KEY), &output));
+  CHECK(output.len == 32);
+  stronghold_buffer_free(&output);
+  CHECK(output.data == NULL);
+  CHECK_OK(stronghold_client_execute(client, BYTES(SIGN), &output));
+  CHECK(output.len == 64);
+  stronghold_buffer_free(&output);
+
+  CHECK_STATUS(stronghold_client_execute(client, BYTES("{\"NoProcedure\":{}}"), &output),
+               STRONGHOLD_STATUS_INVALID_PROCEDURE);
+  CHECK_STATUS(stronghold_client_execute(client, NULL, 1, &output), STRONGHOLD_STATUS_NULL_POINTER);
+  CHECK_STATUS(stronghold_client_execute(NULL, BYTES(SIGN), &output), STRONGHOLD_STATUS_NULL_POINTER);
+
+  // copy the vault into another one
+  CHECK_OK(stronghold_client_sync_vaults(client, BYTES("vault"), BYTES("copy"), STRONGHOLD_MERGE_POLICY_REPLACE));
+  CHECK_OK(stronghold_client_vault_exists(client, BYTES("copy"), &exists));
+  CHECK(exists);
+
+  CHECK_STATUS(stronghold_commit(stronghold), STRONGHOLD_STATUS_NO_SNAPSHOT);
+  CHECK(strcmp(stronghold_status_message(STRONGHOLD_STATUS_NO_SNAPSHOT), "no snapshot file") == 0);
+
+  stronghold_client_free(client);
+  stronghold_free(stronghold);
+}
+
+static void test_store(void) {
+  StrongholdHandle *stronghold = NULL;
+  StrongholdClient *client = NULL;
+  StrongholdBuffer value = {0};
+  bool contains = false;
+
+  CHECK_OK(stronghold_new(&stronghold));
+  CHECK_OK(stronghold_create_client(stronghold, BYTES(CLIENT_PATH), &client));
+
+  CHECK_OK(stronghold_client_store_insert(client, BYTES("key"), BYTES("value"), 0));
+  CHECK_OK(stronghold_client_store_contains(client, BYTES("key"), &contains));
+  CHECK(contains);
+  CHECK_OK(stronghold_client_store_get(client, BYTES("key"), &value));
+  CHECK(value.len == 5 && memcmp(value.data, "value", 5) == 0);
+  stronghold_buffer_free(&value);
+
+  CHECK_OK(stronghold_client_store_delete(client, BYTES("key")));
+  CHECK_STATUS(stronghold_client_store_delete(client, BYTES("key")), STRONGHOLD_STATUS_NOT_FOUND);
+  CHECK_STATUS(stronghold_client_store_get(client, BYTES("key"), &value), STRONGHOLD_STATUS_NOT_FOUND);
+
+  stronghold_client_free(client);
+  stronghold_free(stronghold);
+}
+
+static void test_snapshot(const char *snapshot_path) {
+  StrongholdHandle *stronghold = NULL;
+  StrongholdClient *client = NULL;
+  StrongholdBuffer public_key = {0};
+  StrongholdBuffer loaded_public_key = {0};
+  uint8_t expected[32];
+
+  CHECK_OK(stronghold_open_snapshot(snapshot_path, BYTES(PASSPHRASE), &stronghold));
+  CHECK_OK(stronghold_create_client(stronghold, BYTES(CLIENT_PATH), &client));
+  CHECK_OK(stronghold_client_execute(client, BYTES(GENERATE_KEY), &public_key));
+  CHECK_OK(stronghold_client_execute(client, BYTES(PUBLIC_KEY), &public_key));
+  memcpy(expected, public_key.data, sizeof(expected));
+  stronghold_buffer_free(&public_key);
+  CHECK_OK(stronghold_write_client(stronghold, BYTES(CLIENT_PATH)));
+  CHECK_OK(stronghold_commit(stronghold));
+  stronghold_client_free(client);
+  stronghold_free(stronghold);
+
+  CHECK_STATUS(stronghold_open_snapshot(snapshot_path, BYTES("wrong"), &stronghold), STRONGHOLD_STATUS_SNAPSHOT);
+
+  CHECK_OK(stronghold_open_snapshot(snapshot_path, BYTES(PASSPHRASE), &stronghold));
+  CHECK_OK(stronghold_load_client(stronghold, BYTES(CLIENT_PATH), &client));
+  stronghold_client_free(client);
+  CHECK_OK(stronghold_get_client(stronghold, BYTES(CLIENT_PATH), &client));
+  CHECK_OK(stronghold_client_execute(client, BYTES(PUBLIC_KEY), &loaded_public_key));
+  CHECK(loaded_public_key.len == 32 && memcmp(expected, loaded_public_key.data, 32) == 0);
+  stronghold_buffer_free(&loaded_public_key);
+  stronghold_client_free(client);
+  stronghold_free(stronghold);
+}
+
+int main(int argc, char **argv) {
+  CHECK(argc == 2);
+  test_procedures();
+  test_store();
+  test_snapshot(argv[1]);
+  printf("ok\n");
+  return 0;
+}
@@ -0,0 +1,61 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compiles the C tests in `tests/c` against the generated header and links them with the cdylib.
+
+#![cfg(unix)]
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Directory of the cdylib, which is built next to the `deps` directory of the test binary.
+fn library_dir() -> PathBuf {
+    let exe = env::current_exe().expect("test binary has a path");
+    exe.parent()
+        .and_then(Path::parent)
+        .expect("test binary is in the deps directory")
+        .to_path_buf()
+}
+
+#[test]
+fn test_c_api() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let library_dir = library_dir();
+    let out_dir = env::temp_dir().join(format!("stronghold_native_c_api_{}", std::process::id()));
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let binary = out_dir.join("api_test");
+    let snapshot = out_dir.join("api_test.snapshot");
+
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".into());
+    let status = Command::new(compiler)
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(crate_dir)
+        .arg(crate_dir.join("tests/c/api_test.c"))
+        .arg("-L")
+        .arg(&library_dir)
+        .arg("-lstronghold_native")
+        .arg("-o")
+        .arg(&binary)
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success(), "compiling the C tests failed");
+
+    let output = Command::new(&binary)
+        .arg(&snapshot)
+        .env("LD_LIBRARY_PATH", &library_dir)
+        .env("DYLD_LIBRARY_PATH", &library_dir)
+        .output()
+        .unwrap();
+    let _ = std::fs::remove_dir_all(&out_dir);
+    assert!(
+        output.status.success(),
+        "C tests failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
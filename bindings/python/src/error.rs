@@ -0,0 +1,21 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use iota_stronghold::{procedures::ProcedureError as ClientProcedureError, ClientError};
+use pyo3::{create_exception, exceptions::PyException, PyErr};
+
+create_exception!(stronghold, StrongholdError, PyException);
+create_exception!(stronghold, ProcedureError, StrongholdError);
+
+/// Converts a [`ClientError`] into a `StrongholdError`, or a `ProcedureError` if a procedure failed.
+pub(crate) fn client_error(e: ClientError) -> PyErr {
+    match e {
+        ClientError::Procedure(e) => procedure_error(e),
+        e => StrongholdError::new_err(e.to_string()),
+    }
+}
+
+/// Converts a failed procedure into a `ProcedureError`.
+pub(crate) fn procedure_error(e: ClientProcedureError) -> PyErr {
+    ProcedureError::new_err(e.to_string())
+}
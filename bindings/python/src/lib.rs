@@ -0,0 +1,37 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Python bindings for the Stronghold client.
+//!
+//! Secrets never leave the vaults of a client. Values are only returned to Python where the output of a procedure is
+//! public by design, e.g. public keys, signatures, chain codes or ciphertexts.
+
+mod error;
+mod procedures;
+mod types;
+
+use pyo3::prelude::*;
+
+#[pymodule]
+fn stronghold(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add("StrongholdError", py.get_type::<error::StrongholdError>())?;
+    m.add("ProcedureError", py.get_type::<error::ProcedureError>())?;
+    m.add("HARDENED", procedures::HARDENED)?;
+
+    m.add_class::<types::PyKeyProvider>()?;
+    m.add_class::<types::PyLocation>()?;
+    m.add_class::<types::PyStronghold>()?;
+    m.add_class::<types::PyClient>()?;
+    m.add_class::<types::PyClientVault>()?;
+    m.add_class::<types::PyStore>()?;
+
+    m.add_class::<procedures::PyProcedure>()?;
+    m.add_class::<procedures::PyKeyType>()?;
+    m.add_class::<procedures::PySha2Hash>()?;
+    m.add_class::<procedures::PyMnemonicLanguage>()?;
+    m.add_class::<procedures::PyAeadCipher>()?;
+    m.add_class::<procedures::PyHpkeAead>()?;
+    m.add_class::<procedures::PyOtpHash>()?;
+    m.add_class::<procedures::PySecp256k1EcdsaPublicKeyEncoding>()?;
+    Ok(())
+}
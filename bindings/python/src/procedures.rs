@@ -0,0 +1,998 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::num::NonZeroU32;
+
+use iota_stronghold::procedures::{
+    AeadCipher, AeadDecrypt, AeadEncrypt, AeadNonce, AesKeyWrapCipher, AesKeyWrapDecrypt, AesKeyWrapEncrypt,
+    Argon2Derive, Argon2Params, Argon2Password, Argon2Verify, BIP39Generate, BIP39Recover, Bip32Derive, Chain,
+    ConcatKdf, ConcatSecret, CopyRecord, Ed25519Sign, Ed25519SignBatch, Ed25519SignPrehashed, Ed25519Signatures,
+    Ed25519Verify, Ed25519VerifyPrehashed, EncryptedShares, ExportRecord, GarbageCollect, GenerateKey, Hkdf, Hmac,
+    HmacVerify, HotpGenerate, HpkeAead, HpkeOpen, HpkeSeal, ImportRecord, KeyType, MnemonicLanguage, OtpHash, OtpKind,
+    Pbkdf2Hmac, PepperedHash, PepperedHashVerify, ProcedureOutput, PublicKey, RevokeData, Secp256k1EcdsaPublicKey,
+    Secp256k1EcdsaPublicKeyEncoding, Secp256k1EcdsaSign, Secp256k1EcdsaVerify, Sha2Hash, ShamirCombine, ShamirSplit,
+    ShareEncoding, Slip10Derive, Slip10DeriveInput, Slip10Generate, StrongholdProcedure, TotpGenerate, WriteVault,
+    X25519DiffieHellman, HPKE_ENC_LENGTH, OTP_DEFAULT_DIGITS, TOTP_DEFAULT_PERIOD,
+};
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyBool, PyBytes, PyDict, PyList, PyString},
+};
+
+use crate::{error::ProcedureError, types::PyLocation};
+
+/// Flag of a hardened index in a SLIP-10 or BIP-32 chain.
+pub(crate) const HARDENED: u32 = 0x8000_0000;
+
+/// Type of an asymmetric key.
+#[pyclass(module = "stronghold", name = "KeyType")]
+#[derive(Clone, Copy)]
+pub enum PyKeyType {
+    Ed25519,
+    X25519,
+    Secp256k1Ecdsa,
+}
+
+impl From<PyKeyType> for KeyType {
+    fn from(value: PyKeyType) -> Self {
+        match value {
+            PyKeyType::Ed25519 => KeyType::Ed25519,
+            PyKeyType::X25519 => KeyType::X25519,
+            PyKeyType::Secp256k1Ecdsa => KeyType::Secp256k1Ecdsa,
+        }
+    }
+}
+
+/// Hash function of a HMAC or key derivation.
+#[pyclass(module = "stronghold", name = "Sha2Hash")]
+#[derive(Clone, Copy)]
+pub enum PySha2Hash {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl From<PySha2Hash> for Sha2Hash {
+    fn from(value: PySha2Hash) -> Self {
+        match value {
+            PySha2Hash::Sha256 => Sha2Hash::Sha256,
+            PySha2Hash::Sha384 => Sha2Hash::Sha384,
+            PySha2Hash::Sha512 => Sha2Hash::Sha512,
+        }
+    }
+}
+
+/// Language of a BIP-39 mnemonic.
+#[pyclass(module = "stronghold", name = "MnemonicLanguage")]
+#[derive(Clone, Copy)]
+pub enum PyMnemonicLanguage {
+    English,
+    Japanese,
+}
+
+impl From<PyMnemonicLanguage> for MnemonicLanguage {
+    fn from(value: PyMnemonicLanguage) -> Self {
+        match value {
+            PyMnemonicLanguage::English => MnemonicLanguage::English,
+            PyMnemonicLanguage::Japanese => MnemonicLanguage::Japanese,
+        }
+    }
+}
+
+/// Cipher of an AEAD encryption.
+#[pyclass(module = "stronghold", name = "AeadCipher")]
+#[derive(Clone, Copy)]
+pub enum PyAeadCipher {
+    Aes256Gcm,
+    XChaCha20Poly1305,
+    Aes256GcmSiv,
+    ChaCha20Poly1305,
+}
+
+impl From<PyAeadCipher> for AeadCipher {
+    fn from(value: PyAeadCipher) -> Self {
+        match value {
+            PyAeadCipher::Aes256Gcm => AeadCipher::Aes256Gcm,
+            PyAeadCipher::XChaCha20Poly1305 => AeadCipher::XChaCha20Poly1305,
+            PyAeadCipher::Aes256GcmSiv => AeadCipher::Aes256GcmSiv,
+            PyAeadCipher::ChaCha20Poly1305 => AeadCipher::ChaCha20Poly1305,
+        }
+    }
+}
+
+/// AEAD of an HPKE cipher suite.
+#[pyclass(module = "stronghold", name = "HpkeAead")]
+#[derive(Clone, Copy)]
+pub enum PyHpkeAead {
+    Aes128Gcm,
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+impl From<PyHpkeAead> for HpkeAead {
+    fn from(value: PyHpkeAead) -> Self {
+        match value {
+            PyHpkeAead::Aes128Gcm => HpkeAead::Aes128Gcm,
+            PyHpkeAead::Aes256Gcm => HpkeAead::Aes256Gcm,
+            PyHpkeAead::ChaCha20Poly1305 => HpkeAead::ChaCha20Poly1305,
+        }
+    }
+}
+
+/// Hash function of the HMAC of a one-time password.
+#[pyclass(module = "stronghold", name = "OtpHash")]
+#[derive(Clone, Copy)]
+pub enum PyOtpHash {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl From<PyOtpHash> for OtpHash {
+    fn from(value: PyOtpHash) -> Self {
+        match value {
+            PyOtpHash::Sha1 => OtpHash::Sha1,
+            PyOtpHash::Sha256 => OtpHash::Sha256,
+            PyOtpHash::Sha512 => OtpHash::Sha512,
+        }
+    }
+}
+
+impl From<OtpHash> for PyOtpHash {
+    fn from(value: OtpHash) -> Self {
+        match value {
+            OtpHash::Sha1 => PyOtpHash::Sha1,
+            OtpHash::Sha256 => PyOtpHash::Sha256,
+            OtpHash::Sha512 => PyOtpHash::Sha512,
+        }
+    }
+}
+
+/// Encoding of a secp256k1 public key.
+#[pyclass(module = "stronghold", name = "Secp256k1EcdsaPublicKeyEncoding")]
+#[derive(Clone, Copy)]
+pub enum PySecp256k1EcdsaPublicKeyEncoding {
+    Compressed,
+    Uncompressed,
+    EvmAddress,
+}
+
+impl From<PySecp256k1EcdsaPublicKeyEncoding> for Secp256k1EcdsaPublicKeyEncoding {
+    fn from(value: PySecp256k1EcdsaPublicKeyEncoding) -> Self {
+        match value {
+            PySecp256k1EcdsaPublicKeyEncoding::Compressed => Secp256k1EcdsaPublicKeyEncoding::Compressed,
+            PySecp256k1EcdsaPublicKeyEncoding::Uncompressed => Secp256k1EcdsaPublicKeyEncoding::Uncompressed,
+            PySecp256k1EcdsaPublicKeyEncoding::EvmAddress => Secp256k1EcdsaPublicKeyEncoding::EvmAddress,
+        }
+    }
+}
+
+fn fixed_bytes<const N: usize>(name: &str, bytes: &[u8]) -> PyResult<[u8; N]> {
+    bytes
+        .try_into()
+        .map_err(|_| PyValueError::new_err(format!("{} must be {} bytes long", name, N)))
+}
+
+fn derive_input(seed: Option<PyLocation>, key: Option<PyLocation>) -> PyResult<Slip10DeriveInput> {
+    match (seed, key) {
+        (Some(seed), None) => Ok(Slip10DeriveInput::Seed(seed.inner)),
+        (None, Some(key)) => Ok(Slip10DeriveInput::Key(key.inner)),
+        _ => Err(PyValueError::new_err("exactly one of seed and key must be given")),
+    }
+}
+
+fn share_encoding(slip39_passphrase: Option<&[u8]>, iteration_exponent: u8) -> ShareEncoding {
+    match slip39_passphrase {
+        Some(passphrase) => ShareEncoding::Slip39 {
+            passphrase: passphrase.to_vec(),
+            iteration_exponent,
+        },
+        None => ShareEncoding::Raw,
+    }
+}
+
+fn locations(locations: Vec<PyLocation>) -> Vec<iota_stronghold::Location> {
+    locations.into_iter().map(|location| location.inner).collect()
+}
+
+/// A procedure that can be executed by a client.
+///
+/// Procedures are created with the static methods of this class, e.g. `Procedure.generate_key(...)`. Chains of
+/// SLIP-10 and BIP-32 derivations are lists of indices, hardened indices have the `HARDENED` flag set.
+#[pyclass(module = "stronghold", name = "Procedure")]
+#[derive(Clone)]
+pub struct PyProcedure {
+    pub(crate) inner: StrongholdProcedure,
+}
+
+impl<P: Into<StrongholdProcedure>> From<P> for PyProcedure {
+    fn from(procedure: P) -> Self {
+        PyProcedure {
+            inner: procedure.into(),
+        }
+    }
+}
+
+#[pymethods]
+impl PyProcedure {
+    #[staticmethod]
+    fn write_vault(data: &[u8], location: PyLocation) -> Self {
+        WriteVault {
+            data: data.to_vec(),
+            location: location.inner,
+        }
+        .into()
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (location, should_gc = true))]
+    fn revoke_data(location: PyLocation, should_gc: bool) -> Self {
+        RevokeData {
+            location: location.inner,
+            should_gc,
+        }
+        .into()
+    }
+
+    #[staticmethod]
+    fn garbage_collect(vault_path: &[u8]) -> Self {
+        GarbageCollect {
+            vault_path: vault_path.to_vec(),
+        }
+        .into()
+    }
+
+    #[staticmethod]
+    fn copy_record(source: PyLocation, target: PyLocation) -> Self {
+        CopyRecord {
+            source: source.inner,
+            target: target.inner,
+        }
+        .into()
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (output, size_bytes = None))]
+    fn slip10_generate(output: PyLocation, size_bytes: Option<usize>) -> Self {
+        Slip10Generate {
+            size_bytes,
+            output: output.inner,
+        }
+        .into()
+    }
+
+    /// Returns the chain code of the derived key.
+    #[staticmethod]
+    #[pyo3(signature = (chain, output, *, seed = None, key = None))]
+    fn slip10_derive(
+        chain: Vec<u32>,
+        output: PyLocation,
+        seed: Option<PyLocation>,
+        key: Option<PyLocation>,
+    ) -> PyResult<Self> {
+        Ok(Slip10Derive {
+            chain: Chain::from_u32(chain),
+            input: derive_input(seed, key)?,
+            output: output.inner,
+        }
+        .into())
+    }
+
+    /// Returns the extended public key of the derived key.
+    #[staticmethod]
+    #[pyo3(signature = (chain, output, *, seed = None, key = None))]
+    fn bip32_derive(
+        chain: Vec<u32>,
+        output: PyLocation,
+        seed: Option<PyLocation>,
+        key: Option<PyLocation>,
+    ) -> PyResult<Self> {
+        Ok(Bip32Derive {
+            chain: Chain::from_u32(chain),
+            input: derive_input(seed, key)?,
+            output: output.inner,
+        }
+        .into())
+    }
+
+    /// Returns the mnemonic of the generated seed, which is intended to be written down as backup.
+    #[staticmethod]
+    #[pyo3(signature = (output, language = PyMnemonicLanguage::English, passphrase = None))]
+    fn bip39_generate(output: PyLocation, language: PyMnemonicLanguage, passphrase: Option<String>) -> Self {
+        BIP39Generate {
+            passphrase,
+            language: language.into(),
+            output: output.inner,
+        }
+        .into()
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (mnemonic, output, passphrase = None))]
+    fn bip39_recover(mnemonic: String, output: PyLocation, passphrase: Option<String>) -> Self {
+        BIP39Recover {
+            passphrase,
+            mnemonic,
+            output: output.inner,
+        }
+        .into()
+    }
+
+    #[staticmethod]
+    fn generate_key(ty: PyKeyType, output: PyLocation) -> Self {
+        GenerateKey {
+            ty: ty.into(),
+            output: output.inner,
+        }
+        .into()
+    }
+
+    #[staticmethod]
+    fn public_key(ty: PyKeyType, private_key: PyLocation) -> Self {
+        PublicKey {
+            ty: ty.into(),
+            private_key: private_key.inner,
+        }
+        .into()
+    }
+
+    #[staticmethod]
+    fn ed25519_sign(msg: &[u8], private_key: PyLocation) -> Self {
+        Ed25519Sign {
+            msg: msg.to_vec(),
+            private_key: private_key.inner,
+        }
+        .into()
+    }
+
+    /// Returns the signatures of `msgs`, in the same order.
+    #[staticmethod]
+    fn ed25519_sign_batch(msgs: Vec<Vec<u8>>, private_key: PyLocation) -> Self {
+        Ed25519SignBatch {
+            msgs,
+            private_key: private_key.inner,
+        }
+        .into()
+    }
+
+    /// Returns the Ed25519ph signature of the message with the SHA-512 digest `prehash`.
+    #[staticmethod]
+    #[pyo3(signature = (prehash, private_key, context = Vec::new()))]
+    fn ed25519_sign_prehashed(prehash: &[u8], private_key: PyLocation, context: Vec<u8>) -> Self {
+        Ed25519SignPrehashed {
+            prehash: prehash.to_vec(),
+            context,
+            private_key: private_key.inner,
+        }
+        .into()
+    }
+
+    /// Returns `True` if `signature` is a valid signature of `msg`.
+    #[staticmethod]
+    fn ed25519_verify(msg: &[u8], signature: &[u8], public_key: &[u8]) -> PyResult<Self> {
+        Ok(Ed25519Verify {
+            msg: msg.to_vec(),
+            signature: signature.to_vec(),
+            public_key: fixed_bytes("public_key", public_key)?,
+        }
+        .into())
+    }
+
+    /// Returns `True` if `signature` is a valid Ed25519ph signature of the message with the SHA-512 digest `prehash`.
+    #[staticmethod]
+    #[pyo3(signature = (prehash, signature, public_key, context = Vec::new()))]
+    fn ed25519_verify_prehashed(
+        prehash: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+        context: Vec<u8>,
+    ) -> PyResult<Self> {
+        Ok(Ed25519VerifyPrehashed {
+            prehash: prehash.to_vec(),
+            context,
+            signature: signature.to_vec(),
+            public_key: fixed_bytes("public_key", public_key)?,
+        }
+        .into())
+    }
+
+    #[staticmethod]
+    fn secp256k1_ecdsa_public_key(encoding: PySecp256k1EcdsaPublicKeyEncoding, private_key: PyLocation) -> Self {
+        Secp256k1EcdsaPublicKey {
+            encoding: encoding.into(),
+            private_key: private_key.inner,
+        }
+        .into()
+    }
+
+    #[staticmethod]
+    fn secp256k1_ecdsa_sign(prehash: &[u8], private_key: PyLocation) -> PyResult<Self> {
+        Ok(Secp256k1EcdsaSign {
+            prehash: fixed_bytes("prehash", prehash)?,
+            private_key: private_key.inner,
+        }
+        .into())
+    }
+
+    /// Returns `True` if `signature` is a valid signature of `prehash` by `public_key` in the given `encoding`.
+    #[staticmethod]
+    fn secp256k1_ecdsa_verify(
+        prehash: &[u8],
+        signature: &[u8],
+        encoding: PySecp256k1EcdsaPublicKeyEncoding,
+        public_key: &[u8],
+    ) -> PyResult<Self> {
+        Ok(Secp256k1EcdsaVerify {
+            prehash: fixed_bytes("prehash", prehash)?,
+            signature: signature.to_vec(),
+            encoding: encoding.into(),
+            public_key: public_key.to_vec(),
+        }
+        .into())
+    }
+
+    #[staticmethod]
+    fn x25519_diffie_hellman(public_key: &[u8], private_key: PyLocation, shared_key: PyLocation) -> PyResult<Self> {
+        Ok(X25519DiffieHellman {
+            public_key: fixed_bytes("public_key", public_key)?,
+            private_key: private_key.inner,
+            shared_key: shared_key.inner,
+        }
+        .into())
+    }
+
+    #[staticmethod]
+    fn hmac(hash_type: PySha2Hash, msg: &[u8], key: PyLocation) -> Self {
+        Hmac {
+            hash_type: hash_type.into(),
+            msg: msg.to_vec(),
+            key: key.inner,
+        }
+        .into()
+    }
+
+    /// Returns `True` if `mac` is the HMAC of `msg` with the key at `key`.
+    #[staticmethod]
+    fn hmac_verify(hash_type: PySha2Hash, msg: &[u8], mac: &[u8], key: PyLocation) -> Self {
+        HmacVerify {
+            hash_type: hash_type.into(),
+            msg: msg.to_vec(),
+            mac: mac.to_vec(),
+            key: key.inner,
+        }
+        .into()
+    }
+
+    /// Returns the HOTP (RFC 4226) for `counter` as a string of `digits` digits.
+    #[staticmethod]
+    #[pyo3(signature = (key, counter, *, hash = PyOtpHash::Sha1, digits = OTP_DEFAULT_DIGITS))]
+    fn hotp_generate(key: PyLocation, counter: u64, hash: PyOtpHash, digits: u32) -> Self {
+        HotpGenerate {
+            hash: hash.into(),
+            digits,
+            counter,
+            key: key.inner,
+        }
+        .into()
+    }
+
+    /// Returns the TOTP (RFC 6238) for the Unix time `timestamp` in seconds as a string of `digits` digits.
+    #[staticmethod]
+    #[pyo3(signature = (key, timestamp, *, hash = PyOtpHash::Sha1, digits = OTP_DEFAULT_DIGITS, period = TOTP_DEFAULT_PERIOD))]
+    fn totp_generate(key: PyLocation, timestamp: u64, hash: PyOtpHash, digits: u32, period: u64) -> Self {
+        TotpGenerate {
+            hash: hash.into(),
+            digits,
+            period,
+            timestamp,
+            key: key.inner,
+        }
+        .into()
+    }
+
+    /// Parses an `otpauth://` URI and returns the procedure that writes its shared secret to `location`, together
+    /// with a dict of the remaining parameters: `type`, `label`, `issuer`, `hash`, `digits` and either `counter` or
+    /// `period`.
+    #[staticmethod]
+    fn write_otpauth_uri(py: Python<'_>, uri: &str, location: PyLocation) -> PyResult<(Self, PyObject)> {
+        let (write, parameters) =
+            WriteVault::from_otpauth_uri(uri, location.inner).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let dict = PyDict::new(py);
+        match parameters.kind {
+            OtpKind::Hotp { counter } => {
+                dict.set_item("type", "hotp")?;
+                dict.set_item("counter", counter)?;
+            }
+            OtpKind::Totp { period } => {
+                dict.set_item("type", "totp")?;
+                dict.set_item("period", period)?;
+            }
+        }
+        dict.set_item("label", parameters.label)?;
+        dict.set_item("issuer", parameters.issuer)?;
+        dict.set_item("hash", PyOtpHash::from(parameters.hash).into_py(py))?;
+        dict.set_item("digits", parameters.digits)?;
+        Ok((write.into(), dict.into()))
+    }
+
+    #[staticmethod]
+    fn hkdf(hash_type: PySha2Hash, salt: &[u8], label: &[u8], ikm: PyLocation, okm: PyLocation) -> Self {
+        Hkdf {
+            hash_type: hash_type.into(),
+            salt: salt.to_vec(),
+            label: label.to_vec(),
+            ikm: ikm.inner,
+            okm: okm.inner,
+        }
+        .into()
+    }
+
+    #[staticmethod]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (
+        hash,
+        algorithm_id,
+        shared_secret,
+        key_len,
+        output,
+        apu = Vec::new(),
+        apv = Vec::new(),
+        pub_info = Vec::new(),
+        priv_info = Vec::new()
+    ))]
+    fn concat_kdf(
+        hash: PySha2Hash,
+        algorithm_id: String,
+        shared_secret: PyLocation,
+        key_len: usize,
+        output: PyLocation,
+        apu: Vec<u8>,
+        apv: Vec<u8>,
+        pub_info: Vec<u8>,
+        priv_info: Vec<u8>,
+    ) -> Self {
+        ConcatKdf {
+            hash: hash.into(),
+            algorithm_id,
+            shared_secret: shared_secret.inner,
+            key_len,
+            apu,
+            apv,
+            pub_info,
+            priv_info,
+            output: output.inner,
+        }
+        .into()
+    }
+
+    /// Returns the wrapped key.
+    #[staticmethod]
+    fn aes_key_wrap_encrypt(encryption_key: PyLocation, wrap_key: PyLocation) -> Self {
+        AesKeyWrapEncrypt {
+            cipher: AesKeyWrapCipher::Aes256,
+            encryption_key: encryption_key.inner,
+            wrap_key: wrap_key.inner,
+        }
+        .into()
+    }
+
+    #[staticmethod]
+    fn aes_key_wrap_decrypt(decryption_key: PyLocation, wrapped_key: &[u8], output: PyLocation) -> Self {
+        AesKeyWrapDecrypt {
+            cipher: AesKeyWrapCipher::Aes256,
+            decryption_key: decryption_key.inner,
+            wrapped_key: wrapped_key.to_vec(),
+            output: output.inner,
+        }
+        .into()
+    }
+
+    #[staticmethod]
+    fn pbkdf2_hmac(
+        hash_type: PySha2Hash,
+        password: &[u8],
+        salt: &[u8],
+        count: u32,
+        output: PyLocation,
+    ) -> PyResult<Self> {
+        let count = NonZeroU32::new(count).ok_or_else(|| PyValueError::new_err("count must not be zero"))?;
+        Ok(Pbkdf2Hmac {
+            hash_type: hash_type.into(),
+            password: password.to_vec(),
+            salt: salt.to_vec(),
+            count,
+            output: output.inner,
+        }
+        .into())
+    }
+
+    /// Derives a key of `key_length` bytes with Argon2id from either `password` or the password stored at
+    /// `password_location`. The cost parameters default to the second recommended option of RFC 9106.
+    #[staticmethod]
+    #[pyo3(signature = (salt, output, *, password = None, password_location = None, memory_cost = None, time_cost = None, parallelism = None, key_length = 32))]
+    #[allow(clippy::too_many_arguments)]
+    fn argon2_derive(
+        salt: &[u8],
+        output: PyLocation,
+        password: Option<&[u8]>,
+        password_location: Option<PyLocation>,
+        memory_cost: Option<u32>,
+        time_cost: Option<u32>,
+        parallelism: Option<u32>,
+        key_length: u32,
+    ) -> PyResult<Self> {
+        Ok(Argon2Derive {
+            password: argon2_password(password, password_location)?,
+            salt: salt.to_vec(),
+            params: argon2_params(memory_cost, time_cost, parallelism),
+            key_length,
+            output: output.inner,
+        }
+        .into())
+    }
+
+    /// Returns `True` if either `password` or the password stored at `password_location` matches the Argon2 PHC
+    /// string `hash`.
+    #[staticmethod]
+    #[pyo3(signature = (hash, *, password = None, password_location = None))]
+    fn argon2_verify(hash: String, password: Option<&[u8]>, password_location: Option<PyLocation>) -> PyResult<Self> {
+        Ok(Argon2Verify {
+            password: argon2_password(password, password_location)?,
+            hash,
+        }
+        .into())
+    }
+
+    /// Returns `HMAC(pepper, Argon2id(password, salt))` with the pepper at `pepper`, which must be stored in a
+    /// non-exportable record.
+    #[staticmethod]
+    #[pyo3(signature = (hash_type, password, salt, pepper, *, memory_cost = None, time_cost = None, parallelism = None))]
+    fn peppered_hash(
+        hash_type: PySha2Hash,
+        password: &[u8],
+        salt: &[u8],
+        pepper: PyLocation,
+        memory_cost: Option<u32>,
+        time_cost: Option<u32>,
+        parallelism: Option<u32>,
+    ) -> Self {
+        PepperedHash {
+            hash_type: hash_type.into(),
+            password: password.to_vec(),
+            salt: salt.to_vec(),
+            params: argon2_params(memory_cost, time_cost, parallelism),
+            pepper: pepper.inner,
+        }
+        .into()
+    }
+
+    /// Returns `True` if `hash` is the peppered hash of `password`.
+    #[staticmethod]
+    #[pyo3(signature = (hash_type, password, salt, hash, pepper, *, memory_cost = None, time_cost = None, parallelism = None))]
+    #[allow(clippy::too_many_arguments)]
+    fn peppered_hash_verify(
+        hash_type: PySha2Hash,
+        password: &[u8],
+        salt: &[u8],
+        hash: &[u8],
+        pepper: PyLocation,
+        memory_cost: Option<u32>,
+        time_cost: Option<u32>,
+        parallelism: Option<u32>,
+    ) -> Self {
+        PepperedHashVerify {
+            hash_type: hash_type.into(),
+            password: password.to_vec(),
+            salt: salt.to_vec(),
+            params: argon2_params(memory_cost, time_cost, parallelism),
+            pepper: pepper.inner,
+            hash: hash.to_vec(),
+        }
+        .into()
+    }
+
+    /// Returns `tag || ciphertext`. If `nonce` is `None`, the nonce is generated randomly or derived from `counter`
+    /// or, if `stored_counter` is set, from the key's nonce counter, and `nonce || tag || ciphertext` is returned. A
+    /// key used with counter nonces must be stored in a non-exportable record.
+    #[staticmethod]
+    #[pyo3(signature = (cipher, associated_data, plaintext, nonce, key, *, counter = None, stored_counter = false))]
+    fn aead_encrypt(
+        cipher: PyAeadCipher,
+        associated_data: &[u8],
+        plaintext: &[u8],
+        nonce: Option<&[u8]>,
+        key: PyLocation,
+        counter: Option<u64>,
+        stored_counter: bool,
+    ) -> PyResult<Self> {
+        let nonce = match (nonce, counter, stored_counter) {
+            (Some(nonce), None, false) => AeadNonce::Explicit(nonce.to_vec()),
+            (None, Some(counter), false) => AeadNonce::Counter(counter),
+            (None, None, true) => AeadNonce::StoredCounter,
+            (None, None, false) => AeadNonce::Random,
+            _ => {
+                return Err(PyValueError::new_err(
+                    "nonce, counter and stored_counter are mutually exclusive",
+                ))
+            }
+        };
+        Ok(AeadEncrypt {
+            cipher: cipher.into(),
+            associated_data: associated_data.to_vec(),
+            plaintext: plaintext.to_vec(),
+            nonce,
+            key: key.inner,
+        }
+        .into())
+    }
+
+    /// Returns the plaintext. The procedure is intended for data that has been encrypted by the caller itself.
+    #[staticmethod]
+    fn aead_decrypt(
+        cipher: PyAeadCipher,
+        associated_data: &[u8],
+        ciphertext: &[u8],
+        tag: &[u8],
+        nonce: &[u8],
+        key: PyLocation,
+    ) -> Self {
+        AeadDecrypt {
+            cipher: cipher.into(),
+            associated_data: associated_data.to_vec(),
+            ciphertext: ciphertext.to_vec(),
+            tag: tag.to_vec(),
+            nonce: nonce.to_vec(),
+            key: key.inner,
+        }
+        .into()
+    }
+
+    #[staticmethod]
+    fn concat_secret(location_a: PyLocation, location_b: PyLocation, output_location: PyLocation) -> Self {
+        ConcatSecret {
+            location_a: location_a.inner,
+            location_b: location_b.inner,
+            output_location: output_location.inner,
+        }
+        .into()
+    }
+
+    /// Returns the shares encrypted to `recipients`. If `slip39_passphrase` is given, the shares are SLIP-39
+    /// mnemonics.
+    #[staticmethod]
+    #[pyo3(signature = (secret, threshold, outputs, recipients = Vec::new(), slip39_passphrase = None, iteration_exponent = 0))]
+    fn shamir_split(
+        secret: PyLocation,
+        threshold: u8,
+        outputs: Vec<PyLocation>,
+        recipients: Vec<&[u8]>,
+        slip39_passphrase: Option<&[u8]>,
+        iteration_exponent: u8,
+    ) -> PyResult<Self> {
+        let recipients = recipients
+            .into_iter()
+            .map(|recipient| fixed_bytes("recipient", recipient))
+            .collect::<PyResult<_>>()?;
+        Ok(ShamirSplit {
+            secret: secret.inner,
+            threshold,
+            outputs: locations(outputs),
+            recipients,
+            encoding: share_encoding(slip39_passphrase, iteration_exponent),
+        }
+        .into())
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (shares, output, slip39_passphrase = None))]
+    fn shamir_combine(shares: Vec<PyLocation>, output: PyLocation, slip39_passphrase: Option<&[u8]>) -> Self {
+        ShamirCombine {
+            shares: locations(shares),
+            encoding: share_encoding(slip39_passphrase, 0),
+            output: output.inner,
+        }
+        .into()
+    }
+
+    /// Returns the envelope of the record, encrypted to `recipient`.
+    #[staticmethod]
+    fn export_record(record: PyLocation, sender: PyLocation, recipient: &[u8]) -> PyResult<Self> {
+        Ok(ExportRecord {
+            record: record.inner,
+            sender: sender.inner,
+            recipient: fixed_bytes("recipient", recipient)?,
+        }
+        .into())
+    }
+
+    #[staticmethod]
+    fn import_record(envelope: &[u8], recipient: PyLocation, sender: &[u8], output: PyLocation) -> PyResult<Self> {
+        Ok(ImportRecord {
+            envelope: envelope.to_vec(),
+            recipient: recipient.inner,
+            sender: fixed_bytes("sender", sender)?,
+            output: output.inner,
+        }
+        .into())
+    }
+
+    /// Returns `enc || ciphertext`. The message is authenticated by the X25519 key at `sender`, if it is set.
+    #[staticmethod]
+    #[pyo3(signature = (aead, recipient, plaintext, *, sender = None, info = Vec::new(), aad = Vec::new()))]
+    fn hpke_seal(
+        aead: PyHpkeAead,
+        recipient: &[u8],
+        plaintext: Vec<u8>,
+        sender: Option<PyLocation>,
+        info: Vec<u8>,
+        aad: Vec<u8>,
+    ) -> PyResult<Self> {
+        Ok(HpkeSeal {
+            aead: aead.into(),
+            recipient: fixed_bytes("recipient", recipient)?,
+            sender: sender.map(|sender| sender.inner),
+            info,
+            aad,
+            plaintext,
+        }
+        .into())
+    }
+
+    /// Returns the plaintext of `enc || ciphertext`, as returned by `hpke_seal` or another HPKE implementation.
+    #[staticmethod]
+    #[pyo3(signature = (aead, recipient, sealed, *, sender = None, info = Vec::new(), aad = Vec::new()))]
+    fn hpke_open(
+        aead: PyHpkeAead,
+        recipient: PyLocation,
+        sealed: &[u8],
+        sender: Option<&[u8]>,
+        info: Vec<u8>,
+        aad: Vec<u8>,
+    ) -> PyResult<Self> {
+        if sealed.len() < HPKE_ENC_LENGTH {
+            return Err(PyValueError::new_err(format!(
+                "sealed must be at least {} bytes long",
+                HPKE_ENC_LENGTH
+            )));
+        }
+        let (enc, ciphertext) = sealed.split_at(HPKE_ENC_LENGTH);
+        Ok(HpkeOpen {
+            aead: aead.into(),
+            recipient: recipient.inner,
+            sender: sender.map(|sender| fixed_bytes("sender", sender)).transpose()?,
+            enc: fixed_bytes("enc", enc)?,
+            info,
+            aad,
+            ciphertext: ciphertext.to_vec(),
+        }
+        .into())
+    }
+
+    fn __repr__(&self) -> String {
+        let name = match &self.inner {
+            StrongholdProcedure::WriteVault(_) => "WriteVault",
+            StrongholdProcedure::RevokeData(_) => "RevokeData",
+            StrongholdProcedure::GarbageCollect(_) => "GarbageCollect",
+            StrongholdProcedure::CopyRecord(_) => "CopyRecord",
+            StrongholdProcedure::Slip10Generate(_) => "Slip10Generate",
+            StrongholdProcedure::Slip10Derive(_) => "Slip10Derive",
+            StrongholdProcedure::Bip32Derive(_) => "Bip32Derive",
+            StrongholdProcedure::BIP39Generate(_) => "BIP39Generate",
+            StrongholdProcedure::BIP39Recover(_) => "BIP39Recover",
+            StrongholdProcedure::PublicKey(_) => "PublicKey",
+            StrongholdProcedure::GenerateKey(_) => "GenerateKey",
+            StrongholdProcedure::Ed25519Sign(_) => "Ed25519Sign",
+            StrongholdProcedure::Ed25519SignBatch(_) => "Ed25519SignBatch",
+            StrongholdProcedure::Ed25519SignPrehashed(_) => "Ed25519SignPrehashed",
+            StrongholdProcedure::Ed25519Verify(_) => "Ed25519Verify",
+            StrongholdProcedure::Ed25519VerifyPrehashed(_) => "Ed25519VerifyPrehashed",
+            StrongholdProcedure::Secp256k1EcdsaPublicKey(_) => "Secp256k1EcdsaPublicKey",
+            StrongholdProcedure::Secp256k1EcdsaSign(_) => "Secp256k1EcdsaSign",
+            StrongholdProcedure::Secp256k1EcdsaVerify(_) => "Secp256k1EcdsaVerify",
+            StrongholdProcedure::X25519DiffieHellman(_) => "X25519DiffieHellman",
+            StrongholdProcedure::Hmac(_) => "Hmac",
+            StrongholdProcedure::HmacVerify(_) => "HmacVerify",
+            StrongholdProcedure::HotpGenerate(_) => "HotpGenerate",
+            StrongholdProcedure::TotpGenerate(_) => "TotpGenerate",
+            StrongholdProcedure::Hkdf(_) => "Hkdf",
+            StrongholdProcedure::ConcatKdf(_) => "ConcatKdf",
+            StrongholdProcedure::AesKeyWrapEncrypt(_) => "AesKeyWrapEncrypt",
+            StrongholdProcedure::AesKeyWrapDecrypt(_) => "AesKeyWrapDecrypt",
+            StrongholdProcedure::Pbkdf2Hmac(_) => "Pbkdf2Hmac",
+            StrongholdProcedure::Argon2Derive(_) => "Argon2Derive",
+            StrongholdProcedure::Argon2Verify(_) => "Argon2Verify",
+            StrongholdProcedure::PepperedHash(_) => "PepperedHash",
+            StrongholdProcedure::PepperedHashVerify(_) => "PepperedHashVerify",
+            StrongholdProcedure::AeadEncrypt(_) => "AeadEncrypt",
+            StrongholdProcedure::AeadDecrypt(_) => "AeadDecrypt",
+            StrongholdProcedure::ConcatSecret(_) => "ConcatSecret",
+            StrongholdProcedure::ShamirSplit(_) => "ShamirSplit",
+            StrongholdProcedure::ShamirCombine(_) => "ShamirCombine",
+            StrongholdProcedure::ExportRecord(_) => "ExportRecord",
+            StrongholdProcedure::ImportRecord(_) => "ImportRecord",
+            StrongholdProcedure::HpkeSeal(_) => "HpkeSeal",
+            StrongholdProcedure::HpkeOpen(_) => "HpkeOpen",
+            #[allow(unreachable_patterns)]
+            _ => "Procedure",
+        };
+        format!("Procedure.{}", name)
+    }
+}
+
+/// Converts the output of `procedure` into its Python value. Procedures that only write into the vault return
+/// `None`, so that secrets don't end up as Python objects.
+/// Returns the password of an Argon2 procedure, which is either supplied or stored at a location.
+fn argon2_password(password: Option<&[u8]>, password_location: Option<PyLocation>) -> PyResult<Argon2Password> {
+    match (password, password_location) {
+        (Some(password), None) => Ok(Argon2Password::Supplied(password.to_vec())),
+        (None, Some(location)) => Ok(Argon2Password::Stored(location.inner)),
+        _ => Err(PyValueError::new_err(
+            "exactly one of password and password_location must be given",
+        )),
+    }
+}
+
+/// Returns the Argon2id cost parameters, with the defaults of [`Argon2Params`] for the missing ones.
+fn argon2_params(memory_cost: Option<u32>, time_cost: Option<u32>, parallelism: Option<u32>) -> Argon2Params {
+    let default = Argon2Params::default();
+    Argon2Params {
+        memory_cost: memory_cost.unwrap_or(default.memory_cost),
+        time_cost: time_cost.unwrap_or(default.time_cost),
+        parallelism: parallelism.unwrap_or(default.parallelism),
+    }
+}
+
+pub(crate) fn output_to_py(
+    py: Python<'_>,
+    procedure: &StrongholdProcedure,
+    output: ProcedureOutput,
+) -> PyResult<PyObject> {
+    match procedure {
+        StrongholdProcedure::WriteVault(_)
+        | StrongholdProcedure::RevokeData(_)
+        | StrongholdProcedure::GarbageCollect(_)
+        | StrongholdProcedure::CopyRecord(_)
+        | StrongholdProcedure::Slip10Generate(_)
+        | StrongholdProcedure::BIP39Recover(_)
+        | StrongholdProcedure::GenerateKey(_)
+        | StrongholdProcedure::X25519DiffieHellman(_)
+        | StrongholdProcedure::Hkdf(_)
+        | StrongholdProcedure::ConcatKdf(_)
+        | StrongholdProcedure::AesKeyWrapDecrypt(_)
+        | StrongholdProcedure::Pbkdf2Hmac(_)
+        | StrongholdProcedure::Argon2Derive(_)
+        | StrongholdProcedure::ConcatSecret(_)
+        | StrongholdProcedure::ShamirCombine(_)
+        | StrongholdProcedure::ImportRecord(_) => Ok(py.None()),
+        StrongholdProcedure::Ed25519Verify(_)
+        | StrongholdProcedure::Ed25519VerifyPrehashed(_)
+        | StrongholdProcedure::Secp256k1EcdsaVerify(_)
+        | StrongholdProcedure::HmacVerify(_)
+        | StrongholdProcedure::Argon2Verify(_)
+        | StrongholdProcedure::PepperedHashVerify(_) => {
+            let valid = bool::try_from(output).map_err(|e| ProcedureError::new_err(e.to_string()))?;
+            Ok(PyBool::new(py, valid).into())
+        }
+        StrongholdProcedure::BIP39Generate(_)
+        | StrongholdProcedure::Bip32Derive(_)
+        | StrongholdProcedure::HotpGenerate(_)
+        | StrongholdProcedure::TotpGenerate(_) => {
+            let s = String::try_from(output).map_err(|e| ProcedureError::new_err(e.to_string()))?;
+            Ok(PyString::new(py, &s).into())
+        }
+        StrongholdProcedure::Ed25519SignBatch(_) => {
+            let Ed25519Signatures(signatures) =
+                Ed25519Signatures::try_from(output).map_err(|e| ProcedureError::new_err(e.to_string()))?;
+            let signatures = signatures.iter().map(|signature| PyBytes::new(py, signature));
+            Ok(PyList::new(py, signatures).into())
+        }
+        StrongholdProcedure::ShamirSplit(_) => {
+            let EncryptedShares(shares) =
+                EncryptedShares::try_from(output).map_err(|e| ProcedureError::new_err(e.to_string()))?;
+            let shares = shares.iter().map(|share| PyBytes::new(py, share));
+            Ok(PyList::new(py, shares).into())
+        }
+        _ => Ok(PyBytes::new(py, &Vec::<u8>::from(output)).into()),
+    }
+}
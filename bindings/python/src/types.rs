@@ -0,0 +1,359 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{path::PathBuf, time::Duration};
+
+use iota_stronghold::{
+    procedures::StrongholdProcedure, Client, ClientVault, KeyProvider, Location, SnapshotPath, Store, Stronghold,
+};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+
+use crate::{
+    error::{client_error, procedure_error},
+    procedures::{output_to_py, PyProcedure},
+};
+
+/// Key that encrypts a snapshot file. The key itself is not accessible from Python.
+#[pyclass(module = "stronghold", name = "KeyProvider")]
+pub struct PyKeyProvider {
+    inner: KeyProvider,
+}
+
+#[pymethods]
+impl PyKeyProvider {
+    /// Derives the key from `passphrase` with Blake2b-256.
+    #[staticmethod]
+    fn with_passphrase_hashed_blake2b(passphrase: &[u8]) -> PyResult<Self> {
+        let inner = KeyProvider::with_passphrase_hashed_blake2b(passphrase.to_vec()).map_err(client_error)?;
+        Ok(PyKeyProvider { inner })
+    }
+
+    /// Derives the key from `passphrase` and `salt` with Argon2.
+    #[staticmethod]
+    fn with_passphrase_hashed_argon2(py: Python<'_>, passphrase: &[u8], salt: &[u8]) -> PyResult<Self> {
+        let (passphrase, salt) = (passphrase.to_vec(), salt.to_vec());
+        let inner = py
+            .allow_threads(|| KeyProvider::with_passphrase_hashed_argon2(passphrase, salt))
+            .map_err(client_error)?;
+        Ok(PyKeyProvider { inner })
+    }
+
+    /// Derives the key from `passphrase` with the key derivation function stored in the header of the snapshot
+    /// file at `snapshot_path`.
+    #[staticmethod]
+    fn with_passphrase_for_snapshot(py: Python<'_>, passphrase: &[u8], snapshot_path: PathBuf) -> PyResult<Self> {
+        let passphrase = passphrase.to_vec();
+        let snapshot_path = SnapshotPath::from_path(snapshot_path);
+        let inner = py
+            .allow_threads(|| KeyProvider::with_passphrase_for_snapshot(passphrase, &snapshot_path))
+            .map_err(client_error)?;
+        Ok(PyKeyProvider { inner })
+    }
+}
+
+/// Location of a record in a vault.
+#[pyclass(module = "stronghold", name = "Location")]
+#[derive(Clone)]
+pub struct PyLocation {
+    pub(crate) inner: Location,
+}
+
+#[pymethods]
+impl PyLocation {
+    /// Location of the record at `record_path` in the vault at `vault_path`.
+    #[staticmethod]
+    fn generic(vault_path: &[u8], record_path: &[u8]) -> Self {
+        PyLocation {
+            inner: Location::generic(vault_path, record_path),
+        }
+    }
+
+    /// Location of the `counter`-th record in the vault at `vault_path`.
+    #[staticmethod]
+    fn counter(vault_path: &[u8], counter: usize) -> Self {
+        PyLocation {
+            inner: Location::counter(vault_path, counter),
+        }
+    }
+
+    #[getter]
+    fn vault_path<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, self.inner.vault_path())
+    }
+
+    #[getter]
+    fn record_path<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, self.inner.record_path())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Location(vault_path={:?}, record_path={:?})",
+            self.inner.vault_path(),
+            self.inner.record_path()
+        )
+    }
+}
+
+/// A Stronghold instance that manages clients and their snapshot.
+#[pyclass(module = "stronghold", name = "Stronghold")]
+pub struct PyStronghold {
+    inner: Stronghold,
+}
+
+#[pymethods]
+impl PyStronghold {
+    #[new]
+    fn new() -> Self {
+        PyStronghold {
+            inner: Stronghold::default(),
+        }
+    }
+
+    /// Creates a new, empty client at `client_path`.
+    fn create_client(&self, client_path: &[u8]) -> PyResult<PyClient> {
+        let inner = self.inner.create_client(client_path).map_err(client_error)?;
+        Ok(PyClient { inner })
+    }
+
+    /// Loads the client at `client_path` from the loaded snapshot.
+    fn load_client(&self, client_path: &[u8]) -> PyResult<PyClient> {
+        let inner = self.inner.load_client(client_path).map_err(client_error)?;
+        Ok(PyClient { inner })
+    }
+
+    /// Returns the already loaded client at `client_path`.
+    fn get_client(&self, client_path: &[u8]) -> PyResult<PyClient> {
+        let inner = self.inner.get_client(client_path).map_err(client_error)?;
+        Ok(PyClient { inner })
+    }
+
+    /// Loads the snapshot file at `snapshot_path` and the client at `client_path` from it.
+    fn load_client_from_snapshot(
+        &self,
+        py: Python<'_>,
+        client_path: &[u8],
+        keyprovider: PyRef<'_, PyKeyProvider>,
+        snapshot_path: PathBuf,
+    ) -> PyResult<PyClient> {
+        let snapshot_path = SnapshotPath::from_path(snapshot_path);
+        let keyprovider = &keyprovider.inner;
+        let inner = py
+            .allow_threads(|| {
+                self.inner
+                    .load_client_from_snapshot(client_path, keyprovider, &snapshot_path)
+            })
+            .map_err(client_error)?;
+        Ok(PyClient { inner })
+    }
+
+    /// Loads the snapshot file at `snapshot_path`, without loading any client.
+    fn load_snapshot(
+        &self,
+        py: Python<'_>,
+        keyprovider: PyRef<'_, PyKeyProvider>,
+        snapshot_path: PathBuf,
+    ) -> PyResult<()> {
+        let snapshot_path = SnapshotPath::from_path(snapshot_path);
+        let keyprovider = &keyprovider.inner;
+        py.allow_threads(|| self.inner.load_snapshot(keyprovider, &snapshot_path))
+            .map_err(client_error)?;
+        Ok(())
+    }
+
+    /// Removes `client` from the loaded clients. The client is written into the snapshot before.
+    fn unload_client(&self, client: PyRef<'_, PyClient>) -> PyResult<()> {
+        self.inner.unload_client(client.inner.clone()).map_err(client_error)?;
+        Ok(())
+    }
+
+    /// Writes the state of the client at `client_path` into the snapshot, without persisting it.
+    fn write_client(&self, client_path: &[u8]) -> PyResult<()> {
+        self.inner.write_client(client_path).map_err(client_error)
+    }
+
+    /// Writes all loaded clients into the snapshot file at `snapshot_path`, encrypted with `keyprovider`.
+    fn commit_with_keyprovider(
+        &self,
+        py: Python<'_>,
+        snapshot_path: PathBuf,
+        keyprovider: PyRef<'_, PyKeyProvider>,
+    ) -> PyResult<()> {
+        let snapshot_path = SnapshotPath::from_path(snapshot_path);
+        let keyprovider = &keyprovider.inner;
+        py.allow_threads(|| self.inner.commit_with_keyprovider(&snapshot_path, keyprovider))
+            .map_err(client_error)
+    }
+
+    /// Writes all loaded clients into the snapshot file at `snapshot_path`, encrypted with the key that is stored
+    /// in the snapshot.
+    fn commit(&self, py: Python<'_>, snapshot_path: PathBuf) -> PyResult<()> {
+        let snapshot_path = SnapshotPath::from_path(snapshot_path);
+        py.allow_threads(|| self.inner.commit(&snapshot_path))
+            .map_err(client_error)
+    }
+
+    /// Removes all clients and the snapshot from memory.
+    fn clear(&self) -> PyResult<()> {
+        self.inner.clear().map_err(client_error)
+    }
+
+    /// The store of the Stronghold instance, which is shared by all clients.
+    fn store(&self) -> PyStore {
+        PyStore {
+            inner: self.inner.store(),
+        }
+    }
+}
+
+/// A client with its vaults and store.
+#[pyclass(module = "stronghold", name = "Client")]
+pub struct PyClient {
+    inner: Client,
+}
+
+#[pymethods]
+impl PyClient {
+    /// The vault at `vault_path`.
+    fn vault(&self, vault_path: &[u8]) -> PyClientVault {
+        PyClientVault {
+            inner: self.inner.vault(vault_path),
+        }
+    }
+
+    /// Checks if the vault at `vault_path` exists.
+    fn vault_exists(&self, vault_path: &[u8]) -> PyResult<bool> {
+        self.inner.vault_exists(vault_path).map_err(client_error)
+    }
+
+    /// Checks if a record exists at `location`.
+    fn record_exists(&self, location: PyRef<'_, PyLocation>) -> PyResult<bool> {
+        self.inner.record_exists(&location.inner).map_err(client_error)
+    }
+
+    /// The store of the client.
+    fn store(&self) -> PyStore {
+        PyStore {
+            inner: self.inner.store(),
+        }
+    }
+
+    /// Executes `procedure` and returns its public output, or `None` for procedures that only write secrets.
+    fn execute_procedure(&self, py: Python<'_>, procedure: PyRef<'_, PyProcedure>) -> PyResult<PyObject> {
+        let procedure = procedure.inner.clone();
+        let kind = procedure.clone();
+        let output = py
+            .allow_threads(|| self.inner.execute_procedure(procedure))
+            .map_err(procedure_error)?;
+        output_to_py(py, &kind, output)
+    }
+
+    /// Executes `procedures` in order and returns their outputs. If one of them fails, the secrets written by
+    /// the previous ones are revoked.
+    fn execute_procedure_chained(
+        &self,
+        py: Python<'_>,
+        procedures: Vec<PyRef<'_, PyProcedure>>,
+    ) -> PyResult<Vec<PyObject>> {
+        let procedures: Vec<StrongholdProcedure> = procedures.iter().map(|p| p.inner.clone()).collect();
+        let kinds = procedures.clone();
+        let outputs = py
+            .allow_threads(|| self.inner.execute_procedure_chained(procedures))
+            .map_err(procedure_error)?;
+        kinds
+            .iter()
+            .zip(outputs)
+            .map(|(kind, output)| output_to_py(py, kind, output))
+            .collect()
+    }
+}
+
+/// A vault of a client. Secrets can be written, but never read.
+#[pyclass(module = "stronghold", name = "ClientVault")]
+pub struct PyClientVault {
+    inner: ClientVault,
+}
+
+#[pymethods]
+impl PyClientVault {
+    /// Writes `payload` into the record at `location`.
+    fn write_secret(&self, location: PyRef<'_, PyLocation>, payload: &[u8]) -> PyResult<()> {
+        self.inner
+            .write_secret(location.inner.clone(), payload.to_vec())
+            .map_err(client_error)
+    }
+
+    /// Revokes and garbage collects the record at `record_path`. Returns `True` if the vault has been cleaned up.
+    fn delete_secret(&self, record_path: &[u8]) -> PyResult<bool> {
+        self.inner.delete_secret(record_path).map_err(client_error)
+    }
+
+    /// Revokes the record at `record_path`, without removing it.
+    fn revoke_secret(&self, record_path: &[u8]) -> PyResult<()> {
+        self.inner.revoke_secret(record_path).map_err(client_error)
+    }
+
+    /// Removes all revoked records.
+    fn cleanup(&self) -> PyResult<bool> {
+        self.inner.cleanup().map_err(client_error)
+    }
+
+    /// Returns the number of records in the vault. Revoked records are not counted.
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.inner.list_records().map_err(client_error)?.len())
+    }
+}
+
+/// Key-value store for insensitive data.
+#[pyclass(module = "stronghold", name = "Store")]
+pub struct PyStore {
+    inner: Store,
+}
+
+#[pymethods]
+impl PyStore {
+    /// Inserts `value` at `key`. If `lifetime` is set, the entry expires after the given number of seconds. Returns
+    /// the previous value.
+    ///
+    /// Raises `ValueError` if `lifetime` is negative, not finite or too large.
+    #[pyo3(signature = (key, value, lifetime = None))]
+    fn insert<'py>(
+        &self,
+        py: Python<'py>,
+        key: &[u8],
+        value: &[u8],
+        lifetime: Option<f64>,
+    ) -> PyResult<Option<&'py PyBytes>> {
+        let lifetime = lifetime
+            .map(Duration::try_from_secs_f64)
+            .transpose()
+            .map_err(|e| PyValueError::new_err(format!("invalid lifetime: {}", e)))?;
+        let previous = self
+            .inner
+            .insert(key.to_vec(), value.to_vec(), lifetime)
+            .map_err(client_error)?;
+        Ok(previous.map(|value| PyBytes::new(py, &value)))
+    }
+
+    /// Returns the value at `key`, or `None`.
+    fn get<'py>(&self, py: Python<'py>, key: &[u8]) -> PyResult<Option<&'py PyBytes>> {
+        let value = self.inner.get(key).map_err(client_error)?;
+        Ok(value.map(|value| PyBytes::new(py, &value)))
+    }
+
+    /// Removes the entry at `key` and returns its value, or `None`.
+    fn delete<'py>(&self, py: Python<'py>, key: &[u8]) -> PyResult<Option<&'py PyBytes>> {
+        let value = self.inner.delete(key).map_err(client_error)?;
+        Ok(value.map(|value| PyBytes::new(py, &value)))
+    }
+
+    /// Returns all keys of the store.
+    fn keys<'py>(&self, py: Python<'py>) -> PyResult<Vec<&'py PyBytes>> {
+        let keys = self.inner.keys().map_err(client_error)?;
+        Ok(keys.iter().map(|key| PyBytes::new(py, key)).collect())
+    }
+
+    fn __contains__(&self, key: &[u8]) -> PyResult<bool> {
+        self.inner.contains_key(key).map_err(client_error)
+    }
+}
@@ -0,0 +1,289 @@
+# Copyright 2020-2022 IOTA Stiftung
+# SPDX-License-Identifier: Apache-2.0
+
+import hashlib
+import os
+
+import pytest
+
+from stronghold import (
+    HARDENED,
+    AeadCipher,
+    HpkeAead,
+    KeyProvider,
+    KeyType,
+    Location,
+    OtpHash,
+    Procedure,
+    ProcedureError,
+    Sha2Hash,
+    Stronghold,
+    StrongholdError,
+)
+
+
+def location():
+    return Location.generic(os.urandom(8), os.urandom(8))
+
+
+@pytest.fixture
+def client():
+    return Stronghold().create_client(b"client")
+
+
+def test_generate_key_and_sign(client):
+    key = location()
+    assert client.execute_procedure(Procedure.generate_key(KeyType.Ed25519, key)) is None
+    assert client.record_exists(key)
+
+    public_key = client.execute_procedure(Procedure.public_key(KeyType.Ed25519, key))
+    signature = client.execute_procedure(Procedure.ed25519_sign(b"message", key))
+    assert isinstance(public_key, bytes) and len(public_key) == 32
+    assert isinstance(signature, bytes) and len(signature) == 64
+
+
+def test_ed25519_sign_batch_and_prehashed(client):
+    key = location()
+    client.execute_procedure(Procedure.generate_key(KeyType.Ed25519, key))
+    msgs = [os.urandom(32) for _ in range(8)]
+    signatures = client.execute_procedure(Procedure.ed25519_sign_batch(msgs, key))
+    assert signatures == [client.execute_procedure(Procedure.ed25519_sign(msg, key)) for msg in msgs]
+
+    prehash = hashlib.sha512(b"document").digest()
+    signature = client.execute_procedure(Procedure.ed25519_sign_prehashed(prehash, key))
+    assert len(signature) == 64
+    with pytest.raises(ProcedureError):
+        client.execute_procedure(Procedure.ed25519_sign_prehashed(prehash[:32], key))
+
+
+def test_verify(client):
+    key, mac_key = location(), location()
+    client.execute_procedure(Procedure.generate_key(KeyType.Ed25519, key))
+    public_key = client.execute_procedure(Procedure.public_key(KeyType.Ed25519, key))
+    signature = client.execute_procedure(Procedure.ed25519_sign(b"message", key))
+    assert client.execute_procedure(Procedure.ed25519_verify(b"message", signature, public_key)) is True
+    assert client.execute_procedure(Procedure.ed25519_verify(b"other", signature, public_key)) is False
+
+    client.vault(mac_key.vault_path).write_secret(mac_key, os.urandom(32))
+    mac = client.execute_procedure(Procedure.hmac(Sha2Hash.Sha256, b"message", mac_key))
+    assert client.execute_procedure(Procedure.hmac_verify(Sha2Hash.Sha256, b"message", mac, mac_key)) is True
+    assert client.execute_procedure(Procedure.hmac_verify(Sha2Hash.Sha256, b"other", mac, mac_key)) is False
+
+
+def test_slip10_derive(client):
+    seed, key = location(), location()
+    client.execute_procedure(Procedure.slip10_generate(seed))
+    chain_code = client.execute_procedure(Procedure.slip10_derive([HARDENED | 44, HARDENED | 4218], key, seed=seed))
+    assert len(chain_code) == 32
+    assert client.record_exists(key)
+
+    with pytest.raises(ValueError):
+        Procedure.slip10_derive([HARDENED], location())
+
+
+def test_bip39_generate_and_recover(client):
+    seed, recovered = location(), location()
+    mnemonic = client.execute_procedure(Procedure.bip39_generate(seed, passphrase="secret"))
+    assert isinstance(mnemonic, str) and len(mnemonic.split()) == 24
+
+    assert client.execute_procedure(Procedure.bip39_recover(mnemonic, recovered, passphrase="secret")) is None
+
+    key_a, key_b = location(), location()
+    chain = [HARDENED | 44]
+    client.execute_procedure(Procedure.slip10_derive(chain, key_a, seed=seed))
+    client.execute_procedure(Procedure.slip10_derive(chain, key_b, seed=recovered))
+    public_a = client.execute_procedure(Procedure.public_key(KeyType.Ed25519, key_a))
+    public_b = client.execute_procedure(Procedure.public_key(KeyType.Ed25519, key_b))
+    assert public_a == public_b
+
+
+def test_write_secret_is_not_readable(client):
+    secret = location()
+    client.vault(secret.vault_path).write_secret(secret, b"password")
+    assert not hasattr(client.vault(secret.vault_path), "read_secret")
+
+    mac = client.execute_procedure(Procedure.hmac(Sha2Hash.Sha256, b"message", secret))
+    assert len(mac) == 32
+
+
+def test_aead_roundtrip(client):
+    key = location()
+    client.vault(key.vault_path).write_secret(key, os.urandom(32))
+    nonce = os.urandom(24)
+    encrypted = client.execute_procedure(
+        Procedure.aead_encrypt(AeadCipher.XChaCha20Poly1305, b"ad", b"plaintext", nonce, key)
+    )
+    tag, ciphertext = encrypted[:16], encrypted[16:]
+    plaintext = client.execute_procedure(
+        Procedure.aead_decrypt(AeadCipher.XChaCha20Poly1305, b"ad", ciphertext, tag, nonce, key)
+    )
+    assert plaintext == b"plaintext"
+
+
+def test_aead_generated_nonce(client):
+    key = location()
+    client.vault(key.vault_path).write_secret(key, os.urandom(32))
+    encrypted = client.execute_procedure(Procedure.aead_encrypt(AeadCipher.Aes256GcmSiv, b"ad", b"plaintext", None, key))
+    nonce, tag, ciphertext = encrypted[:12], encrypted[12:28], encrypted[28:]
+    plaintext = client.execute_procedure(
+        Procedure.aead_decrypt(AeadCipher.Aes256GcmSiv, b"ad", ciphertext, tag, nonce, key)
+    )
+    assert plaintext == b"plaintext"
+
+    # Records written with `write_secret` are exportable and thus can't be used with counter nonces.
+    with pytest.raises(ProcedureError):
+        client.execute_procedure(
+            Procedure.aead_encrypt(AeadCipher.ChaCha20Poly1305, b"ad", b"plaintext", None, key, counter=1)
+        )
+
+
+def test_stored_nonce_counter(client):
+    key = location()
+    client.vault(key.vault_path).write_secret(key, os.urandom(32))
+    with pytest.raises(ValueError):
+        Procedure.aead_encrypt(AeadCipher.Aes256Gcm, b"", b"plaintext", None, key, counter=1, stored_counter=True)
+    # Records written with `write_secret` are exportable and thus can't be used with counter nonces.
+    with pytest.raises(ProcedureError):
+        client.execute_procedure(
+            Procedure.aead_encrypt(AeadCipher.Aes256Gcm, b"", b"plaintext", None, key, stored_counter=True)
+        )
+
+
+def test_otp(client):
+    totp, hotp = location(), location()
+    write, parameters = Procedure.write_otpauth_uri(
+        "otpauth://totp/Example:alice?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Example&digits=8", totp
+    )
+    assert parameters["type"] == "totp" and parameters["period"] == 30 and parameters["digits"] == 8
+    client.execute_procedure(write)
+    assert client.execute_procedure(Procedure.totp_generate(totp, 59, digits=8)) == "94287082"
+
+    client.vault(hotp.vault_path).write_secret(hotp, b"12345678901234567890")
+    assert client.execute_procedure(Procedure.hotp_generate(hotp, 1)) == "287082"
+    assert len(client.execute_procedure(Procedure.hotp_generate(hotp, 1, hash=OtpHash.Sha512, digits=10))) == 10
+
+    with pytest.raises(ValueError):
+        Procedure.write_otpauth_uri("otpauth://hotp/alice?secret=GEZDGNBV", hotp)
+
+
+def test_argon2(client):
+    password, key = location(), location()
+    client.vault(password.vault_path).write_secret(password, b"password")
+    costs = dict(memory_cost=64, time_cost=1, parallelism=1)
+    salt = os.urandom(16)
+    assert client.execute_procedure(Procedure.argon2_derive(salt, key, password_location=password, **costs)) is None
+    mac = client.execute_procedure(Procedure.hmac(Sha2Hash.Sha256, b"message", key))
+
+    supplied = location()
+    client.execute_procedure(Procedure.argon2_derive(salt, supplied, password=b"password", **costs))
+    assert client.execute_procedure(Procedure.hmac(Sha2Hash.Sha256, b"message", supplied)) == mac
+
+    hash = "$argon2id$v=19$m=64,t=1,p=1$c29tZXNhbHQ$cpx6VEQbwTVZvcpxNIxOVUWZ5xnAipUmAe1cg2GMG70"
+    assert client.execute_procedure(Procedure.argon2_verify(hash, password_location=password)) is True
+    assert client.execute_procedure(Procedure.argon2_verify(hash, password=b"wrong")) is False
+    with pytest.raises(ValueError):
+        Procedure.argon2_verify(hash)
+
+
+def test_peppered_hash(client):
+    pepper = location()
+    client.vault(pepper.vault_path).write_secret(pepper, os.urandom(32))
+    salt = os.urandom(16)
+    costs = dict(memory_cost=64, time_cost=1, parallelism=1)
+    # Records written with `write_secret` are exportable and thus can't be used as pepper.
+    with pytest.raises(ProcedureError):
+        client.execute_procedure(Procedure.peppered_hash(Sha2Hash.Sha256, b"password", salt, pepper, **costs))
+
+
+def test_hpke_seal_open(client):
+    recipient, sender = location(), location()
+    client.execute_procedure(Procedure.generate_key(KeyType.X25519, recipient))
+    client.execute_procedure(Procedure.generate_key(KeyType.X25519, sender))
+    recipient_public_key = client.execute_procedure(Procedure.public_key(KeyType.X25519, recipient))
+    sender_public_key = client.execute_procedure(Procedure.public_key(KeyType.X25519, sender))
+
+    sealed = client.execute_procedure(
+        Procedure.hpke_seal(HpkeAead.ChaCha20Poly1305, recipient_public_key, b"plaintext", sender=sender, aad=b"ad")
+    )
+    assert len(sealed) == 32 + len(b"plaintext") + 16
+    plaintext = client.execute_procedure(
+        Procedure.hpke_open(HpkeAead.ChaCha20Poly1305, recipient, sealed, sender=sender_public_key, aad=b"ad")
+    )
+    assert plaintext == b"plaintext"
+
+    with pytest.raises(ProcedureError):
+        client.execute_procedure(Procedure.hpke_open(HpkeAead.ChaCha20Poly1305, recipient, sealed, aad=b"ad"))
+
+
+def test_chained_procedures(client):
+    seed, key = location(), location()
+    outputs = client.execute_procedure_chained(
+        [
+            Procedure.slip10_generate(seed),
+            Procedure.slip10_derive([HARDENED], key, seed=seed),
+            Procedure.public_key(KeyType.Ed25519, key),
+        ]
+    )
+    assert outputs[0] is None
+    assert len(outputs[2]) == 32
+
+
+def test_shamir_split_combine(client):
+    secret, recovered = location(), location()
+    shares = [location() for _ in range(3)]
+    client.vault(secret.vault_path).write_secret(secret, os.urandom(32))
+
+    encrypted = client.execute_procedure(Procedure.shamir_split(secret, 2, shares))
+    assert encrypted == []
+    client.execute_procedure(Procedure.shamir_combine(shares[1:], recovered))
+
+    mac_a = client.execute_procedure(Procedure.hmac(Sha2Hash.Sha256, b"msg", secret))
+    mac_b = client.execute_procedure(Procedure.hmac(Sha2Hash.Sha256, b"msg", recovered))
+    assert mac_a == mac_b
+
+
+def test_procedure_error(client):
+    with pytest.raises(ProcedureError):
+        client.execute_procedure(Procedure.public_key(KeyType.Ed25519, location()))
+    assert issubclass(ProcedureError, StrongholdError)
+
+    with pytest.raises(ValueError):
+        Procedure.x25519_diffie_hellman(b"short", location(), location())
+
+
+def test_store():
+    stronghold = Stronghold()
+    store = stronghold.create_client(b"client").store()
+    assert store.insert(b"key", b"value") is None
+    assert b"key" in store
+    assert store.get(b"key") == b"value"
+    assert store.keys() == [b"key"]
+    assert store.delete(b"key") == b"value"
+    assert store.get(b"key") is None
+    for lifetime in (-1.0, float("nan"), float("inf")):
+        with pytest.raises(ValueError):
+            store.insert(b"key", b"value", lifetime)
+
+
+def test_snapshot_roundtrip(tmp_path):
+    snapshot_path = str(tmp_path / "test.snapshot")
+    keyprovider = KeyProvider.with_passphrase_hashed_blake2b(b"passphrase")
+    key = location()
+
+    stronghold = Stronghold()
+    client = stronghold.create_client(b"client")
+    client.execute_procedure(Procedure.generate_key(KeyType.Ed25519, key))
+    public_key = client.execute_procedure(Procedure.public_key(KeyType.Ed25519, key))
+    client.store().insert(b"key", b"value")
+    stronghold.write_client(b"client")
+    stronghold.commit_with_keyprovider(snapshot_path, keyprovider)
+
+    stronghold = Stronghold()
+    client = stronghold.load_client_from_snapshot(b"client", keyprovider, snapshot_path)
+    assert client.execute_procedure(Procedure.public_key(KeyType.Ed25519, key)) == public_key
+    assert client.store().get(b"key") == b"value"
+
+    with pytest.raises(StrongholdError):
+        Stronghold().load_client_from_snapshot(
+            b"client", KeyProvider.with_passphrase_hashed_blake2b(b"wrong"), snapshot_path
+        )
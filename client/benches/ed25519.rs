@@ -0,0 +1,101 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use crypto::hashes::{sha::Sha512, Digest};
+use iota_stronghold::{
+    procedures::{Ed25519Sign, Ed25519SignBatch, Ed25519SignPrehashed, GenerateKey, KeyType},
+    Client, Location, Stronghold,
+};
+
+const MESSAGES: usize = 1000;
+
+fn setup() -> (Client, Location) {
+    let stronghold = Stronghold::default();
+    let client = stronghold.create_client(b"bench").unwrap();
+    let key = Location::generic(b"vault", b"ed25519");
+    client
+        .execute_procedure(GenerateKey {
+            ty: KeyType::Ed25519,
+            output: key.clone(),
+        })
+        .unwrap();
+    (client, key)
+}
+
+fn messages() -> Vec<Vec<u8>> {
+    (0..MESSAGES)
+        .map(|i| format!("transaction {}", i).into_bytes())
+        .collect()
+}
+
+/// Signing of 1000 messages with one procedure per message, and with a single batch procedure.
+fn bench_ed25519_sign_batch(c: &mut Criterion) {
+    let (client, key) = setup();
+    let mut group = c.benchmark_group("ed25519_sign_1000");
+    group.sample_size(10);
+    group.bench_function("single", |b| {
+        b.iter_batched(
+            messages,
+            |msgs| {
+                for msg in msgs {
+                    client
+                        .execute_procedure(Ed25519Sign {
+                            msg,
+                            private_key: key.clone(),
+                        })
+                        .unwrap();
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("batch", |b| {
+        b.iter_batched(
+            messages,
+            |msgs| {
+                client
+                    .execute_procedure(Ed25519SignBatch {
+                        msgs,
+                        private_key: key.clone(),
+                    })
+                    .unwrap()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+/// Signing of a 16 MiB document, directly and as Ed25519ph.
+fn bench_ed25519_sign_prehashed(c: &mut Criterion) {
+    let (client, key) = setup();
+    let document = vec![0x42u8; 16 << 20];
+    let mut group = c.benchmark_group("ed25519_sign_16mib");
+    group.sample_size(10);
+    group.bench_function("pure", |b| {
+        b.iter(|| {
+            client
+                .execute_procedure(Ed25519Sign {
+                    msg: document.clone(),
+                    private_key: key.clone(),
+                })
+                .unwrap()
+        })
+    });
+    group.bench_function("prehashed", |b| {
+        b.iter(|| {
+            client
+                .execute_procedure(Ed25519SignPrehashed {
+                    prehash: Sha512::digest(&document).to_vec(),
+                    context: vec![],
+                    private_key: key.clone(),
+                })
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_ed25519_sign_batch, bench_ed25519_sign_prehashed);
+criterion_main!(benches);
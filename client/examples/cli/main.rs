@@ -77,9 +77,14 @@ pub struct StrongholdCLI {
 #[derive(Subcommand, Debug)]
 #[non_exhaustive]
 pub enum Command {
-    #[clap(about = "Generates a secret key and returns the public key, Possible values are ['Ed25519', 'X25519']")]
+    #[clap(
+        about = "Generates a secret key and returns the public key, Possible values are ['Ed25519', 'X25519', 'Secp256k1']"
+    )]
     GenerateKey {
-        #[clap(long, help = "The key type to use. Possible values are: ['Ed25519', 'X25519']")]
+        #[clap(
+            long,
+            help = "The key type to use. Possible values are: ['Ed25519', 'X25519', 'Secp256k1']"
+        )]
         key_type: String,
 
         #[clap(flatten)]
@@ -245,6 +250,7 @@ async fn command_generate_key(key_type: String, location: VaultLocation) {
     let keytype = match key_type.to_lowercase().as_str() {
         "ed25519" => KeyType::Ed25519,
         "x25519" => KeyType::X25519,
+        "secp256k1" => KeyType::Secp256k1Ecdsa,
         _ => {
             error!("Unknown key type: {}", key_type);
             return;
@@ -89,6 +89,7 @@ pub fn parse_keytype(value: &str) -> Result<KeyType, ReplError<String>> {
     match value.to_lowercase().as_str() {
         "ed25519" => Ok(KeyType::Ed25519),
         "x25519" => Ok(KeyType::X25519),
+        "secp256k1" => Ok(KeyType::Secp256k1Ecdsa),
         _ => Err(ReplError::Invalid("Key Type".to_string())),
     }
 }
@@ -0,0 +1,69 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Serves the clients of a Stronghold snapshot to other processes on the same host.
+//!
+//! ```text
+//! stronghold-daemon <socket> <snapshot> <token>
+//! ```
+//!
+//! The passphrase of the snapshot is read from the first line of stdin. If the snapshot does not exist yet, it is
+//! created on the first commit with a key derived from the passphrase with Blake2b-256. Sessions are authenticated
+//! with the access token at `<token>`, which is created with owner-only permissions if it does not exist.
+
+use std::{
+    io::{self, BufRead},
+    process::exit,
+};
+
+use iota_stronghold::{
+    daemon::{load_or_create_token, Daemon},
+    KeyProvider, SnapshotPath, Stronghold,
+};
+use zeroize::Zeroize;
+
+const USAGE: &str = "usage: stronghold-daemon <socket> <snapshot> <token>";
+
+fn fail<E: std::fmt::Display>(context: &str, e: E) -> ! {
+    eprintln!("{}: {}", context, e);
+    exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (socket, snapshot, token) = match args.as_slice() {
+        [socket, snapshot, token] => (socket, SnapshotPath::from_path(snapshot), token),
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    };
+
+    let mut passphrase = match io::stdin().lock().lines().next() {
+        Some(Ok(line)) => line,
+        _ => {
+            eprintln!("missing snapshot passphrase on stdin");
+            exit(2);
+        }
+    };
+
+    let stronghold = Stronghold::default();
+    let keyprovider = if snapshot.exists() {
+        let keyprovider = KeyProvider::with_passphrase_for_snapshot(passphrase.as_bytes().to_vec(), &snapshot)
+            .unwrap_or_else(|e| fail("deriving snapshot key failed", e));
+        stronghold
+            .load_snapshot(&keyprovider, &snapshot)
+            .unwrap_or_else(|e| fail("loading snapshot failed", e));
+        keyprovider
+    } else {
+        KeyProvider::with_passphrase_hashed_blake2b(passphrase.as_bytes().to_vec())
+            .unwrap_or_else(|e| fail("deriving snapshot key failed", e))
+    };
+    passphrase.zeroize();
+
+    let token = load_or_create_token(token).unwrap_or_else(|e| fail("loading access token failed", e));
+    let daemon = Daemon::new(stronghold, keyprovider, snapshot, token);
+    if let Err(e) = daemon.serve(socket) {
+        fail("serving failed", e);
+    }
+}
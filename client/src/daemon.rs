@@ -0,0 +1,21 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Access to a [`crate::Stronghold`] owned by another process on the same host.
+//!
+//! The [`Daemon`] serves [`crate::procedures::StrongholdProcedure`]s over a Unix domain socket. Every connection is
+//! a session that is bound to one client of the daemon's [`crate::Stronghold`]. A session has to be authenticated
+//! before any request is served: the daemon sends a random challenge, which the connecting process answers with
+//! the HMAC-SHA256 of the challenge and the client path, keyed with the access token of the daemon. Processes
+//! that can read the access token may use the secrets of the daemon without knowing the snapshot passphrase.
+//! The number of concurrent connections is limited, see [`Daemon::set_max_connections`].
+//!
+//! [`DaemonClient`] implements the connecting side of the protocol.
+
+mod protocol;
+mod remote;
+mod server;
+
+pub use protocol::*;
+pub use remote::*;
+pub use server::*;
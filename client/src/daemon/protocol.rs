@@ -0,0 +1,144 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use crypto::{macs::hmac::HMAC_SHA256, utils::rand::fill};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use crate::{
+    procedures::{ProcedureOutput, StrongholdProcedure},
+    DaemonError, Location,
+};
+
+/// Size of the access token and of the authentication challenge.
+pub const TOKEN_SIZE: usize = 32;
+
+/// Maximum size of a single serialized message.
+pub const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Request of a [`crate::daemon::DaemonClient`].
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Request {
+    /// Answers the challenge of the daemon and binds the session to the client at `client_path`.
+    Authenticate { client_path: Vec<u8>, mac: Vec<u8> },
+
+    /// Executes the procedures sequentially, see [`crate::Client::execute_procedure_chained`].
+    Procedures(Vec<StrongholdProcedure>),
+
+    /// Checks if the vault at the path exists.
+    VaultExists(Vec<u8>),
+
+    /// Checks if the record at the location exists.
+    RecordExists(Location),
+
+    /// Writes the state of the daemon into its snapshot file.
+    Commit,
+}
+
+/// Response of the [`crate::daemon::Daemon`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Response {
+    /// Random challenge that has to be answered with [`Request::Authenticate`].
+    Challenge(Vec<u8>),
+
+    /// The session has been authenticated. The session is bound to the connection, requests on it are served for
+    /// the authenticated client.
+    Authenticated,
+
+    /// Outputs of [`Request::Procedures`].
+    Outputs(Vec<ProcedureOutput>),
+
+    /// Answer to [`Request::VaultExists`] and [`Request::RecordExists`].
+    Exists(bool),
+
+    /// The snapshot has been written.
+    Committed,
+
+    /// The request failed.
+    Error(DaemonError),
+}
+
+/// Computes the answer to an authentication `challenge` for the client at `client_path`.
+pub fn authentication_mac(token: &[u8], challenge: &[u8], client_path: &[u8]) -> [u8; TOKEN_SIZE] {
+    let mut msg = Vec::with_capacity(challenge.len() + client_path.len());
+    msg.extend_from_slice(challenge);
+    msg.extend_from_slice(client_path);
+    let mut mac = [0u8; TOKEN_SIZE];
+    HMAC_SHA256(&msg, token, &mut mac);
+    mac
+}
+
+/// Returns `TOKEN_SIZE` random bytes.
+pub(crate) fn random_bytes() -> Result<Vec<u8>, DaemonError> {
+    let mut bytes = vec![0u8; TOKEN_SIZE];
+    fill(&mut bytes).map_err(|e| DaemonError::Io(e.to_string()))?;
+    Ok(bytes)
+}
+
+/// Reads the access token at `path`, or creates a new random token that is only readable by the owner.
+pub fn load_or_create_token<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, DaemonError> {
+    let path = path.as_ref();
+    if path.exists() {
+        let token = fs::read(path)?;
+        if token.len() != TOKEN_SIZE {
+            return Err(DaemonError::InvalidMessage(format!(
+                "access token must be {} bytes, found {}",
+                TOKEN_SIZE,
+                token.len()
+            )));
+        }
+        return Ok(token);
+    }
+
+    let token = random_bytes()?;
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(&token)?;
+    Ok(token)
+}
+
+/// Writes a length prefixed message.
+pub(crate) fn write_message<W: Write, T: Serialize>(writer: &mut W, message: &T) -> Result<(), DaemonError> {
+    let mut bytes = bincode::serialize(message)?;
+    let result = if bytes.len() > MAX_MESSAGE_SIZE {
+        Err(DaemonError::InvalidMessage("message exceeds maximum size".to_string()))
+    } else {
+        writer
+            .write_all(&(bytes.len() as u32).to_le_bytes())
+            .and_then(|_| writer.write_all(&bytes))
+            .and_then(|_| writer.flush())
+            .map_err(DaemonError::from)
+    };
+    bytes.zeroize();
+    result
+}
+
+/// Reads a length prefixed message. Returns `None` if the peer closed the connection.
+pub(crate) fn read_message<R: Read, T: DeserializeOwned>(reader: &mut R) -> Result<Option<T>, DaemonError> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(DaemonError::InvalidMessage("message exceeds maximum size".to_string()));
+    }
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes)?;
+    let message = bincode::deserialize(&bytes);
+    bytes.zeroize();
+    Ok(Some(message?))
+}
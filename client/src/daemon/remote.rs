@@ -0,0 +1,124 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{os::unix::net::UnixStream, path::Path};
+
+use crate::{
+    daemon::{authentication_mac, read_message, write_message, Request, Response},
+    procedures::{Procedure, ProcedureOutput, StrongholdProcedure},
+    DaemonError, Location,
+};
+
+/// Session with a [`crate::daemon::Daemon`], bound to one client of the daemon's [`crate::Stronghold`].
+///
+/// # Example
+pub struct DaemonClient {
+    stream: UnixStream,
+}
+
+impl DaemonClient {
+    /// Connects to the daemon listening at `socket_path` and authenticates a session for the client at
+    /// `client_path` with the access `token` of the daemon.
+    ///
+    /// # Example
+    pub fn connect<S, P>(socket_path: S, token: &[u8], client_path: P) -> Result<Self, DaemonError>
+    where
+        S: AsRef<Path>,
+        P: AsRef<[u8]>,
+    {
+        let mut stream = UnixStream::connect(socket_path)?;
+        let challenge = match Self::receive(&mut stream)? {
+            Response::Challenge(challenge) => challenge,
+            other => return Err(unexpected(other)),
+        };
+
+        let client_path = client_path.as_ref().to_vec();
+        let mac = authentication_mac(token, &challenge, &client_path).to_vec();
+        write_message(&mut stream, &Request::Authenticate { client_path, mac })?;
+
+        match Self::receive(&mut stream)? {
+            Response::Authenticated => Ok(DaemonClient { stream }),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Executes a cryptographic [`Procedure`] on the daemon and returns its output.
+    ///
+    /// # Example
+    pub fn execute_procedure<P>(&mut self, procedure: P) -> Result<P::Output, DaemonError>
+    where
+        P: Procedure + Into<StrongholdProcedure>,
+    {
+        let output = self
+            .execute_procedure_chained(vec![procedure.into()])?
+            .pop()
+            .ok_or_else(|| DaemonError::InvalidMessage("missing procedure output".to_string()))?;
+        output
+            .try_into()
+            .map_err(|_| DaemonError::InvalidMessage("unexpected procedure output".to_string()))
+    }
+
+    /// Executes a list of cryptographic procedures sequentially on the daemon, see
+    /// [`crate::Client::execute_procedure_chained`].
+    ///
+    /// # Example
+    pub fn execute_procedure_chained(
+        &mut self,
+        procedures: Vec<StrongholdProcedure>,
+    ) -> Result<Vec<ProcedureOutput>, DaemonError> {
+        match self.request(&Request::Procedures(procedures))? {
+            Response::Outputs(outputs) => Ok(outputs),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Returns `true`, if a vault exists
+    ///
+    /// # Example
+    pub fn vault_exists<P>(&mut self, vault_path: P) -> Result<bool, DaemonError>
+    where
+        P: AsRef<[u8]>,
+    {
+        match self.request(&Request::VaultExists(vault_path.as_ref().to_vec()))? {
+            Response::Exists(exists) => Ok(exists),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Returns `true`, if the record exists
+    ///
+    /// # Example
+    pub fn record_exists(&mut self, location: &Location) -> Result<bool, DaemonError> {
+        match self.request(&Request::RecordExists(location.clone()))? {
+            Response::Exists(exists) => Ok(exists),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Writes the state of the daemon into its snapshot file.
+    ///
+    /// # Example
+    pub fn commit(&mut self) -> Result<(), DaemonError> {
+        match self.request(&Request::Commit)? {
+            Response::Committed => Ok(()),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    fn request(&mut self, request: &Request) -> Result<Response, DaemonError> {
+        write_message(&mut self.stream, request)?;
+        Self::receive(&mut self.stream)
+    }
+
+    fn receive(stream: &mut UnixStream) -> Result<Response, DaemonError> {
+        match read_message(stream)? {
+            Some(Response::Error(e)) => Err(e),
+            Some(response) => Ok(response),
+            None => Err(DaemonError::Io("connection closed by the daemon".to_string())),
+        }
+    }
+}
+
+fn unexpected(response: Response) -> DaemonError {
+    DaemonError::InvalidMessage(format!("unexpected response: {:?}", response))
+}
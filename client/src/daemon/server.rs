@@ -0,0 +1,239 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    fs,
+    io::ErrorKind,
+    os::unix::{
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+use crate::{
+    daemon::{authentication_mac, random_bytes, read_message, write_message, Request, Response},
+    Client, ClientError, DaemonError, KeyProvider, SnapshotPath, Stronghold,
+};
+
+/// Default maximum number of concurrent connections of a [`Daemon`].
+pub const DEFAULT_MAX_CONNECTIONS: usize = 64;
+
+/// Time a connection has to answer the authentication challenge.
+const AUTHENTICATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serves the clients of a [`Stronghold`] to other processes over a Unix domain socket.
+///
+/// # Example
+#[derive(Clone)]
+pub struct Daemon {
+    inner: Arc<DaemonInner>,
+}
+
+struct DaemonInner {
+    stronghold: Stronghold,
+    keyprovider: KeyProvider,
+    snapshot_path: SnapshotPath,
+    token: Vec<u8>,
+
+    // Serializes loading and creating clients of new sessions
+    clients: Mutex<()>,
+
+    // Number of open connections and the maximum number of concurrent connections
+    connections: AtomicUsize,
+    max_connections: AtomicUsize,
+}
+
+/// Slot of an open connection, which is released when the connection is closed.
+struct Connection(Arc<DaemonInner>);
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.0.connections.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Drop for DaemonInner {
+    fn drop(&mut self) {
+        self.token.zeroize();
+    }
+}
+
+impl Daemon {
+    /// Creates a new [`Daemon`] for `stronghold`. Sessions are authenticated with `token`, commits are written to
+    /// `snapshot_path` with the key of `keyprovider`.
+    pub fn new(stronghold: Stronghold, keyprovider: KeyProvider, snapshot_path: SnapshotPath, token: Vec<u8>) -> Self {
+        Daemon {
+            inner: Arc::new(DaemonInner {
+                stronghold,
+                keyprovider,
+                snapshot_path,
+                token,
+                clients: Mutex::new(()),
+                connections: AtomicUsize::new(0),
+                max_connections: AtomicUsize::new(DEFAULT_MAX_CONNECTIONS),
+            }),
+        }
+    }
+
+    /// Sets the maximum number of concurrent connections. Further connections are rejected with
+    /// [`DaemonError::TooManyConnections`]. Defaults to [`DEFAULT_MAX_CONNECTIONS`].
+    pub fn set_max_connections(&self, max_connections: usize) {
+        self.inner.max_connections.store(max_connections, Ordering::SeqCst);
+    }
+
+    /// Binds the socket at `socket_path` and serves incoming connections, each on its own thread, up to the maximum
+    /// number of concurrent connections.
+    /// A stale socket file at `socket_path` is replaced. The socket is only accessible by the owner.
+    ///
+    /// # Example
+    pub fn serve<P: AsRef<Path>>(&self, socket_path: P) -> Result<(), DaemonError> {
+        let listener = self.bind(socket_path)?;
+        self.serve_listener(listener)
+    }
+
+    /// Binds the socket at `socket_path`, see [`Daemon::serve`].
+    pub fn bind<P: AsRef<Path>>(&self, socket_path: P) -> Result<UnixListener, DaemonError> {
+        let socket_path = socket_path.as_ref();
+        match fs::remove_file(socket_path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        let listener = UnixListener::bind(socket_path)?;
+        fs::set_permissions(socket_path, fs::Permissions::from_mode(0o600))?;
+        Ok(listener)
+    }
+
+    /// Serves incoming connections of `listener`, each on its own thread, up to the maximum number of concurrent
+    /// connections.
+    pub fn serve_listener(&self, listener: UnixListener) -> Result<(), DaemonError> {
+        for stream in listener.incoming() {
+            // A failed accept, e.g. because the process ran out of file descriptors, only affects this connection.
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::warn!("accepting a daemon connection failed: {}", e);
+                    continue;
+                }
+            };
+            let connection = match self.open_connection() {
+                Some(connection) => connection,
+                None => {
+                    let _ = write_message(&mut stream, &Response::Error(DaemonError::TooManyConnections));
+                    continue;
+                }
+            };
+            let daemon = self.clone();
+            thread::spawn(move || {
+                let _connection = connection;
+                let _ = daemon.handle_connection(stream);
+            });
+        }
+        Ok(())
+    }
+
+    /// Runs a session on `stream` until the peer closes the connection. The peer has to authenticate within
+    /// a timeout.
+    pub fn handle_connection(&self, mut stream: UnixStream) -> Result<(), DaemonError> {
+        stream.set_read_timeout(Some(AUTHENTICATION_TIMEOUT))?;
+        let client = match self.authenticate(&mut stream)? {
+            Some(client) => client,
+            None => return Ok(()),
+        };
+        stream.set_read_timeout(None)?;
+
+        while let Some(request) = read_message::<_, Request>(&mut stream)? {
+            let response = match self.handle_request(&client, request) {
+                Ok(response) => response,
+                Err(e) => Response::Error(e),
+            };
+            write_message(&mut stream, &response)?;
+        }
+        Ok(())
+    }
+
+    fn authenticate(&self, stream: &mut UnixStream) -> Result<Option<Client>, DaemonError> {
+        let challenge = random_bytes()?;
+        write_message(stream, &Response::Challenge(challenge.clone()))?;
+
+        let (client_path, mac) = match read_message::<_, Request>(stream)? {
+            Some(Request::Authenticate { client_path, mac }) => (client_path, mac),
+            Some(_) => {
+                write_message(stream, &Response::Error(DaemonError::Unauthenticated))?;
+                return Err(DaemonError::Unauthenticated);
+            }
+            None => return Ok(None),
+        };
+
+        let expected = authentication_mac(&self.inner.token, &challenge, &client_path);
+        if !bool::from(expected.ct_eq(&mac)) {
+            write_message(stream, &Response::Error(DaemonError::AuthenticationFailed))?;
+            return Err(DaemonError::AuthenticationFailed);
+        }
+
+        let client = match self.open_client(&client_path) {
+            Ok(client) => client,
+            Err(e) => {
+                let e = DaemonError::from(e);
+                write_message(stream, &Response::Error(e.clone()))?;
+                return Err(e);
+            }
+        };
+        write_message(stream, &Response::Authenticated)?;
+        Ok(Some(client))
+    }
+
+    /// Reserves the slot of a new connection, if the maximum number of concurrent connections is not reached.
+    fn open_connection(&self) -> Option<Connection> {
+        let max_connections = self.inner.max_connections.load(Ordering::SeqCst);
+        self.inner
+            .connections
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |open| {
+                (open < max_connections).then_some(open + 1)
+            })
+            .ok()
+            .map(|_| Connection(self.inner.clone()))
+    }
+
+    /// Returns the client at `client_path`, loading it from the snapshot or creating it if necessary.
+    fn open_client(&self, client_path: &[u8]) -> Result<Client, ClientError> {
+        let _guard = self.inner.clients.lock()?;
+        let stronghold = &self.inner.stronghold;
+        if let Ok(client) = stronghold.get_client(client_path) {
+            return Ok(client);
+        }
+        match stronghold.load_client(client_path) {
+            Err(ClientError::ClientDataNotPresent) => stronghold.create_client(client_path),
+            result => result,
+        }
+    }
+
+    fn handle_request(&self, client: &Client, request: Request) -> Result<Response, DaemonError> {
+        match request {
+            Request::Authenticate { .. } => Err(DaemonError::InvalidMessage(
+                "session has already been authenticated".to_string(),
+            )),
+            Request::Procedures(procedures) => {
+                let outputs = client.execute_procedure_chained(procedures)?;
+                Ok(Response::Outputs(outputs))
+            }
+            Request::VaultExists(vault_path) => Ok(Response::Exists(client.vault_exists(vault_path)?)),
+            Request::RecordExists(location) => Ok(Response::Exists(client.record_exists(&location)?)),
+            Request::Commit => {
+                self.inner
+                    .stronghold
+                    .commit_with_keyprovider(&self.inner.snapshot_path, &self.inner.keyprovider)?;
+                Ok(Response::Committed)
+            }
+        }
+    }
+}
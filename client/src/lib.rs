@@ -14,6 +14,9 @@ pub use crate::{internal::Provider, security::*, types::*, utils::*};
 #[cfg(feature = "std")]
 pub use engine::runtime::MemoryError;
 
+#[cfg(feature = "std")]
+pub use engine::snapshot::{SnapshotHeader, SnapshotKdf};
+
 #[cfg(feature = "std")]
 pub(crate) use crate::sync::SnapshotHierarchy;
 
@@ -32,6 +35,9 @@ pub mod procedures;
 #[cfg(feature = "std")]
 pub mod sync;
 
+#[cfg(all(feature = "std", feature = "daemon", unix))]
+pub mod daemon;
+
 // is this std?
 #[cfg(feature = "std")]
 pub mod utils;
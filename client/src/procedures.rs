@@ -1,22 +1,44 @@
 // Copyright 2020-2021 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+mod ciphers;
 mod clientrunner;
+mod hpke;
+mod otp;
+mod password;
 mod primitives;
+mod shamir;
+mod slip39;
+mod transfer;
 mod types;
 
+pub use ciphers::{Aes256GcmSiv, ChaCha20Poly1305};
 pub use clientrunner::*;
+pub use hpke::{HpkeAead, HpkeOpen, HpkeSeal, HPKE_ENC_LENGTH};
+pub use otp::{
+    HotpGenerate, OtpAuthUri, OtpHash, OtpKind, TotpGenerate, OTP_DEFAULT_DIGITS, OTP_MAX_DIGITS, OTP_MIN_DIGITS,
+    TOTP_DEFAULT_PERIOD,
+};
+pub use password::{Argon2Derive, Argon2Params, Argon2Password, Argon2Verify, PepperedHash, PepperedHashVerify};
+pub use shamir::{decrypt_share, EncryptedShares, ShamirCombine, ShamirShares, ShamirSplit, ShareEncoding};
+pub use transfer::{ExportRecord, ImportRecord, RECORD_ENVELOPE_MAGIC, RECORD_ENVELOPE_VERSION};
 
 #[cfg(feature = "insecure")]
 pub use primitives::CompareSecret;
 
 pub use primitives::{
-    AeadCipher, AeadDecrypt, AeadEncrypt, AesKeyWrapCipher, AesKeyWrapDecrypt, AesKeyWrapEncrypt, BIP39Generate,
-    BIP39Recover, Chain, ChainCode, ConcatKdf, ConcatSecret, CopyRecord, Ed25519Sign, GarbageCollect, GenerateKey,
-    Hkdf, Hmac, KeyType, MnemonicLanguage, Pbkdf2Hmac, PublicKey, RevokeData, Sha2Hash, Slip10Derive,
-    Slip10DeriveInput, Slip10Generate, StrongholdProcedure, WriteVault, X25519DiffieHellman,
+    AeadCipher, AeadDecrypt, AeadEncrypt, AeadNonce, AesKeyWrapCipher, AesKeyWrapDecrypt, AesKeyWrapEncrypt,
+    BIP39Generate, BIP39Recover, Bip32Derive, Chain, ChainCode, ConcatKdf, ConcatSecret, CopyRecord, Ed25519Sign,
+    Ed25519SignBatch, Ed25519SignPrehashed, Ed25519Signatures, Ed25519Verify, Ed25519VerifyPrehashed, GarbageCollect,
+    GenerateKey, Hkdf, Hmac, HmacVerify, KeyType, MnemonicLanguage, Pbkdf2Hmac, PublicKey, RevokeData,
+    Secp256k1EcdsaPublicKey, Secp256k1EcdsaPublicKeyEncoding, Secp256k1EcdsaSign, Secp256k1EcdsaVerify, Sha2Hash,
+    Slip10Derive, Slip10DeriveInput, Slip10Generate, StrongholdProcedure, StrongholdProcedurePermission, WriteVault,
+    X25519DiffieHellman, BIP32_EXTENDED_KEY_LENGTH, ED25519PH_MAX_CONTEXT_LENGTH, ED25519PH_PREHASH_LENGTH,
+    SECP256K1_ECDSA_EVM_ADDRESS_LENGTH, SECP256K1_ECDSA_PREHASH_LENGTH, SECP256K1_ECDSA_SECRET_KEY_LENGTH,
+    SECP256K1_ECDSA_SIGNATURE_LENGTH,
 };
 pub use types::{
-    DeriveSecret, FatalProcedureError, GenerateSecret, Procedure, ProcedureError, ProcedureOutput, UseSecret,
+    DeriveSecret, FatalProcedureError, GenerateSecret, PolicyError, Procedure, ProcedureError, ProcedureOutput,
+    UseSecret,
 };
 pub(crate) use types::{Products, Runner};
@@ -0,0 +1,189 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! AEAD ciphers that are not provided by `iota-crypto`, implementing its [`Aead`] trait so that they can be used
+//! interchangeably with [`crypto::ciphers::aes_gcm::Aes256Gcm`] and [`crypto::ciphers::chacha::XChaCha20Poly1305`].
+
+use crypto::ciphers::traits::{
+    consts::{U12, U16, U32},
+    Aead, Key, Nonce, Tag,
+};
+
+/// AES-256-GCM-SIV as defined in RFC 8452.
+///
+/// In contrast to AES-GCM, the reuse of a nonce with the same key only reveals whether the same message has been
+/// encrypted, but neither the authentication key nor the XOR of the plaintexts.
+pub struct Aes256GcmSiv;
+
+impl Aead for Aes256GcmSiv {
+    type KeyLength = U32;
+    type NonceLength = U12;
+    type TagLength = U16;
+
+    const NAME: &'static str = "AES-256-GCM-SIV";
+
+    fn encrypt(
+        key: &Key<Self>,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        plaintext: &[u8],
+        ciphertext: &mut [u8],
+        tag: &mut Tag<Self>,
+    ) -> crypto::Result<usize> {
+        use aes_gcm_siv::aead::{AeadInPlace, KeyInit};
+
+        if plaintext.len() > ciphertext.len() {
+            return Err(crypto::Error::BufferSize {
+                name: "ciphertext",
+                needs: plaintext.len(),
+                has: ciphertext.len(),
+            });
+        }
+        let ciphertext = &mut ciphertext[..plaintext.len()];
+        ciphertext.copy_from_slice(plaintext);
+        let out = aes_gcm_siv::Aes256GcmSiv::new(key)
+            .encrypt_in_place_detached(nonce, associated_data, ciphertext)
+            .map_err(|_| crypto::Error::CipherError { alg: Self::NAME })?;
+        tag.copy_from_slice(&out);
+        Ok(plaintext.len())
+    }
+
+    fn decrypt(
+        key: &Key<Self>,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        plaintext: &mut [u8],
+        ciphertext: &[u8],
+        tag: &Tag<Self>,
+    ) -> crypto::Result<usize> {
+        use aes_gcm_siv::aead::{AeadInPlace, KeyInit};
+
+        if ciphertext.len() > plaintext.len() {
+            return Err(crypto::Error::BufferSize {
+                name: "plaintext",
+                needs: ciphertext.len(),
+                has: plaintext.len(),
+            });
+        }
+        let plaintext = &mut plaintext[..ciphertext.len()];
+        plaintext.copy_from_slice(ciphertext);
+        aes_gcm_siv::Aes256GcmSiv::new(key)
+            .decrypt_in_place_detached(nonce, associated_data, plaintext, tag)
+            .map_err(|_| crypto::Error::CipherError { alg: Self::NAME })?;
+        Ok(ciphertext.len())
+    }
+}
+
+/// ChaCha20Poly1305 with a 96 bit nonce, as defined in RFC 8439.
+pub struct ChaCha20Poly1305;
+
+impl Aead for ChaCha20Poly1305 {
+    type KeyLength = U32;
+    type NonceLength = U12;
+    type TagLength = U16;
+
+    const NAME: &'static str = "ChaCha20Poly1305";
+
+    fn encrypt(
+        key: &Key<Self>,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        plaintext: &[u8],
+        ciphertext: &mut [u8],
+        tag: &mut Tag<Self>,
+    ) -> crypto::Result<usize> {
+        use chacha20poly1305::aead::{AeadInPlace, KeyInit};
+
+        if plaintext.len() > ciphertext.len() {
+            return Err(crypto::Error::BufferSize {
+                name: "ciphertext",
+                needs: plaintext.len(),
+                has: ciphertext.len(),
+            });
+        }
+        let ciphertext = &mut ciphertext[..plaintext.len()];
+        ciphertext.copy_from_slice(plaintext);
+        let out = chacha20poly1305::ChaCha20Poly1305::new(key)
+            .encrypt_in_place_detached(nonce, associated_data, ciphertext)
+            .map_err(|_| crypto::Error::CipherError { alg: Self::NAME })?;
+        tag.copy_from_slice(&out);
+        Ok(plaintext.len())
+    }
+
+    fn decrypt(
+        key: &Key<Self>,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        plaintext: &mut [u8],
+        ciphertext: &[u8],
+        tag: &Tag<Self>,
+    ) -> crypto::Result<usize> {
+        use chacha20poly1305::aead::{AeadInPlace, KeyInit};
+
+        if ciphertext.len() > plaintext.len() {
+            return Err(crypto::Error::BufferSize {
+                name: "plaintext",
+                needs: ciphertext.len(),
+                has: plaintext.len(),
+            });
+        }
+        let plaintext = &mut plaintext[..ciphertext.len()];
+        plaintext.copy_from_slice(ciphertext);
+        chacha20poly1305::ChaCha20Poly1305::new(key)
+            .decrypt_in_place_detached(nonce, associated_data, plaintext, tag)
+            .map_err(|_| crypto::Error::CipherError { alg: Self::NAME })?;
+        Ok(ciphertext.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_vector<A: Aead>(key: &str, nonce: &str, aad: &str, plaintext: &str, result: &str) {
+        let (key, nonce, aad, plaintext) = (
+            hex::decode(key).unwrap(),
+            hex::decode(nonce).unwrap(),
+            hex::decode(aad).unwrap(),
+            hex::decode(plaintext).unwrap(),
+        );
+        let mut ciphertext = vec![0; plaintext.len()];
+        let mut tag = vec![0; A::TAG_LENGTH];
+        A::try_encrypt(&key, &nonce, &aad, &plaintext, &mut ciphertext, &mut tag).unwrap();
+        assert_eq!(hex::encode([&ciphertext[..], &tag].concat()), result);
+
+        let mut decrypted = vec![0; ciphertext.len()];
+        A::try_decrypt(&key, &nonce, &aad, &mut decrypted, &ciphertext, &tag).unwrap();
+        assert_eq!(decrypted, plaintext);
+
+        tag[0] ^= 1;
+        assert!(A::try_decrypt(&key, &nonce, &aad, &mut decrypted, &ciphertext, &tag).is_err());
+    }
+
+    #[test]
+    fn test_aes256_gcm_siv_vectors() {
+        // RFC 8452, C.2: AEAD_AES_256_GCM_SIV
+        let key = "0100000000000000000000000000000000000000000000000000000000000000";
+        let nonce = "030000000000000000000000";
+        check_vector::<Aes256GcmSiv>(key, nonce, "", "", "07f5f4169bbf55a8400cd47ea6fd400f");
+        check_vector::<Aes256GcmSiv>(
+            key,
+            nonce,
+            "",
+            "0100000000000000",
+            "c2ef328e5c71c83b843122130f7364b761e0b97427e3df28",
+        );
+    }
+
+    #[test]
+    fn test_chacha20_poly1305_vector() {
+        // RFC 8439, 2.8.2: Example and Test Vector for AEAD_CHACHA20_POLY1305
+        check_vector::<ChaCha20Poly1305>(
+            "808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f",
+            "070000004041424344454647",
+            "50515253c0c1c2c3c4c5c6c7",
+            "4c616469657320616e642047656e746c656d656e206f662074686520636c617373206f66202739393a204966204920636f756c64206f6666657220796f75206f6e6c79206f6e652074697020666f7220746865206675747572652c2073756e73637265656e20776f756c642062652069742e",
+            "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d63dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b3692ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc3ff4def08e4b7a9de576d26586cec64b61161ae10b594f09e26a7e902ecbd0600691",
+        );
+    }
+}
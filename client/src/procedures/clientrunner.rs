@@ -3,7 +3,8 @@
 
 use std::{
     error::Error,
-    sync::{Arc, RwLock},
+    sync::{atomic::Ordering, Arc, RwLock},
+    time::SystemTime,
 };
 
 use engine::{
@@ -14,9 +15,11 @@ use engine::{
 use crate::{
     derive_vault_id,
     procedures::{
-        FatalProcedureError, Procedure, ProcedureError, ProcedureOutput, Products, Runner, StrongholdProcedure,
+        FatalProcedureError, KeyType, PolicyError, Procedure, ProcedureError, ProcedureOutput, Products, Runner,
+        StrongholdProcedure,
     },
-    Client, ClientError, ClientVault, KeyStore, Location, Provider, RecordError, Store, VaultError,
+    Client, ClientError, ClientVault, KeyStore, KeyUsage, Location, Provider, RecordError, RecordMetadata,
+    RecordPolicy, Store, VaultError,
 };
 use stronghold_utils::random as rand;
 pub const DEFAULT_RANDOM_HINT_SIZE: usize = 24;
@@ -45,6 +48,320 @@ macro_rules! resolve_locations {
 // ported [`Runner`] impl for [`Client`]
 impl Runner for Client {
     fn get_guards<F, T, const N: usize>(
+        &self,
+        locations: [Location; N],
+        usage: [KeyUsage; N],
+        f: F,
+    ) -> Result<T, ProcedureError>
+    where
+        F: FnOnce([Buffer<u8>; N]) -> Result<T, FatalProcedureError>,
+    {
+        let policy_locations = locations.clone();
+        self.enforce_policies(&policy_locations, &usage, || {
+            Ok(self.get_guards_unchecked(locations, f)?)
+        })
+    }
+
+    fn exec_proc<F, T, const N: usize>(
+        &self,
+        source_locations: [Location; N],
+        usage: [KeyUsage; N],
+        target_location: &Location,
+        key_type: Option<KeyType>,
+        f: F,
+    ) -> Result<T, ProcedureError>
+    where
+        F: FnOnce([Buffer<u8>; N]) -> Result<Products<T>, FatalProcedureError>,
+    {
+        let policy_locations = source_locations.clone();
+        self.enforce_policies(&policy_locations, &usage, || {
+            Ok(self.exec_proc_unchecked(source_locations, target_location, key_type, None, f)?)
+        })
+    }
+
+    fn write_to_vault(&self, location: &Location, value: Vec<u8>) -> Result<(), RecordError> {
+        self.write_to_vault_with_policy(location, value, None)
+    }
+
+    fn record_info(&self, location: &Location) -> Result<(RecordHint, Option<RecordMetadata>), RecordError> {
+        let (vault_id, record_id) = location.resolve();
+
+        let keystore = self.keystore.read().map_err(|_| RecordError::LockPoisoned)?;
+        let db = self.db.read().map_err(|_| RecordError::LockPoisoned)?;
+
+        let key = keystore
+            .get_key(vault_id)
+            .ok_or_else(|| RecordError::RecordNotFound(record_id.into()))?;
+        let hint = db.get_hint(&key, vault_id, record_id)?;
+        let metadata = RecordMetadata::from_bytes(&db.get_metadata(&key, vault_id, record_id)?)?;
+        Ok((hint, metadata))
+    }
+
+    fn write_record(
+        &self,
+        location: &Location,
+        value: Vec<u8>,
+        hint: RecordHint,
+        metadata: RecordMetadata,
+    ) -> Result<(), RecordError> {
+        let (vault_id, record_id) = location.resolve();
+
+        let mut keystore = self.keystore.write().map_err(|_| RecordError::LockPoisoned)?;
+        let mut db = self.db.write().map_err(|_| RecordError::LockPoisoned)?;
+
+        if !keystore.vault_exists(vault_id) {
+            let key = keystore.create_key(vault_id).map_err(|_| RecordError::InvalidKey)?;
+            db.init_vault(&key, vault_id);
+        }
+        let mut metadata = metadata;
+        metadata.record_path = self.indexed_record_path(location).map(|path| path.to_vec());
+        let key = keystore.take_key(vault_id).unwrap();
+        let res = db
+            .write(&key, vault_id, record_id, &value, hint)
+            .and_then(|_| db.set_metadata(&key, vault_id, record_id, &metadata.to_bytes()));
+
+        // this should return an error
+        keystore
+            .get_or_insert_key(vault_id, key)
+            .expect("Inserting key into vault failed");
+        res
+    }
+
+    fn next_nonce_counter(&self, location: &Location, counter: Option<u64>) -> Result<u64, ProcedureError> {
+        let (vault_id, record_id) = location.resolve();
+
+        // The counter is checked and raised under the same lock, so that no counter is used twice.
+        let keystore = self.keystore.read().map_err(|_| RecordError::LockPoisoned)?;
+        let mut db = self.db.write().map_err(|_| RecordError::LockPoisoned)?;
+
+        let key = keystore
+            .get_key(vault_id)
+            .ok_or_else(|| RecordError::RecordNotFound(record_id.into()))?;
+        let mut metadata = RecordMetadata::from_bytes(&db.get_metadata(&key, vault_id, record_id)?)?
+            .unwrap_or_else(|| RecordMetadata::new(None));
+        if metadata.policy.usage.contains(KeyUsage::EXPORT) {
+            return Err(PolicyError::Exportable.into());
+        }
+        let counter = counter.unwrap_or(metadata.next_nonce_counter);
+        if counter < metadata.next_nonce_counter {
+            return Err(FatalProcedureError::from(format!(
+                "nonce counter {} has already been used with this key",
+                counter
+            ))
+            .into());
+        }
+        metadata.next_nonce_counter = counter
+            .checked_add(1)
+            .ok_or_else(|| FatalProcedureError::from("nonce counter is exhausted".to_string()))?;
+        db.set_metadata(&key, vault_id, record_id, &metadata.to_bytes())?;
+        Ok(counter)
+    }
+
+    fn revoke_data(&self, location: &Location) -> Result<(), RecordError> {
+        let (vault_id, record_id) = location.resolve();
+
+        let mut keystore = self.keystore.write().map_err(|_| RecordError::LockPoisoned)?;
+        let mut db = self.db.write().map_err(|_| RecordError::LockPoisoned)?;
+
+        if let Some(key) = keystore.take_key(vault_id) {
+            let res = db.revoke_record(&key, vault_id, record_id);
+
+            // this should return an error
+            keystore
+                .get_or_insert_key(vault_id, key)
+                .expect("Inserting key into vault failed");
+            res?;
+        }
+        Ok(())
+    }
+
+    fn garbage_collect(&self, vault_id: VaultId) -> Result<bool, VaultError<FatalProcedureError>> {
+        let mut keystore = self.keystore.write().map_err(|_| VaultError::LockPoisoned)?;
+        let mut db = self.db.write().map_err(|_| VaultError::LockPoisoned)?;
+
+        let key = match keystore.take_key(vault_id) {
+            Some(key) => key,
+            None => return Ok(false),
+        };
+        db.garbage_collect_vault(&key, vault_id);
+        keystore
+            .get_or_insert_key(vault_id, key)
+            .expect("Inserting key into vault failed");
+        Ok(true)
+    }
+}
+
+/// [`Runner`] that attaches a [`RecordPolicy`] to every record written by a procedure.
+pub(crate) struct PolicyRunner<'a> {
+    client: &'a Client,
+    policy: RecordPolicy,
+}
+
+impl<'a> PolicyRunner<'a> {
+    pub(crate) fn new(client: &'a Client, policy: RecordPolicy) -> Self {
+        PolicyRunner { client, policy }
+    }
+}
+
+impl<'a> Runner for PolicyRunner<'a> {
+    fn get_guards<F, T, const N: usize>(
+        &self,
+        locations: [Location; N],
+        usage: [KeyUsage; N],
+        f: F,
+    ) -> Result<T, ProcedureError>
+    where
+        F: FnOnce([Buffer<u8>; N]) -> Result<T, FatalProcedureError>,
+    {
+        self.client.get_guards(locations, usage, f)
+    }
+
+    fn exec_proc<F, T, const N: usize>(
+        &self,
+        source_locations: [Location; N],
+        usage: [KeyUsage; N],
+        target_location: &Location,
+        key_type: Option<KeyType>,
+        f: F,
+    ) -> Result<T, ProcedureError>
+    where
+        F: FnOnce([Buffer<u8>; N]) -> Result<Products<T>, FatalProcedureError>,
+    {
+        let policy_locations = source_locations.clone();
+        self.client.enforce_policies(&policy_locations, &usage, || {
+            Ok(self
+                .client
+                .exec_proc_unchecked(source_locations, target_location, key_type, Some(&self.policy), f)?)
+        })
+    }
+
+    fn write_to_vault(&self, location: &Location, value: Vec<u8>) -> Result<(), RecordError> {
+        self.client
+            .write_to_vault_with_policy(location, value, Some(&self.policy))
+    }
+
+    fn record_info(&self, location: &Location) -> Result<(RecordHint, Option<RecordMetadata>), RecordError> {
+        self.client.record_info(location)
+    }
+
+    fn write_record(
+        &self,
+        location: &Location,
+        value: Vec<u8>,
+        hint: RecordHint,
+        mut metadata: RecordMetadata,
+    ) -> Result<(), RecordError> {
+        metadata.policy = self.policy.clone();
+        metadata.uses = 0;
+        self.client.write_record(location, value, hint, metadata)
+    }
+
+    fn next_nonce_counter(&self, location: &Location, counter: Option<u64>) -> Result<u64, ProcedureError> {
+        self.client.next_nonce_counter(location, counter)
+    }
+
+    fn revoke_data(&self, location: &Location) -> Result<(), RecordError> {
+        self.client.revoke_data(location)
+    }
+
+    fn garbage_collect(&self, vault_id: VaultId) -> Result<bool, VaultError<FatalProcedureError>> {
+        self.client.garbage_collect(vault_id)
+    }
+}
+
+/// Reads the metadata of the record at `location`. Missing vaults and records are reported when the record is accessed,
+/// `None` is returned for them as well as for records without metadata.
+fn read_metadata(
+    keystore: &KeyStore<Provider>,
+    db: &DbView<Provider>,
+    location: &Location,
+) -> Result<Option<(Key<Provider>, RecordMetadata)>, ProcedureError> {
+    let (vault_id, record_id) = location.resolve();
+    let key = match keystore.get_key(vault_id) {
+        Some(key) if db.contains_record(vault_id, record_id) => key,
+        _ => return Ok(None),
+    };
+    let metadata = RecordMetadata::from_bytes(&db.get_metadata(&key, vault_id, record_id)?)?;
+    Ok(metadata.map(|metadata| (key, metadata)))
+}
+
+impl Client {
+    /// Checks the [`RecordPolicy`] of each record at `locations` for the respective `usage` and runs `f` if all
+    /// policies permit the operation. Uses of records with a limited number of uses are counted before `f` runs, so
+    /// that concurrent operations can't exceed the limit, and are given back if `f` fails.
+    pub(crate) fn enforce_policies<T, E>(
+        &self,
+        locations: &[Location],
+        usage: &[KeyUsage],
+        f: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E>
+    where
+        E: From<ProcedureError>,
+    {
+        let counted = self.count_uses(locations, usage)?;
+        let output = f();
+        if output.is_err() && !counted.is_empty() {
+            // the operation failed, the error of `f` is reported instead of a failure to give back the uses.
+            let _ = self.give_back_uses(&counted);
+        }
+        output
+    }
+
+    /// Checks the policies of the records at `locations` and counts one use of each record with a limited number of
+    /// uses. The write lock on the records is only taken if such a record exists. Returns the counted locations.
+    fn count_uses(&self, locations: &[Location], usage: &[KeyUsage]) -> Result<Vec<Location>, ProcedureError> {
+        let keystore = self.keystore.read().map_err(|_| RecordError::LockPoisoned)?;
+        let now = SystemTime::now();
+
+        let mut limited = Vec::new();
+        {
+            let db = self.db.read().map_err(|_| RecordError::LockPoisoned)?;
+            for (location, usage) in locations.iter().zip(usage) {
+                if let Some((_, metadata)) = read_metadata(&keystore, &db, location)? {
+                    metadata.policy.check(*usage, metadata.uses, now)?;
+                    if metadata.policy.max_uses.is_some() {
+                        limited.push((location, *usage));
+                    }
+                }
+            }
+        }
+        if limited.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // the records may have been used since the read lock has been released, their policies are checked again.
+        let mut db = self.db.write().map_err(|_| RecordError::LockPoisoned)?;
+        let mut counted = Vec::with_capacity(limited.len());
+        for (location, usage) in limited {
+            if let Some((key, mut metadata)) = read_metadata(&keystore, &db, location)? {
+                metadata.policy.check(usage, metadata.uses, now)?;
+                metadata.uses += 1;
+                counted.push((location, key, metadata));
+            }
+        }
+        for (location, key, metadata) in &counted {
+            let (vault_id, record_id) = location.resolve();
+            db.set_metadata(key, vault_id, record_id, &metadata.to_bytes())?;
+        }
+        Ok(counted.into_iter().map(|(location, ..)| location.clone()).collect())
+    }
+
+    /// Gives back the uses counted by [`Client::count_uses`] after the operation on the records failed.
+    fn give_back_uses(&self, locations: &[Location]) -> Result<(), ProcedureError> {
+        let keystore = self.keystore.read().map_err(|_| RecordError::LockPoisoned)?;
+        let mut db = self.db.write().map_err(|_| RecordError::LockPoisoned)?;
+        for location in locations {
+            if let Some((key, mut metadata)) = read_metadata(&keystore, &db, location)? {
+                metadata.uses = metadata.uses.saturating_sub(1);
+                let (vault_id, record_id) = location.resolve();
+                db.set_metadata(&key, vault_id, record_id, &metadata.to_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies `f` to the buffers from the given `locations` without checking the policies of the records.
+    fn get_guards_unchecked<F, T, const N: usize>(
         &self,
         locations: [Location; N],
         f: F,
@@ -70,10 +387,14 @@ impl Runner for Client {
         }
     }
 
-    fn exec_proc<F, T, const N: usize>(
+    /// Executes `f` on the secrets at `source_locations` without checking the policies of the records, and writes
+    /// the produced secret to `target_location`. If `policy` is set, it replaces the policy of the target record.
+    fn exec_proc_unchecked<F, T, const N: usize>(
         &self,
         source_locations: [Location; N],
         target_location: &Location,
+        key_type: Option<KeyType>,
+        policy: Option<&RecordPolicy>,
         f: F,
     ) -> Result<T, VaultError<FatalProcedureError>>
     where
@@ -106,22 +427,35 @@ impl Runner for Client {
             .get_key(target_vid)
             .ok_or(VaultError::VaultNotFound(target_vid))?;
 
-        let res = db.exec_procedure(
+        db.exec_procedure(
             sources,
             &target_key,
             target_vid,
             target_rid,
             random_hint,
             execute_procedure,
-        );
+        )?;
+        let record_path = self.indexed_record_path(target_location);
+        update_metadata(
+            &mut db,
+            &target_key,
+            target_vid,
+            target_rid,
+            key_type,
+            policy,
+            record_path,
+        )?;
 
-        match res {
-            Ok(()) => Ok(ret.unwrap()),
-            Err(e) => Err(e),
-        }
+        Ok(ret.unwrap())
     }
 
-    fn write_to_vault(&self, location: &Location, value: Vec<u8>) -> Result<(), RecordError> {
+    /// Writes `value` to `location`. If `policy` is set, it replaces the policy of the record.
+    fn write_to_vault_with_policy(
+        &self,
+        location: &Location,
+        value: Vec<u8>,
+        policy: Option<&RecordPolicy>,
+    ) -> Result<(), RecordError> {
         let (vault_id, record_id) = location.resolve();
 
         let mut keystore = self.keystore.write().map_err(|_| RecordError::LockPoisoned)?;
@@ -133,8 +467,11 @@ impl Runner for Client {
             db.init_vault(&key, vault_id);
         }
         let random_hint = RecordHint::new(rand::variable_bytestring(DEFAULT_RANDOM_HINT_SIZE)).unwrap();
+        let record_path = self.indexed_record_path(location);
         let key = keystore.take_key(vault_id).unwrap();
-        let res = db.write(&key, vault_id, record_id, &value, random_hint);
+        let res = db
+            .write(&key, vault_id, record_id, &value, random_hint)
+            .and_then(|_| update_metadata(&mut db, &key, vault_id, record_id, None, policy, record_path));
 
         // this should return an error
         keystore
@@ -143,41 +480,16 @@ impl Runner for Client {
         res
     }
 
-    fn revoke_data(&self, location: &Location) -> Result<(), RecordError> {
-        let (vault_id, record_id) = location.resolve();
-
-        let mut keystore = self.keystore.write().map_err(|_| RecordError::LockPoisoned)?;
-        let mut db = self.db.write().map_err(|_| RecordError::LockPoisoned)?;
-
-        if let Some(key) = keystore.take_key(vault_id) {
-            let res = db.revoke_record(&key, vault_id, record_id);
-
-            // this should return an error
-            keystore
-                .get_or_insert_key(vault_id, key)
-                .expect("Inserting key into vault failed");
-            res?;
+    /// Returns the record path of `location` if it should be stored in the record path index.
+    fn indexed_record_path<'a>(&self, location: &'a Location) -> Option<&'a [u8]> {
+        match location {
+            Location::Generic { record_path, .. } if self.record_path_index.load(Ordering::Relaxed) => {
+                Some(record_path)
+            }
+            _ => None,
         }
-        Ok(())
-    }
-
-    fn garbage_collect(&self, vault_id: VaultId) -> Result<bool, VaultError<FatalProcedureError>> {
-        let mut keystore = self.keystore.write().map_err(|_| VaultError::LockPoisoned)?;
-        let mut db = self.db.write().map_err(|_| VaultError::LockPoisoned)?;
-
-        let key = match keystore.take_key(vault_id) {
-            Some(key) => key,
-            None => return Ok(false),
-        };
-        db.garbage_collect_vault(&key, vault_id);
-        keystore
-            .get_or_insert_key(vault_id, key)
-            .expect("Inserting key into vault failed");
-        Ok(true)
     }
-}
 
-impl Client {
     /// Applies `f` to the buffer from the given `location`.
     pub(crate) fn get_guard<F, T>(&self, location: &Location, f: F) -> Result<T, VaultError<FatalProcedureError>>
     where
@@ -209,3 +521,29 @@ impl Client {
         }
     }
 }
+
+/// Updates the [`RecordMetadata`] of a record after its content has been written. If `policy` is set, it replaces
+/// the policy of the record and resets the number of uses. If `record_path` is set, it is stored in the record path
+/// index.
+fn update_metadata(
+    db: &mut DbView<Provider>,
+    key: &Key<Provider>,
+    vault_id: VaultId,
+    record_id: RecordId,
+    key_type: Option<KeyType>,
+    policy: Option<&RecordPolicy>,
+    record_path: Option<&[u8]>,
+) -> Result<(), RecordError> {
+    let mut metadata = match RecordMetadata::from_bytes(&db.get_metadata(key, vault_id, record_id)?)? {
+        Some(previous) => previous.updated(key_type),
+        None => RecordMetadata::new(key_type),
+    };
+    if let Some(policy) = policy {
+        metadata.policy = policy.clone();
+        metadata.uses = 0;
+    }
+    if let Some(record_path) = record_path {
+        metadata.record_path = Some(record_path.to_vec());
+    }
+    db.set_metadata(key, vault_id, record_id, &metadata.to_bytes())
+}
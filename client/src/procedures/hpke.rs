@@ -0,0 +1,411 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hybrid Public Key Encryption (HPKE, RFC 9180) with the KEM `DHKEM(X25519, HKDF-SHA256)` and the KDF
+//! `HKDF-SHA256`, in the base and the auth mode.
+//!
+//! [`HpkeSeal`] encrypts a single message to the X25519 public key of a recipient, [`HpkeOpen`] decrypts it with
+//! the X25519 secret key of the recipient in the vault. In the auth mode, the message is additionally authenticated
+//! by the X25519 key of the sender. The output of [`HpkeSeal`] is `enc || ciphertext`, where `enc` is the encapsulated
+//! ephemeral public key and `ciphertext` includes the tag of the AEAD, as used by other HPKE implementations.
+
+use aes_gcm::{Aes128Gcm, Aes256Gcm};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305,
+};
+use crypto::{hashes::sha::Sha256, keys::x25519};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use stronghold_utils::GuardDebug;
+use zeroize::Zeroizing;
+
+use super::types::*;
+use crate::{KeyUsage, Location};
+
+/// Length of the encapsulated key `enc` of `DHKEM(X25519, HKDF-SHA256)`.
+pub const HPKE_ENC_LENGTH: usize = x25519::PUBLIC_KEY_LENGTH;
+
+/// Identifier of the KEM `DHKEM(X25519, HKDF-SHA256)`.
+const KEM_ID: u16 = 0x0020;
+/// Identifier of the KDF `HKDF-SHA256`.
+const KDF_ID: u16 = 0x0001;
+/// Length of the shared secret of the KEM.
+const KEM_SECRET_LENGTH: usize = 32;
+/// Length of the nonce of all supported AEADs.
+const AEAD_NONCE_LENGTH: usize = 12;
+
+/// AEAD of an HPKE cipher suite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HpkeAead {
+    Aes128Gcm,
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+impl HpkeAead {
+    fn id(&self) -> u16 {
+        match self {
+            HpkeAead::Aes128Gcm => 0x0001,
+            HpkeAead::Aes256Gcm => 0x0002,
+            HpkeAead::ChaCha20Poly1305 => 0x0003,
+        }
+    }
+
+    fn key_length(&self) -> usize {
+        match self {
+            HpkeAead::Aes128Gcm => 16,
+            HpkeAead::Aes256Gcm | HpkeAead::ChaCha20Poly1305 => 32,
+        }
+    }
+
+    fn seal(&self, key: &[u8], nonce: &[u8], aad: &[u8], msg: &[u8]) -> Result<Vec<u8>, FatalProcedureError> {
+        let payload = Payload { msg, aad };
+        let nonce = nonce.into();
+        let ciphertext = match self {
+            HpkeAead::Aes128Gcm => Aes128Gcm::new(key.into()).encrypt(nonce, payload),
+            HpkeAead::Aes256Gcm => Aes256Gcm::new(key.into()).encrypt(nonce, payload),
+            HpkeAead::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.into()).encrypt(nonce, payload),
+        };
+        ciphertext.map_err(|_| "hpke: encryption failed".to_string().into())
+    }
+
+    fn open(&self, key: &[u8], nonce: &[u8], aad: &[u8], msg: &[u8]) -> Result<Vec<u8>, FatalProcedureError> {
+        let payload = Payload { msg, aad };
+        let nonce = nonce.into();
+        let plaintext = match self {
+            HpkeAead::Aes128Gcm => Aes128Gcm::new(key.into()).decrypt(nonce, payload),
+            HpkeAead::Aes256Gcm => Aes256Gcm::new(key.into()).decrypt(nonce, payload),
+            HpkeAead::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.into()).decrypt(nonce, payload),
+        };
+        plaintext.map_err(|_| "hpke: decryption failed".to_string().into())
+    }
+}
+
+/// `mode_base` and `mode_auth` of RFC 9180.
+#[derive(Clone, Copy)]
+enum Mode {
+    Base = 0x00,
+    Auth = 0x02,
+}
+
+/// `LabeledExtract` of RFC 9180.
+fn labeled_extract(suite_id: &[u8], salt: &[u8], label: &[u8], ikm: &[u8]) -> Zeroizing<Vec<u8>> {
+    let mut labeled_ikm = Zeroizing::new(Vec::new());
+    labeled_ikm.extend_from_slice(b"HPKE-v1");
+    labeled_ikm.extend_from_slice(suite_id);
+    labeled_ikm.extend_from_slice(label);
+    labeled_ikm.extend_from_slice(ikm);
+    let (prk, _) = Hkdf::<Sha256>::extract(Some(salt), &labeled_ikm);
+    Zeroizing::new(prk.to_vec())
+}
+
+/// `LabeledExpand` of RFC 9180.
+fn labeled_expand(
+    suite_id: &[u8],
+    prk: &[u8],
+    label: &[u8],
+    info: &[u8],
+    length: usize,
+) -> Result<Zeroizing<Vec<u8>>, FatalProcedureError> {
+    let mut labeled_info = Vec::new();
+    labeled_info.extend_from_slice(&(length as u16).to_be_bytes());
+    labeled_info.extend_from_slice(b"HPKE-v1");
+    labeled_info.extend_from_slice(suite_id);
+    labeled_info.extend_from_slice(label);
+    labeled_info.extend_from_slice(info);
+    let mut okm = Zeroizing::new(vec![0; length]);
+    Hkdf::<Sha256>::from_prk(prk)
+        .ok()
+        .and_then(|hkdf| hkdf.expand(&labeled_info, &mut okm).ok())
+        .ok_or_else(|| FatalProcedureError::from("hpke: invalid key derivation".to_string()))?;
+    Ok(okm)
+}
+
+fn kem_suite_id() -> Vec<u8> {
+    [&b"KEM"[..], &KEM_ID.to_be_bytes()].concat()
+}
+
+fn hpke_suite_id(aead: HpkeAead) -> Vec<u8> {
+    [
+        &b"HPKE"[..],
+        &KEM_ID.to_be_bytes(),
+        &KDF_ID.to_be_bytes(),
+        &aead.id().to_be_bytes(),
+    ]
+    .concat()
+}
+
+/// Diffie-Hellman, failing on the all-zero output of small order public keys.
+fn dh(sk: &x25519::SecretKey, pk: &x25519::PublicKey) -> Result<Zeroizing<Vec<u8>>, FatalProcedureError> {
+    let shared = Zeroizing::new(sk.diffie_hellman(pk).as_bytes().to_vec());
+    if shared.iter().all(|b| *b == 0) {
+        return Err("hpke: invalid public key".to_string().into());
+    }
+    Ok(shared)
+}
+
+/// `ExtractAndExpand` of the KEM.
+fn extract_and_expand(dh: &[u8], kem_context: &[u8]) -> Result<Zeroizing<Vec<u8>>, FatalProcedureError> {
+    let suite_id = kem_suite_id();
+    let eae_prk = labeled_extract(&suite_id, b"", b"eae_prk", dh);
+    labeled_expand(&suite_id, &eae_prk, b"shared_secret", kem_context, KEM_SECRET_LENGTH)
+}
+
+/// Key and nonce of a single-shot encryption context.
+struct Context {
+    key: Zeroizing<Vec<u8>>,
+    nonce: Zeroizing<Vec<u8>>,
+}
+
+/// `KeySchedule` of RFC 9180, without PSK.
+fn key_schedule(aead: HpkeAead, mode: Mode, shared_secret: &[u8], info: &[u8]) -> Result<Context, FatalProcedureError> {
+    let suite_id = hpke_suite_id(aead);
+    let psk_id_hash = labeled_extract(&suite_id, b"", b"psk_id_hash", b"");
+    let info_hash = labeled_extract(&suite_id, b"", b"info_hash", info);
+    let mut context = vec![mode as u8];
+    context.extend_from_slice(&psk_id_hash);
+    context.extend_from_slice(&info_hash);
+
+    let secret = labeled_extract(&suite_id, shared_secret, b"secret", b"");
+    Ok(Context {
+        key: labeled_expand(&suite_id, &secret, b"key", &context, aead.key_length())?,
+        nonce: labeled_expand(&suite_id, &secret, b"base_nonce", &context, AEAD_NONCE_LENGTH)?,
+    })
+}
+
+/// Single-shot `Seal` with the ephemeral key `ephemeral`. Returns `enc || ciphertext`.
+pub(crate) fn hpke_seal(
+    aead: HpkeAead,
+    ephemeral: &x25519::SecretKey,
+    recipient: &[u8; x25519::PUBLIC_KEY_LENGTH],
+    sender: Option<&x25519::SecretKey>,
+    info: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, FatalProcedureError> {
+    let recipient_key = x25519::PublicKey::from_bytes(*recipient);
+    let enc = ephemeral.public_key().to_bytes();
+    let mut dh_output = dh(ephemeral, &recipient_key)?;
+    let mut kem_context = [&enc[..], recipient].concat();
+    let mode = match sender {
+        Some(sender) => {
+            dh_output.extend_from_slice(&dh(sender, &recipient_key)?);
+            kem_context.extend_from_slice(&sender.public_key().to_bytes());
+            Mode::Auth
+        }
+        None => Mode::Base,
+    };
+    let shared_secret = extract_and_expand(&dh_output, &kem_context)?;
+    let context = key_schedule(aead, mode, &shared_secret, info)?;
+    let ciphertext = aead.seal(&context.key, &context.nonce, aad, plaintext)?;
+    Ok([&enc[..], &ciphertext].concat())
+}
+
+/// Single-shot `Open` of `ciphertext`, that has been sealed with the encapsulated key `enc`.
+pub(crate) fn hpke_open(
+    aead: HpkeAead,
+    recipient: &x25519::SecretKey,
+    sender: Option<&[u8; x25519::PUBLIC_KEY_LENGTH]>,
+    enc: &[u8; HPKE_ENC_LENGTH],
+    info: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, FatalProcedureError> {
+    let mut dh_output = dh(recipient, &x25519::PublicKey::from_bytes(*enc))?;
+    let mut kem_context = [&enc[..], &recipient.public_key().to_bytes()].concat();
+    let mode = match sender {
+        Some(sender) => {
+            dh_output.extend_from_slice(&dh(recipient, &x25519::PublicKey::from_bytes(*sender))?);
+            kem_context.extend_from_slice(sender);
+            Mode::Auth
+        }
+        None => Mode::Base,
+    };
+    let shared_secret = extract_and_expand(&dh_output, &kem_context)?;
+    let context = key_schedule(aead, mode, &shared_secret, info)?;
+    aead.open(&context.key, &context.nonce, aad, ciphertext)
+}
+
+fn x25519_secret_key(bytes: &[u8]) -> Result<x25519::SecretKey, FatalProcedureError> {
+    x25519::SecretKey::try_from_slice(bytes).map_err(|e| e.into())
+}
+
+/// Encrypts `plaintext` to the X25519 public key `recipient` with a fresh ephemeral key. If `sender` is set, the
+/// X25519 secret key at this location authenticates the message (auth mode), otherwise the base mode is used.
+///
+/// The output is `enc || ciphertext`, see [`HPKE_ENC_LENGTH`]. The sender key has to permit [`KeyUsage::DERIVE`].
+#[derive(Clone, GuardDebug, Serialize, Deserialize)]
+pub struct HpkeSeal {
+    pub aead: HpkeAead,
+
+    pub recipient: [u8; x25519::PUBLIC_KEY_LENGTH],
+
+    pub sender: Option<Location>,
+
+    pub info: Vec<u8>,
+
+    pub aad: Vec<u8>,
+
+    pub plaintext: Vec<u8>,
+}
+
+impl Procedure for HpkeSeal {
+    type Output = Vec<u8>;
+
+    fn execute<R: Runner>(self, runner: &R) -> Result<Self::Output, ProcedureError> {
+        let ephemeral = x25519::SecretKey::generate().map_err(FatalProcedureError::from)?;
+        let seal = |sender: Option<&x25519::SecretKey>| {
+            hpke_seal(
+                self.aead,
+                &ephemeral,
+                &self.recipient,
+                sender,
+                &self.info,
+                &self.aad,
+                &self.plaintext,
+            )
+        };
+        match &self.sender {
+            Some(sender) => runner.get_guards([sender.clone()], [KeyUsage::DERIVE], |[sender]| {
+                seal(Some(&x25519_secret_key(&sender.borrow())?))
+            }),
+            None => Ok(seal(None)?),
+        }
+    }
+}
+
+/// Decrypts the `ciphertext` with the encapsulated key `enc`, that has been created by [`HpkeSeal`] or any other
+/// HPKE implementation for the X25519 secret key at `recipient`. If `sender` is set, the message has to be
+/// authenticated by this X25519 public key (auth mode), otherwise the base mode is used.
+///
+/// The recipient key has to permit [`KeyUsage::DERIVE`].
+#[derive(Clone, GuardDebug, Serialize, Deserialize)]
+pub struct HpkeOpen {
+    pub aead: HpkeAead,
+
+    pub recipient: Location,
+
+    pub sender: Option<[u8; x25519::PUBLIC_KEY_LENGTH]>,
+
+    pub enc: [u8; HPKE_ENC_LENGTH],
+
+    pub info: Vec<u8>,
+
+    pub aad: Vec<u8>,
+
+    pub ciphertext: Vec<u8>,
+}
+
+impl Procedure for HpkeOpen {
+    type Output = Vec<u8>;
+
+    fn execute<R: Runner>(self, runner: &R) -> Result<Self::Output, ProcedureError> {
+        runner.get_guards([self.recipient.clone()], [KeyUsage::DERIVE], |[recipient]| {
+            hpke_open(
+                self.aead,
+                &x25519_secret_key(&recipient.borrow())?,
+                self.sender.as_ref(),
+                &self.enc,
+                &self.info,
+                &self.aad,
+                &self.ciphertext,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INFO: &str = "4f6465206f6e2061204772656369616e2055726e";
+    const AAD: &str = "436f756e742d30";
+    const PT: &str = "4265617574792069732074727574682c20747275746820626561757479";
+
+    fn secret_key(hex_key: &str) -> x25519::SecretKey {
+        x25519::SecretKey::try_from_slice(&hex::decode(hex_key).unwrap()).unwrap()
+    }
+
+    fn public_key(hex_key: &str) -> [u8; 32] {
+        hex::decode(hex_key).unwrap().try_into().unwrap()
+    }
+
+    fn check_vector(
+        aead: HpkeAead,
+        sk_e: &str,
+        pk_e: &str,
+        sk_r: &str,
+        pk_r: &str,
+        sender: Option<(&str, &str)>,
+        ct: &str,
+    ) {
+        let (info, aad, pt) = (
+            hex::decode(INFO).unwrap(),
+            hex::decode(AAD).unwrap(),
+            hex::decode(PT).unwrap(),
+        );
+        let (sk_e, sk_r, pk_r) = (secret_key(sk_e), secret_key(sk_r), public_key(pk_r));
+        assert_eq!(sk_r.public_key().to_bytes(), pk_r);
+        let sender = sender.map(|(sk_s, pk_s)| (secret_key(sk_s), public_key(pk_s)));
+        if let Some((sk_s, pk_s)) = &sender {
+            assert_eq!(&sk_s.public_key().to_bytes(), pk_s);
+        }
+
+        let sealed = hpke_seal(aead, &sk_e, &pk_r, sender.as_ref().map(|(sk, _)| sk), &info, &aad, &pt).unwrap();
+        let (enc, ciphertext) = sealed.split_at(HPKE_ENC_LENGTH);
+        assert_eq!(hex::encode(enc), pk_e);
+        assert_eq!(hex::encode(ciphertext), ct);
+
+        let enc = enc.try_into().unwrap();
+        let sender_public_key = sender.as_ref().map(|(_, pk)| pk);
+        let opened = hpke_open(aead, &sk_r, sender_public_key, &enc, &info, &aad, ciphertext).unwrap();
+        assert_eq!(opened, pt);
+        assert!(hpke_open(aead, &sk_r, sender_public_key, &enc, &info, b"", ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_hpke_rfc9180_base_aes128gcm() {
+        // RFC 9180, A.1.1: DHKEM(X25519, HKDF-SHA256), HKDF-SHA256, AES-128-GCM, Base Setup
+        check_vector(
+            HpkeAead::Aes128Gcm,
+            "52c4a758a802cd8b936eceea314432798d5baf2d7e9235dc084ab1b9cfa2f736",
+            "37fda3567bdbd628e88668c3c8d7e97d1d1253b6d4ea6d44c150f741f1bf4431",
+            "4612c550263fc8ad58375df3f557aac531d26850903e55a9f23f21d8534e8ac8",
+            "3948cfe0ad1ddb695d780e59077195da6c56506b027329794ab02bca80815c4d",
+            None,
+            "f938558b5d72f1a23810b4be2ab4f84331acc02fc97babc53a52ae8218a355a96d8770ac83d07bea87e13c512a",
+        );
+    }
+
+    #[test]
+    fn test_hpke_rfc9180_auth_aes128gcm() {
+        // RFC 9180, A.1.3: DHKEM(X25519, HKDF-SHA256), HKDF-SHA256, AES-128-GCM, Auth Setup
+        check_vector(
+            HpkeAead::Aes128Gcm,
+            "ff4442ef24fbc3c1ff86375b0be1e77e88a0de1e79b30896d73411c5ff4c3518",
+            "23fb952571a14a25e3d678140cd0e5eb47a0961bb18afcf85896e5453c312e76",
+            "fdea67cf831f1ca98d8e27b1f6abeb5b7745e9d35348b80fa407ff6958f9137e",
+            "1632d5c2f71c2b38d0a8fcc359355200caa8b1ffdf28618080466c909cb69b2e",
+            Some((
+                "dc4a146313cce60a278a5323d321f051c5707e9c45ba21a3479fecdf76fc69dd",
+                "8b0c70873dc5aecb7f9ee4e62406a397b350e57012be45cf53b7105ae731790b",
+            )),
+            "5fd92cc9d46dbf8943e72a07e42f363ed5f721212cd90bcfd072bfd9f44e06b80fd17824947496e21b680c141b",
+        );
+    }
+
+    #[test]
+    fn test_hpke_rfc9180_base_chacha20poly1305() {
+        // RFC 9180, A.2.1: DHKEM(X25519, HKDF-SHA256), HKDF-SHA256, ChaCha20Poly1305, Base Setup
+        check_vector(
+            HpkeAead::ChaCha20Poly1305,
+            "f4ec9b33b792c372c1d2c2063507b684ef925b8c75a42dbcbf57d63ccd381600",
+            "1afa08d3dec047a643885163f1180476fa7ddb54c6a8029ea33f95796bf2ac4a",
+            "8057991eef8f1f1af18f4a9491d16a1ce333f695d4db8e38da75975c4478e0fb",
+            "4310ee97d88cc1f088a5576c77ab0cf5c3ac797f3d95139c6c84b5429c59662a",
+            None,
+            "1c5250d8034ec2b784ba2cfd69dbdb8af406cfe3ff938e131f0def8c8b60b4db21993c62ce81883d2dd1b51a28",
+        );
+    }
+}
@@ -0,0 +1,401 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! One-time passwords: HOTP (RFC 4226) and TOTP (RFC 6238).
+//!
+//! The shared secret is stored in the vault, e.g. by importing an `otpauth://` URI with
+//! [`WriteVault::from_otpauth_uri`], and the codes are computed by [`HotpGenerate`] and [`TotpGenerate`] without
+//! reading the secret out of the vault.
+
+use crypto::{
+    hashes::sha::{SHA256_LEN, SHA512_LEN},
+    macs::hmac::{HMAC_SHA256, HMAC_SHA512},
+};
+use engine::runtime::memories::buffer::Buffer;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{primitives::WriteVault, types::*};
+use crate::{ClientError, KeyUsage, Location};
+
+/// Default number of digits of a one-time password.
+pub const OTP_DEFAULT_DIGITS: u32 = 6;
+/// Minimum number of digits of a one-time password, as required by RFC 4226.
+pub const OTP_MIN_DIGITS: u32 = 6;
+/// Maximum number of digits of a one-time password. The truncated HMAC has 31 bits and thus at most 10 digits.
+pub const OTP_MAX_DIGITS: u32 = 10;
+/// Default time step of a TOTP in seconds.
+pub const TOTP_DEFAULT_PERIOD: u64 = 30;
+
+/// Hash function of the HMAC of a one-time password.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OtpHash {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// Generate the HOTP (RFC 4226) for `counter` with the shared secret at `key`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotpGenerate {
+    pub hash: OtpHash,
+
+    pub digits: u32,
+
+    pub counter: u64,
+
+    pub key: Location,
+}
+
+impl UseSecret<1> for HotpGenerate {
+    type Output = String;
+
+    fn use_secret(self, guards: [Buffer<u8>; 1]) -> Result<Self::Output, FatalProcedureError> {
+        hotp(self.hash, &guards[0].borrow(), self.counter, self.digits)
+    }
+
+    fn source(&self) -> [Location; 1] {
+        [self.key.clone()]
+    }
+
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::SIGN]
+    }
+}
+
+/// Generate the TOTP (RFC 6238) for the Unix time `timestamp` in seconds with the shared secret at `key`.
+///
+/// The time steps of `period` seconds are counted from the Unix epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotpGenerate {
+    pub hash: OtpHash,
+
+    pub digits: u32,
+
+    pub period: u64,
+
+    pub timestamp: u64,
+
+    pub key: Location,
+}
+
+impl UseSecret<1> for TotpGenerate {
+    type Output = String;
+
+    fn use_secret(self, guards: [Buffer<u8>; 1]) -> Result<Self::Output, FatalProcedureError> {
+        if self.period == 0 {
+            return Err("TOTP period must not be zero".to_string().into());
+        }
+        hotp(
+            self.hash,
+            &guards[0].borrow(),
+            self.timestamp / self.period,
+            self.digits,
+        )
+    }
+
+    fn source(&self) -> [Location; 1] {
+        [self.key.clone()]
+    }
+
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::SIGN]
+    }
+}
+
+/// Type specific parameters of an `otpauth://` URI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OtpKind {
+    Hotp { counter: u64 },
+    Totp { period: u64 },
+}
+
+/// Parameters of an `otpauth://` URI, apart from the shared secret.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OtpAuthUri {
+    pub kind: OtpKind,
+
+    pub label: String,
+
+    pub issuer: Option<String>,
+
+    pub hash: OtpHash,
+
+    pub digits: u32,
+}
+
+impl OtpAuthUri {
+    /// Returns the [`HotpGenerate`] procedure for the shared secret at `key`, or `None` for a TOTP URI.
+    pub fn hotp(&self, key: Location) -> Option<HotpGenerate> {
+        match self.kind {
+            OtpKind::Hotp { counter } => Some(HotpGenerate {
+                hash: self.hash,
+                digits: self.digits,
+                counter,
+                key,
+            }),
+            OtpKind::Totp { .. } => None,
+        }
+    }
+
+    /// Returns the [`TotpGenerate`] procedure for `timestamp` and the shared secret at `key`, or `None` for a HOTP
+    /// URI.
+    pub fn totp(&self, key: Location, timestamp: u64) -> Option<TotpGenerate> {
+        match self.kind {
+            OtpKind::Totp { period } => Some(TotpGenerate {
+                hash: self.hash,
+                digits: self.digits,
+                period,
+                timestamp,
+                key,
+            }),
+            OtpKind::Hotp { .. } => None,
+        }
+    }
+}
+
+impl WriteVault {
+    /// Parses an `otpauth://hotp/...` or `otpauth://totp/...` URI and returns the [`WriteVault`] procedure that
+    /// writes the base32 decoded shared secret to `location`, together with the remaining parameters of the URI.
+    pub fn from_otpauth_uri(uri: &str, location: Location) -> Result<(Self, OtpAuthUri), ClientError> {
+        let error = |msg: &str| ClientError::Inner(format!("invalid otpauth URI: {}", msg));
+
+        let rest = uri.strip_prefix("otpauth://").ok_or_else(|| error("wrong scheme"))?;
+        let (kind, rest) = rest.split_once('/').ok_or_else(|| error("missing label"))?;
+        let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let label = percent_decode(label).ok_or_else(|| error("invalid label encoding"))?;
+
+        let mut secret = None;
+        let mut issuer = None;
+        let mut hash = OtpHash::default();
+        let mut digits = OTP_DEFAULT_DIGITS;
+        let mut counter = None;
+        let mut period = TOTP_DEFAULT_PERIOD;
+        for parameter in query.split('&').filter(|p| !p.is_empty()) {
+            let (name, value) = parameter.split_once('=').unwrap_or((parameter, ""));
+            match name {
+                "secret" => secret = Some(base32_decode(value).ok_or_else(|| error("invalid secret encoding"))?),
+                "issuer" => issuer = Some(percent_decode(value).ok_or_else(|| error("invalid issuer encoding"))?),
+                "algorithm" => {
+                    hash = match value.to_uppercase().as_str() {
+                        "SHA1" => OtpHash::Sha1,
+                        "SHA256" => OtpHash::Sha256,
+                        "SHA512" => OtpHash::Sha512,
+                        _ => return Err(error("unsupported algorithm")),
+                    }
+                }
+                "digits" => {
+                    digits = value
+                        .parse()
+                        .ok()
+                        .filter(|d| (OTP_MIN_DIGITS..=OTP_MAX_DIGITS).contains(d))
+                        .ok_or_else(|| error("invalid digits"))?
+                }
+                "counter" => counter = Some(value.parse().map_err(|_| error("invalid counter"))?),
+                "period" => {
+                    period = value
+                        .parse()
+                        .ok()
+                        .filter(|p| *p > 0)
+                        .ok_or_else(|| error("invalid period"))?
+                }
+                _ => {}
+            }
+        }
+
+        let kind = match kind {
+            "hotp" => OtpKind::Hotp {
+                counter: counter.ok_or_else(|| error("missing counter"))?,
+            },
+            "totp" => OtpKind::Totp { period },
+            _ => return Err(error("unsupported type")),
+        };
+        let data = secret.ok_or_else(|| error("missing secret"))?;
+        let parameters = OtpAuthUri {
+            kind,
+            label,
+            issuer,
+            hash,
+            digits,
+        };
+        Ok((WriteVault { data, location }, parameters))
+    }
+}
+
+/// Computes the HOTP for `counter`, including the dynamic truncation of RFC 4226 that RFC 6238 applies to all
+/// hash functions.
+fn hotp(hash: OtpHash, key: &[u8], counter: u64, digits: u32) -> Result<String, FatalProcedureError> {
+    if !(OTP_MIN_DIGITS..=OTP_MAX_DIGITS).contains(&digits) {
+        return Err(format!(
+            "number of OTP digits must be between {} and {}",
+            OTP_MIN_DIGITS, OTP_MAX_DIGITS
+        )
+        .into());
+    }
+    let msg = counter.to_be_bytes();
+    let mut mac = match hash {
+        OtpHash::Sha1 => {
+            let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts keys of any length");
+            mac.update(&msg);
+            mac.finalize().into_bytes().to_vec()
+        }
+        OtpHash::Sha256 => {
+            let mut mac = [0; SHA256_LEN];
+            HMAC_SHA256(&msg, key, &mut mac);
+            mac.to_vec()
+        }
+        OtpHash::Sha512 => {
+            let mut mac = [0; SHA512_LEN];
+            HMAC_SHA512(&msg, key, &mut mac);
+            mac.to_vec()
+        }
+    };
+    let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+    let mut truncated = [0u8; 4];
+    truncated.copy_from_slice(&mac[offset..offset + 4]);
+    let code = u64::from(u32::from_be_bytes(truncated) & 0x7fff_ffff) % 10u64.pow(digits);
+    mac.zeroize();
+    Ok(format!("{:0width$}", code, width = digits as usize))
+}
+
+/// Decodes base32 (RFC 4648) without or with padding, case insensitively, as used for the secrets of `otpauth://`
+/// URIs.
+fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(encoded.len() * 5 / 8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for c in encoded.trim_end_matches('=').bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a',
+            b'2'..=b'7' => c - b'2' + 26,
+            _ => {
+                decoded.zeroize();
+                return None;
+            }
+        };
+        buffer = (buffer << 5) | u16::from(value);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    buffer.zeroize();
+    Some(decoded)
+}
+
+/// Decodes the percent-encoding of a URI component.
+fn percent_decode(encoded: &str) -> Option<String> {
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut bytes = encoded.bytes();
+    while let Some(b) = bytes.next() {
+        if b == b'%' {
+            let hex = [bytes.next()?, bytes.next()?];
+            decoded.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            decoded.push(b);
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hotp_vectors() {
+        // RFC 4226, Appendix D
+        let codes = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583", "399871", "520489",
+        ];
+        for (counter, code) in codes.iter().enumerate() {
+            assert_eq!(
+                &hotp(OtpHash::Sha1, b"12345678901234567890", counter as u64, 6).unwrap(),
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn test_totp_vectors() {
+        // RFC 6238, Appendix B
+        let sha1_key = b"12345678901234567890";
+        let sha256_key = b"12345678901234567890123456789012";
+        let sha512_key = b"1234567890123456789012345678901234567890123456789012345678901234";
+        let vectors = [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1111111111, "14050471", "67062674", "99943326"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (2000000000, "69279037", "90698825", "38618901"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ];
+        for (time, sha1_code, sha256_code, sha512_code) in vectors {
+            let counter = time / TOTP_DEFAULT_PERIOD;
+            assert_eq!(hotp(OtpHash::Sha1, sha1_key, counter, 8).unwrap(), sha1_code);
+            assert_eq!(hotp(OtpHash::Sha256, sha256_key, counter, 8).unwrap(), sha256_code);
+            assert_eq!(hotp(OtpHash::Sha512, sha512_key, counter, 8).unwrap(), sha512_code);
+        }
+        assert!(hotp(OtpHash::Sha1, sha1_key, 0, 5).is_err());
+        assert!(hotp(OtpHash::Sha1, sha1_key, 0, 11).is_err());
+    }
+
+    #[test]
+    fn test_base32_decode() {
+        assert_eq!(
+            base32_decode("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap(),
+            b"12345678901234567890"
+        );
+        assert_eq!(base32_decode("mzxw6ytboi======").unwrap(), b"foobar");
+        assert_eq!(base32_decode("MZXW6").unwrap(), b"foo");
+        assert!(base32_decode("MZXW1").is_none());
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        let location = Location::generic(b"vault".to_vec(), b"record".to_vec());
+        let (write, parameters) = WriteVault::from_otpauth_uri(
+            "otpauth://totp/ACME%20Co:john@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACME%20Co&algorithm=SHA256&digits=8&period=60",
+            location.clone(),
+        )
+        .unwrap();
+        assert_eq!(write.data, b"12345678901234567890");
+        assert_eq!(
+            parameters,
+            OtpAuthUri {
+                kind: OtpKind::Totp { period: 60 },
+                label: "ACME Co:john@example.com".to_string(),
+                issuer: Some("ACME Co".to_string()),
+                hash: OtpHash::Sha256,
+                digits: 8,
+            }
+        );
+        assert!(parameters.hotp(location.clone()).is_none());
+        assert_eq!(parameters.totp(location.clone(), 120).unwrap().period, 60);
+
+        let (_, parameters) =
+            WriteVault::from_otpauth_uri("otpauth://hotp/label?secret=MZXW6&counter=5", location.clone()).unwrap();
+        assert_eq!(parameters.kind, OtpKind::Hotp { counter: 5 });
+        assert_eq!(parameters.hash, OtpHash::Sha1);
+        assert_eq!(parameters.digits, OTP_DEFAULT_DIGITS);
+
+        for uri in [
+            "http://totp/label?secret=MZXW6",
+            "otpauth://totp/label",
+            "otpauth://hotp/label?secret=MZXW6",
+            "otpauth://totp/label?secret=MZXW6&algorithm=MD5",
+            "otpauth://totp/label?secret=MZXW6&digits=4",
+            "otpauth://totp/label?secret=MZXW6&period=0",
+            "otpauth://totp/label?secret=MZXW1",
+            "otpauth://push/label?secret=MZXW6",
+        ] {
+            assert!(WriteVault::from_otpauth_uri(uri, location.clone()).is_err(), "{}", uri);
+        }
+    }
+}
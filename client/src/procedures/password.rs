@@ -0,0 +1,308 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Password hashing with Argon2id (RFC 9106).
+//!
+//! [`Argon2Derive`] derives a key from a password into the vault, [`Argon2Verify`] checks a password against a PHC
+//! string as stored by a credential database. The password is either supplied with the procedure or read from a
+//! record.
+//!
+//! [`PepperedHash`] and [`PepperedHashVerify`] additionally key the Argon2id hash of a password with a pepper, i.e.
+//! a server-side secret that is stored in a non-exportable record, so that leaked hashes can't be brute-forced
+//! without the pepper.
+
+use engine::snapshot::{ARGON2_MAX_ITERATIONS, ARGON2_MAX_LANES, ARGON2_MAX_MEMORY, ARGON2_MAX_SALT_LENGTH};
+use serde::{Deserialize, Serialize};
+use stronghold_utils::GuardDebug;
+use subtle::ConstantTimeEq;
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{
+    primitives::{hmac, Sha2Hash},
+    types::*,
+};
+use crate::{KeyUsage, Location};
+
+/// Length of the Argon2id hash of the password that is keyed with the pepper by [`PepperedHash`].
+const PEPPERED_HASH_ARGON2_LENGTH: u32 = 32;
+
+/// Maximum length of a key derived by [`Argon2Derive`] in bytes.
+const ARGON2_MAX_KEY_LENGTH: u32 = 1024;
+
+/// Password of an Argon2 procedure.
+#[derive(Clone, GuardDebug, Serialize, Deserialize)]
+pub enum Argon2Password {
+    /// The password is supplied with the procedure.
+    Supplied(Vec<u8>),
+    /// The password is read from the record at the location, which must permit [`KeyUsage::DERIVE`].
+    Stored(Location),
+}
+
+impl Argon2Password {
+    /// Returns the location of a stored password.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Argon2Password::Supplied(_) => None,
+            Argon2Password::Stored(location) => Some(location),
+        }
+    }
+
+    /// Applies `f` to the password, reading it from the vault if it is stored.
+    fn with_password<R, F, T>(&self, runner: &R, f: F) -> Result<T, ProcedureError>
+    where
+        R: Runner,
+        F: FnOnce(&[u8]) -> Result<T, FatalProcedureError>,
+    {
+        match self {
+            Argon2Password::Supplied(password) => f(password).map_err(ProcedureError::from),
+            Argon2Password::Stored(location) => {
+                runner.get_guards([location.clone()], [KeyUsage::DERIVE], |[guard]| f(&guard.borrow()))
+            }
+        }
+    }
+}
+
+impl Drop for Argon2Password {
+    fn drop(&mut self) {
+        if let Argon2Password::Supplied(password) = self {
+            password.zeroize();
+        }
+    }
+}
+
+/// Cost parameters of Argon2id. Procedures fail with parameters beyond the bounds of
+/// [`Argon2Params::is_within_limits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Argon2Params {
+    /// Memory size in KiB.
+    pub memory_cost: u32,
+
+    /// Number of passes.
+    pub time_cost: u32,
+
+    /// Degree of parallelism, i.e. number of lanes.
+    pub parallelism: u32,
+}
+
+impl Default for Argon2Params {
+    /// The second recommended option of RFC 9106: 64 MiB of memory, 3 passes and 4 lanes.
+    fn default() -> Self {
+        Argon2Params {
+            memory_cost: 1 << 16,
+            time_cost: 3,
+            parallelism: 4,
+        }
+    }
+}
+
+impl Argon2Params {
+    /// Returns `true` if the parameters don't exceed the bounds of the snapshot key derivation, i.e.
+    /// [`ARGON2_MAX_MEMORY`], [`ARGON2_MAX_ITERATIONS`] and [`ARGON2_MAX_LANES`]. The parameters may be chosen by the
+    /// caller of a procedure, so they have to be checked before hashing with them.
+    pub fn is_within_limits(&self) -> bool {
+        self.memory_cost <= ARGON2_MAX_MEMORY
+            && self.time_cost <= ARGON2_MAX_ITERATIONS
+            && self.parallelism <= ARGON2_MAX_LANES
+    }
+
+    /// Fails if the parameters, the length of `salt` or `hash_length` are out of range.
+    fn check_limits(&self, salt: &[u8], hash_length: u32) -> Result<(), ProcedureError> {
+        if !self.is_within_limits() || salt.len() > ARGON2_MAX_SALT_LENGTH || hash_length > ARGON2_MAX_KEY_LENGTH {
+            return Err(FatalProcedureError::from("Argon2 parameters out of range".to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// Reads the parameters of the Argon2 PHC string `hash`, e.g. `$argon2id$v=19$m=65536,t=3,p=4$<salt>$<hash>`.
+    fn from_encoded(hash: &str) -> Option<Self> {
+        let params = hash.split('$').find(|part| part.starts_with("m="))?;
+        let (mut memory_cost, mut time_cost, mut parallelism) = (None, None, None);
+        for param in params.split(',') {
+            match param.split_once('=')? {
+                ("m", value) => memory_cost = Some(value.parse().ok()?),
+                ("t", value) => time_cost = Some(value.parse().ok()?),
+                ("p", value) => parallelism = Some(value.parse().ok()?),
+                _ => return None,
+            }
+        }
+        Some(Argon2Params {
+            memory_cost: memory_cost?,
+            time_cost: time_cost?,
+            parallelism: parallelism?,
+        })
+    }
+
+    fn hash(&self, password: &[u8], salt: &[u8], hash_length: u32) -> Result<Vec<u8>, FatalProcedureError> {
+        let config = argon2::Config {
+            variant: argon2::Variant::Argon2id,
+            version: argon2::Version::Version13,
+            mem_cost: self.memory_cost,
+            time_cost: self.time_cost,
+            lanes: self.parallelism,
+            hash_length,
+            ..Default::default()
+        };
+        argon2::hash_raw(password, salt, &config).map_err(|e| FatalProcedureError::from(e.to_string()))
+    }
+}
+
+/// Derives a key of `key_length` bytes from the password with Argon2id and writes it to `output`. The key is at most
+/// 1024 bytes long.
+#[derive(Clone, GuardDebug, Serialize, Deserialize)]
+pub struct Argon2Derive {
+    pub password: Argon2Password,
+
+    pub salt: Vec<u8>,
+
+    pub params: Argon2Params,
+
+    pub key_length: u32,
+
+    pub output: Location,
+}
+
+impl Procedure for Argon2Derive {
+    type Output = ();
+
+    fn execute<R: Runner>(self, runner: &R) -> Result<Self::Output, ProcedureError> {
+        self.params.check_limits(&self.salt, self.key_length)?;
+        let derive = |password: &[u8]| {
+            let secret = self.params.hash(password, &self.salt, self.key_length)?;
+            Ok(Products { secret, output: () })
+        };
+        match &self.password {
+            Argon2Password::Supplied(password) => runner.exec_proc([], [], &self.output, None, |_| derive(password)),
+            Argon2Password::Stored(location) => {
+                runner.exec_proc([location.clone()], [KeyUsage::DERIVE], &self.output, None, |[guard]| {
+                    derive(&guard.borrow())
+                })
+            }
+        }
+    }
+}
+
+/// Returns `true` if the password matches the Argon2 PHC string `hash`, e.g.
+/// `$argon2id$v=19$m=65536,t=3,p=4$<salt>$<hash>`. Fails if `hash` is not a valid PHC string, or if its parameters
+/// exceed the bounds of [`Argon2Params::is_within_limits`].
+#[derive(Clone, GuardDebug, Serialize, Deserialize)]
+pub struct Argon2Verify {
+    pub password: Argon2Password,
+
+    pub hash: String,
+}
+
+impl Procedure for Argon2Verify {
+    type Output = bool;
+
+    fn execute<R: Runner>(self, runner: &R) -> Result<Self::Output, ProcedureError> {
+        // the parameters are read from the PHC string, which may come from an untrusted credential database.
+        let params = Argon2Params::from_encoded(&self.hash)
+            .ok_or_else(|| FatalProcedureError::from("invalid Argon2 PHC string".to_string()))?;
+        params.check_limits(&[], 0)?;
+        self.password.with_password(runner, |password| {
+            argon2::verify_encoded(&self.hash, password).map_err(|e| FatalProcedureError::from(e.to_string()))
+        })
+    }
+}
+
+/// Computes `HMAC(pepper, Argon2id(password, salt))` with the pepper at `pepper` and returns the digest. The Argon2id
+/// hash never leaves the procedure.
+///
+/// The record of the pepper must not permit [`KeyUsage::EXPORT`], so that it can't be copied or exported from the
+/// vault, and has to permit [`KeyUsage::SIGN`].
+#[derive(Clone, GuardDebug, Serialize, Deserialize)]
+pub struct PepperedHash {
+    pub hash_type: Sha2Hash,
+
+    pub password: Vec<u8>,
+
+    pub salt: Vec<u8>,
+
+    pub params: Argon2Params,
+
+    pub pepper: Location,
+}
+
+impl Procedure for PepperedHash {
+    type Output = Vec<u8>;
+
+    fn execute<R: Runner>(self, runner: &R) -> Result<Self::Output, ProcedureError> {
+        peppered_hash(
+            runner,
+            &self.hash_type,
+            &self.password,
+            &self.salt,
+            &self.params,
+            &self.pepper,
+        )
+    }
+}
+
+impl Drop for PepperedHash {
+    fn drop(&mut self) {
+        self.password.zeroize();
+    }
+}
+
+/// Returns `true` if `hash` is the [`PepperedHash`] of the password. The digests are compared in constant time.
+#[derive(Clone, GuardDebug, Serialize, Deserialize)]
+pub struct PepperedHashVerify {
+    pub hash_type: Sha2Hash,
+
+    pub password: Vec<u8>,
+
+    pub salt: Vec<u8>,
+
+    pub params: Argon2Params,
+
+    pub pepper: Location,
+
+    pub hash: Vec<u8>,
+}
+
+impl Procedure for PepperedHashVerify {
+    type Output = bool;
+
+    fn execute<R: Runner>(self, runner: &R) -> Result<Self::Output, ProcedureError> {
+        let expected = peppered_hash(
+            runner,
+            &self.hash_type,
+            &self.password,
+            &self.salt,
+            &self.params,
+            &self.pepper,
+        )?;
+        Ok(expected.ct_eq(&self.hash).into())
+    }
+}
+
+impl Drop for PepperedHashVerify {
+    fn drop(&mut self) {
+        self.password.zeroize();
+    }
+}
+
+fn peppered_hash<R: Runner>(
+    runner: &R,
+    hash_type: &Sha2Hash,
+    password: &[u8],
+    salt: &[u8],
+    params: &Argon2Params,
+    pepper: &Location,
+) -> Result<Vec<u8>, ProcedureError> {
+    params.check_limits(salt, PEPPERED_HASH_ARGON2_LENGTH)?;
+    // Check the pepper before computing the expensive Argon2id hash.
+    let (_, metadata) = runner.record_info(pepper)?;
+    let exportable = match metadata {
+        Some(metadata) => metadata.policy.usage.contains(KeyUsage::EXPORT),
+        // Records without metadata have the default policy, which permits all usages.
+        None => true,
+    };
+    if exportable {
+        return Err(PolicyError::Exportable.into());
+    }
+    let hashed = Zeroizing::new(params.hash(password, salt, PEPPERED_HASH_ARGON2_LENGTH)?);
+    runner.get_guards([pepper.clone()], [KeyUsage::SIGN], |[guard]| {
+        Ok(hmac(hash_type, &hashed, &guard.borrow()))
+    })
+}
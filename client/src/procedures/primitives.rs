@@ -3,16 +3,14 @@
 
 use std::str::FromStr;
 
-use super::types::*;
-use crate::{derive_record_id, derive_vault_id, Client, ClientError, Location, UseKey};
+use super::{ciphers::*, hpke::*, otp::*, password::*, shamir::*, transfer::*, types::*};
+use crate::{
+    derive_record_id, derive_vault_id, Client, ClientError, FwRequest, KeyUsage, Location, PermissionValue,
+    RecordMetadata, RecordPolicy, UseKey, VariantPermission,
+};
 pub use crypto::keys::slip10::{Chain, ChainCode};
 use crypto::{
-    ciphers::{
-        aes_gcm::Aes256Gcm,
-        aes_kw::Aes256Kw,
-        chacha::XChaCha20Poly1305,
-        traits::{Aead, Tag},
-    },
+    ciphers::{aes_gcm::Aes256Gcm, aes_kw::Aes256Kw, chacha::XChaCha20Poly1305, traits::Aead},
     hashes::{
         sha::{Sha256, Sha384, Sha512, SHA256_LEN, SHA384_LEN, SHA512_LEN},
         Digest,
@@ -27,16 +25,50 @@ use crypto::{
     utils::rand::fill,
 };
 
-use engine::runtime::memories::buffer::{Buffer, Ref};
+use curve25519_dalek::{
+    constants::ED25519_BASEPOINT_TABLE,
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar,
+};
+use engine::{
+    runtime::memories::buffer::{Buffer, Ref},
+    vault::RecordHint,
+};
+use k256::{
+    ecdsa::{self, signature::hazmat::PrehashVerifier},
+    elliptic_curve::PrimeField,
+};
+use ripemd::Ripemd160;
 use serde::{Deserialize, Serialize};
-use stronghold_utils::GuardDebug;
+use sha3::Keccak256;
+use stronghold_utils::{random, GuardDebug, RequestPermissions};
+use subtle::ConstantTimeEq;
 use zeroize::Zeroize;
 
+/// Length of the SHA-512 prehash signed by [`Ed25519SignPrehashed`].
+pub const ED25519PH_PREHASH_LENGTH: usize = SHA512_LEN;
+/// Maximum length of the context of an Ed25519ph signature.
+pub const ED25519PH_MAX_CONTEXT_LENGTH: usize = 255;
+/// Domain separation prefix `dom2` of Ed25519ph signatures.
+const ED25519PH_DOM2_PREFIX: &[u8] = b"SigEd25519 no Ed25519 collisions";
+/// Length of a raw secp256k1 secret key.
+pub const SECP256K1_ECDSA_SECRET_KEY_LENGTH: usize = 32;
+/// Length of the prehash signed by [`Secp256k1EcdsaSign`].
+pub const SECP256K1_ECDSA_PREHASH_LENGTH: usize = 32;
+/// Length of a recoverable secp256k1 signature (`r || s || v`).
+pub const SECP256K1_ECDSA_SIGNATURE_LENGTH: usize = 65;
+/// Length of an EVM address.
+pub const SECP256K1_ECDSA_EVM_ADDRESS_LENGTH: usize = 20;
+/// Length of a BIP32 extended private key record (`k || c`).
+pub const BIP32_EXTENDED_KEY_LENGTH: usize = 64;
+/// Version bytes of a mainnet extended public key (`xpub`).
+const BIP32_XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+
 /// Enum that wraps all cryptographic procedures that are supported by Stronghold.
 ///
 /// A procedure performs a (cryptographic) operation on a secret in the vault and/
 /// or generates a new secret.
-#[derive(Clone, GuardDebug, Serialize, Deserialize)]
+#[derive(Clone, GuardDebug, Serialize, Deserialize, RequestPermissions)]
 pub enum StrongholdProcedure {
     WriteVault(WriteVault),
     RevokeData(RevokeData),
@@ -44,21 +76,42 @@ pub enum StrongholdProcedure {
     CopyRecord(CopyRecord),
     Slip10Generate(Slip10Generate),
     Slip10Derive(Slip10Derive),
+    Bip32Derive(Bip32Derive),
     BIP39Generate(BIP39Generate),
     BIP39Recover(BIP39Recover),
     PublicKey(PublicKey),
     GenerateKey(GenerateKey),
     Ed25519Sign(Ed25519Sign),
+    Ed25519SignBatch(Ed25519SignBatch),
+    Ed25519SignPrehashed(Ed25519SignPrehashed),
+    Ed25519Verify(Ed25519Verify),
+    Ed25519VerifyPrehashed(Ed25519VerifyPrehashed),
+    Secp256k1EcdsaPublicKey(Secp256k1EcdsaPublicKey),
+    Secp256k1EcdsaSign(Secp256k1EcdsaSign),
+    Secp256k1EcdsaVerify(Secp256k1EcdsaVerify),
     X25519DiffieHellman(X25519DiffieHellman),
     Hmac(Hmac),
+    HmacVerify(HmacVerify),
+    HotpGenerate(HotpGenerate),
+    TotpGenerate(TotpGenerate),
     Hkdf(Hkdf),
     ConcatKdf(ConcatKdf),
     AesKeyWrapEncrypt(AesKeyWrapEncrypt),
     AesKeyWrapDecrypt(AesKeyWrapDecrypt),
     Pbkdf2Hmac(Pbkdf2Hmac),
+    Argon2Derive(Argon2Derive),
+    Argon2Verify(Argon2Verify),
+    PepperedHash(PepperedHash),
+    PepperedHashVerify(PepperedHashVerify),
     AeadEncrypt(AeadEncrypt),
     AeadDecrypt(AeadDecrypt),
     ConcatSecret(ConcatSecret),
+    ShamirSplit(ShamirSplit),
+    ShamirCombine(ShamirCombine),
+    ExportRecord(ExportRecord),
+    ImportRecord(ImportRecord),
+    HpkeSeal(HpkeSeal),
+    HpkeOpen(HpkeOpen),
 
     #[cfg(feature = "insecure")]
     CompareSecret(CompareSecret),
@@ -76,21 +129,42 @@ impl Procedure for StrongholdProcedure {
             CopyRecord(proc) => proc.execute(runner).map(|o| o.into()),
             Slip10Generate(proc) => proc.execute(runner).map(|o| o.into()),
             Slip10Derive(proc) => proc.execute(runner).map(|o| o.into()),
+            Bip32Derive(proc) => proc.execute(runner).map(|o| o.into()),
             BIP39Generate(proc) => proc.execute(runner).map(|o| o.into()),
             BIP39Recover(proc) => proc.execute(runner).map(|o| o.into()),
             GenerateKey(proc) => proc.execute(runner).map(|o| o.into()),
             PublicKey(proc) => proc.execute(runner).map(|o| o.into()),
             Ed25519Sign(proc) => proc.execute(runner).map(|o| o.into()),
+            Ed25519SignBatch(proc) => proc.execute(runner).map(|o| o.into()),
+            Ed25519SignPrehashed(proc) => proc.execute(runner).map(|o| o.into()),
+            Ed25519Verify(proc) => proc.execute(runner).map(|o| o.into()),
+            Ed25519VerifyPrehashed(proc) => proc.execute(runner).map(|o| o.into()),
+            Secp256k1EcdsaPublicKey(proc) => proc.execute(runner).map(|o| o.into()),
+            Secp256k1EcdsaSign(proc) => proc.execute(runner).map(|o| o.into()),
+            Secp256k1EcdsaVerify(proc) => proc.execute(runner).map(|o| o.into()),
             X25519DiffieHellman(proc) => proc.execute(runner).map(|o| o.into()),
             Hmac(proc) => proc.execute(runner).map(|o| o.into()),
+            HmacVerify(proc) => proc.execute(runner).map(|o| o.into()),
+            HotpGenerate(proc) => proc.execute(runner).map(|o| o.into()),
+            TotpGenerate(proc) => proc.execute(runner).map(|o| o.into()),
             Hkdf(proc) => proc.execute(runner).map(|o| o.into()),
             ConcatKdf(proc) => proc.execute(runner).map(|o| o.into()),
             AesKeyWrapEncrypt(proc) => proc.execute(runner).map(|o| o.into()),
             AesKeyWrapDecrypt(proc) => proc.execute(runner).map(|o| o.into()),
             Pbkdf2Hmac(proc) => proc.execute(runner).map(|o| o.into()),
+            Argon2Derive(proc) => proc.execute(runner).map(|o| o.into()),
+            Argon2Verify(proc) => proc.execute(runner).map(|o| o.into()),
+            PepperedHash(proc) => proc.execute(runner).map(|o| o.into()),
+            PepperedHashVerify(proc) => proc.execute(runner).map(|o| o.into()),
             AeadEncrypt(proc) => proc.execute(runner).map(|o| o.into()),
             AeadDecrypt(proc) => proc.execute(runner).map(|o| o.into()),
             ConcatSecret(proc) => proc.exec(runner).map(|o| o.into()),
+            ShamirSplit(proc) => proc.execute(runner).map(|o| o.into()),
+            ShamirCombine(proc) => proc.execute(runner).map(|o| o.into()),
+            ExportRecord(proc) => proc.execute(runner).map(|o| o.into()),
+            ImportRecord(proc) => proc.execute(runner).map(|o| o.into()),
+            HpkeSeal(proc) => proc.execute(runner).map(|o| o.into()),
+            HpkeOpen(proc) => proc.execute(runner).map(|o| o.into()),
 
             #[cfg(feature = "insecure")]
             CompareSecret(proc) => proc.exec(runner).map(|o| o.into()),
@@ -110,32 +184,118 @@ impl StrongholdProcedure {
                 input: Slip10DeriveInput::Key(input),
                 ..
             })
+            | StrongholdProcedure::Bip32Derive(Bip32Derive {
+                input: Slip10DeriveInput::Seed(input),
+                ..
+            })
+            | StrongholdProcedure::Bip32Derive(Bip32Derive {
+                input: Slip10DeriveInput::Key(input),
+                ..
+            })
             | StrongholdProcedure::PublicKey(PublicKey { private_key: input, .. })
             | StrongholdProcedure::Ed25519Sign(Ed25519Sign { private_key: input, .. })
+            | StrongholdProcedure::Ed25519SignBatch(Ed25519SignBatch { private_key: input, .. })
+            | StrongholdProcedure::Ed25519SignPrehashed(Ed25519SignPrehashed { private_key: input, .. })
+            | StrongholdProcedure::Secp256k1EcdsaPublicKey(Secp256k1EcdsaPublicKey { private_key: input, .. })
+            | StrongholdProcedure::Secp256k1EcdsaSign(Secp256k1EcdsaSign { private_key: input, .. })
             | StrongholdProcedure::X25519DiffieHellman(X25519DiffieHellman { private_key: input, .. })
             | StrongholdProcedure::Hkdf(Hkdf { ikm: input, .. })
             | StrongholdProcedure::ConcatKdf(ConcatKdf {
                 shared_secret: input, ..
             })
             | StrongholdProcedure::Hmac(Hmac { key: input, .. })
+            | StrongholdProcedure::HmacVerify(HmacVerify { key: input, .. })
+            | StrongholdProcedure::HotpGenerate(HotpGenerate { key: input, .. })
+            | StrongholdProcedure::TotpGenerate(TotpGenerate { key: input, .. })
             | StrongholdProcedure::AeadEncrypt(AeadEncrypt { key: input, .. })
-            | StrongholdProcedure::AeadDecrypt(AeadDecrypt { key: input, .. }) => Some(input.clone()),
+            | StrongholdProcedure::AeadDecrypt(AeadDecrypt { key: input, .. })
+            | StrongholdProcedure::ExportRecord(ExportRecord { record: input, .. })
+            | StrongholdProcedure::Argon2Derive(Argon2Derive {
+                password: Argon2Password::Stored(input),
+                ..
+            })
+            | StrongholdProcedure::Argon2Verify(Argon2Verify {
+                password: Argon2Password::Stored(input),
+                ..
+            })
+            | StrongholdProcedure::PepperedHash(PepperedHash { pepper: input, .. })
+            | StrongholdProcedure::PepperedHashVerify(PepperedHashVerify { pepper: input, .. })
+            | StrongholdProcedure::HpkeSeal(HpkeSeal {
+                sender: Some(input), ..
+            })
+            | StrongholdProcedure::HpkeOpen(HpkeOpen { recipient: input, .. }) => Some(input.clone()),
             _ => None,
         }
     }
+
+    /// Returns the paths of all vaults that are read or written by the procedure.
+    pub(crate) fn vault_paths(&self) -> Vec<&[u8]> {
+        use StrongholdProcedure::*;
+        let locations: Vec<&Location> = match self {
+            WriteVault(proc) => vec![&proc.location],
+            RevokeData(proc) => vec![&proc.location],
+            GarbageCollect(proc) => return vec![&proc.vault_path],
+            CopyRecord(proc) => vec![&proc.source, &proc.target],
+            Slip10Generate(proc) => vec![&proc.output],
+            Slip10Derive(proc) => vec![proc.input.location(), &proc.output],
+            Bip32Derive(proc) => vec![proc.input.location(), &proc.output],
+            BIP39Generate(proc) => vec![&proc.output],
+            BIP39Recover(proc) => vec![&proc.output],
+            PublicKey(proc) => vec![&proc.private_key],
+            GenerateKey(proc) => vec![&proc.output],
+            Ed25519Sign(proc) => vec![&proc.private_key],
+            Ed25519SignBatch(proc) => vec![&proc.private_key],
+            Ed25519SignPrehashed(proc) => vec![&proc.private_key],
+            Ed25519Verify(_) | Ed25519VerifyPrehashed(_) | Secp256k1EcdsaVerify(_) => vec![],
+            Secp256k1EcdsaPublicKey(proc) => vec![&proc.private_key],
+            Secp256k1EcdsaSign(proc) => vec![&proc.private_key],
+            X25519DiffieHellman(proc) => vec![&proc.private_key, &proc.shared_key],
+            Hmac(proc) => vec![&proc.key],
+            HmacVerify(proc) => vec![&proc.key],
+            HotpGenerate(proc) => vec![&proc.key],
+            TotpGenerate(proc) => vec![&proc.key],
+            Hkdf(proc) => vec![&proc.ikm, &proc.okm],
+            ConcatKdf(proc) => vec![&proc.shared_secret, &proc.output],
+            AesKeyWrapEncrypt(proc) => vec![&proc.encryption_key, &proc.wrap_key],
+            AesKeyWrapDecrypt(proc) => vec![&proc.decryption_key, &proc.output],
+            Pbkdf2Hmac(proc) => vec![&proc.output],
+            Argon2Derive(proc) => proc.password.location().into_iter().chain([&proc.output]).collect(),
+            Argon2Verify(proc) => proc.password.location().into_iter().collect(),
+            PepperedHash(proc) => vec![&proc.pepper],
+            PepperedHashVerify(proc) => vec![&proc.pepper],
+            AeadEncrypt(proc) => vec![&proc.key],
+            AeadDecrypt(proc) => vec![&proc.key],
+            ConcatSecret(proc) => vec![&proc.location_a, &proc.location_b, &proc.output_location],
+            ShamirSplit(proc) => std::iter::once(&proc.secret).chain(&proc.outputs).collect(),
+            ShamirCombine(proc) => proc.shares.iter().chain([&proc.output]).collect(),
+            ExportRecord(proc) => vec![&proc.record, &proc.sender],
+            ImportRecord(proc) => vec![&proc.recipient, &proc.output],
+            HpkeSeal(proc) => proc.sender.iter().collect(),
+            HpkeOpen(proc) => vec![&proc.recipient],
+
+            #[cfg(feature = "insecure")]
+            CompareSecret(proc) => vec![&proc.location],
+        };
+        locations.into_iter().map(Location::vault_path).collect()
+    }
+
     pub(crate) fn output(&self) -> Option<Location> {
         match self {
             StrongholdProcedure::WriteVault(WriteVault { location: output, .. })
             | StrongholdProcedure::CopyRecord(CopyRecord { target: output, .. })
             | StrongholdProcedure::Slip10Generate(Slip10Generate { output, .. })
             | StrongholdProcedure::Slip10Derive(Slip10Derive { output, .. })
+            | StrongholdProcedure::Bip32Derive(Bip32Derive { output, .. })
             | StrongholdProcedure::BIP39Generate(BIP39Generate { output, .. })
             | StrongholdProcedure::BIP39Recover(BIP39Recover { output, .. })
             | StrongholdProcedure::GenerateKey(GenerateKey { output, .. })
             | StrongholdProcedure::X25519DiffieHellman(X25519DiffieHellman { shared_key: output, .. })
             | StrongholdProcedure::Hkdf(Hkdf { okm: output, .. })
             | StrongholdProcedure::ConcatKdf(ConcatKdf { output, .. })
-            | StrongholdProcedure::Pbkdf2Hmac(Pbkdf2Hmac { output, .. }) => Some(output.clone()),
+            | StrongholdProcedure::Pbkdf2Hmac(Pbkdf2Hmac { output, .. })
+            | StrongholdProcedure::Argon2Derive(Argon2Derive { output, .. })
+            | StrongholdProcedure::ShamirCombine(ShamirCombine { output, .. })
+            | StrongholdProcedure::ImportRecord(ImportRecord { output, .. }) => Some(output.clone()),
             _ => None,
         }
     }
@@ -201,10 +361,10 @@ generic_procedures! {
 
 generic_procedures! {
     // Stronghold procedures that implement the `UseSecret` trait.
-    UseSecret<1> => { PublicKey, Ed25519Sign, Hmac, AeadEncrypt, AeadDecrypt },
+    UseSecret<1> => { PublicKey, Ed25519Sign, Ed25519SignBatch, Ed25519SignPrehashed, Secp256k1EcdsaPublicKey, Secp256k1EcdsaSign, Hmac, HmacVerify, HotpGenerate, TotpGenerate, AeadDecrypt },
     UseSecret<2> => { AesKeyWrapEncrypt },
     // Stronghold procedures that implement the `DeriveSecret` trait.
-    DeriveSecret<1> => { CopyRecord, Slip10Derive, X25519DiffieHellman, Hkdf, ConcatKdf, AesKeyWrapDecrypt },
+    DeriveSecret<1> => { CopyRecord, Slip10Derive, Bip32Derive, X25519DiffieHellman, Hkdf, ConcatKdf, AesKeyWrapDecrypt },
     DeriveSecret<2> => { ConcatSecret }
 }
 
@@ -212,7 +372,7 @@ procedures! {
     // Stronghold procedures that implement the `GenerateSecret` trait.
     GenerateSecret => { WriteVault, BIP39Generate, BIP39Recover, Slip10Generate, GenerateKey, Pbkdf2Hmac },
     // Stronghold procedures that directly implement the `Procedure` trait.
-    _ => { RevokeData, GarbageCollect }
+    _ => { RevokeData, GarbageCollect, ShamirSplit, ShamirCombine, ExportRecord, ImportRecord, Ed25519Verify, Ed25519VerifyPrehashed, Secp256k1EcdsaVerify, HpkeSeal, HpkeOpen, AeadEncrypt, Argon2Derive, Argon2Verify, PepperedHash, PepperedHashVerify }
 }
 
 /// Write data to the specified [`Location`].
@@ -298,6 +458,10 @@ impl DeriveSecret<1> for CopyRecord {
         [self.source.clone()]
     }
 
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::EXPORT]
+    }
+
     fn target(&self) -> &Location {
         &self.target
     }
@@ -313,12 +477,86 @@ pub enum MnemonicLanguage {
 pub enum AeadCipher {
     Aes256Gcm,
     XChaCha20Poly1305,
+    Aes256GcmSiv,
+    ChaCha20Poly1305,
 }
 
+impl AeadCipher {
+    /// Length of the nonce of the cipher.
+    pub fn nonce_length(&self) -> usize {
+        match self {
+            AeadCipher::Aes256Gcm => Aes256Gcm::NONCE_LENGTH,
+            AeadCipher::XChaCha20Poly1305 => XChaCha20Poly1305::NONCE_LENGTH,
+            AeadCipher::Aes256GcmSiv => Aes256GcmSiv::NONCE_LENGTH,
+            AeadCipher::ChaCha20Poly1305 => ChaCha20Poly1305::NONCE_LENGTH,
+        }
+    }
+
+    /// Length of the tag of the cipher.
+    pub fn tag_length(&self) -> usize {
+        match self {
+            AeadCipher::Aes256Gcm => Aes256Gcm::TAG_LENGTH,
+            AeadCipher::XChaCha20Poly1305 => XChaCha20Poly1305::TAG_LENGTH,
+            AeadCipher::Aes256GcmSiv => Aes256GcmSiv::TAG_LENGTH,
+            AeadCipher::ChaCha20Poly1305 => ChaCha20Poly1305::TAG_LENGTH,
+        }
+    }
+}
+
+/// Nonce of an [`AeadEncrypt`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AeadNonce {
+    /// Nonce chosen by the caller. It must never be used twice with the same key, except for
+    /// [`AeadCipher::Aes256GcmSiv`], where a reused nonce only reveals whether the same message was encrypted.
+    Explicit(Vec<u8>),
+
+    /// Random nonce generated by the procedure, which prepends it to the output. Random 96 bit nonces should not be
+    /// used for more than 2^32 messages per key.
+    Random,
+
+    /// Nonce derived from a counter, which is encoded big-endian into the last 8 bytes of an otherwise zero nonce. The
+    /// procedure prepends the nonce to the output. The client rejects a counter that is not greater than the last
+    /// counter used with the same key, which is persisted in the key's [`RecordMetadata::next_nonce_counter`].
+    ///
+    /// The record of the key must not permit [`KeyUsage::EXPORT`], so that it can't be copied together with its
+    /// counter.
+    Counter(u64),
+
+    /// Nonce derived like [`AeadNonce::Counter`] from the key's [`RecordMetadata::next_nonce_counter`], which is
+    /// incremented atomically.
+    StoredCounter,
+}
+
+impl AeadNonce {
+    /// Returns the nonce for the encryption with `cipher` and the key at `key`.
+    fn to_bytes<R: Runner>(&self, cipher: AeadCipher, key: &Location, runner: &R) -> Result<Vec<u8>, ProcedureError> {
+        let mut nonce = vec![0; cipher.nonce_length()];
+        let counter = match self {
+            AeadNonce::Explicit(explicit) => return Ok(explicit.clone()),
+            AeadNonce::Random => {
+                fill(&mut nonce).map_err(FatalProcedureError::from)?;
+                return Ok(nonce);
+            }
+            AeadNonce::Counter(counter) => runner.next_nonce_counter(key, Some(*counter))?,
+            AeadNonce::StoredCounter => runner.next_nonce_counter(key, None)?,
+        };
+        let offset = nonce.len() - 8;
+        nonce[offset..].copy_from_slice(&counter.to_be_bytes());
+        Ok(nonce)
+    }
+}
+
+impl From<Vec<u8>> for AeadNonce {
+    fn from(nonce: Vec<u8>) -> Self {
+        AeadNonce::Explicit(nonce)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum KeyType {
     Ed25519,
     X25519,
+    Secp256k1Ecdsa,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -463,6 +701,24 @@ pub enum Slip10DeriveInput {
     Key(Location),
 }
 
+impl Slip10DeriveInput {
+    /// Returns the location of the seed or parent key.
+    pub(crate) fn location(&self) -> &Location {
+        match self {
+            Slip10DeriveInput::Seed(location) | Slip10DeriveInput::Key(location) => location,
+        }
+    }
+
+    /// Returns the usage that the input record has to permit for a derivation along `chain`. Deriving a key along
+    /// an empty chain copies it, which requires the same usage as copying the record.
+    fn usage(&self, chain: &Chain) -> KeyUsage {
+        match self {
+            Slip10DeriveInput::Key(_) if chain.segments().is_empty() => KeyUsage::EXPORT,
+            _ => KeyUsage::DERIVE,
+        }
+    }
+}
+
 /// Derive a SLIP10 child key from a seed or a parent key, store it in output location and
 /// return the corresponding chain code
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -499,9 +755,174 @@ impl DeriveSecret<1> for Slip10Derive {
         }
     }
 
+    fn usage(&self) -> [KeyUsage; 1] {
+        [self.input.usage(&self.chain)]
+    }
+
     fn target(&self) -> &Location {
         &self.output
     }
+
+    fn key_type(&self) -> Option<KeyType> {
+        Some(KeyType::Ed25519)
+    }
+}
+
+/// Derive a BIP32 secp256k1 child key from a seed or a parent key, store it in the output location and
+/// return the extended public key of the child as base58check encoded `xpub` string.
+///
+/// The stored record has the same layout as a SLIP10 key: the 32 byte secret key followed by the 32 byte chain
+/// code, so it can be used directly as input of [`Secp256k1EcdsaSign`] or of further [`Bip32Derive`] calls.
+/// In contrast to SLIP10, the chain may contain non-hardened segments.
+///
+/// **Note**: when deriving from a [`Slip10DeriveInput::Key`], the depth of the parent key is unknown. The
+/// depth encoded in the returned `xpub` is therefore relative to the parent key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bip32Derive {
+    pub chain: Chain,
+
+    pub input: Slip10DeriveInput,
+
+    pub output: Location,
+}
+
+impl DeriveSecret<1> for Bip32Derive {
+    type Output = String;
+
+    fn derive(self, guards: [Buffer<u8>; 1]) -> Result<Products<String>, FatalProcedureError> {
+        let mut key = match self.input {
+            Slip10DeriveInput::Key(_) => {
+                let raw = guards[0].borrow();
+                if raw.len() != BIP32_EXTENDED_KEY_LENGTH {
+                    return Err(crypto::Error::BufferSize {
+                        has: raw.len(),
+                        needs: BIP32_EXTENDED_KEY_LENGTH,
+                        name: "extended key",
+                    }
+                    .into());
+                }
+                let mut key = [0u8; BIP32_EXTENDED_KEY_LENGTH];
+                key.copy_from_slice(&raw);
+                key
+            }
+            Slip10DeriveInput::Seed(_) => bip32_master_key(&guards[0].borrow())?,
+        };
+
+        let mut parent_fingerprint = [0u8; 4];
+        let mut child_number = [0u8; 4];
+        for segment in self.chain.segments() {
+            parent_fingerprint = bip32_fingerprint(&key)?;
+            child_number = segment.bs();
+            let child = bip32_child_key(&key, segment.hardened(), segment.bs());
+            key.zeroize();
+            key = child?;
+        }
+
+        let depth = u8::try_from(self.chain.segments().len())
+            .map_err(|_| FatalProcedureError::from("BIP32 chains are limited to 255 segments".to_owned()))?;
+        let public_key = secp256k1_ecdsa_secret_key(&key[..32])?
+            .verifying_key()
+            .to_encoded_point(true);
+
+        let mut xpub = Vec::with_capacity(78);
+        xpub.extend_from_slice(&BIP32_XPUB_VERSION);
+        xpub.push(depth);
+        xpub.extend_from_slice(&parent_fingerprint);
+        xpub.extend_from_slice(&child_number);
+        xpub.extend_from_slice(&key[32..]);
+        xpub.extend_from_slice(public_key.as_bytes());
+
+        let secret = key.to_vec();
+        key.zeroize();
+
+        Ok(Products {
+            secret,
+            output: bs58::encode(xpub).with_check().into_string(),
+        })
+    }
+
+    fn source(&self) -> [Location; 1] {
+        match &self.input {
+            Slip10DeriveInput::Key(loc) => [loc.clone()],
+            Slip10DeriveInput::Seed(loc) => [loc.clone()],
+        }
+    }
+
+    fn usage(&self) -> [KeyUsage; 1] {
+        [self.input.usage(&self.chain)]
+    }
+
+    fn target(&self) -> &Location {
+        &self.output
+    }
+
+    fn key_type(&self) -> Option<KeyType> {
+        Some(KeyType::Secp256k1Ecdsa)
+    }
+}
+
+/// Computes the BIP32 master key `k || c` from a seed.
+fn bip32_master_key(seed: &[u8]) -> Result<[u8; BIP32_EXTENDED_KEY_LENGTH], crypto::Error> {
+    let mut key = [0u8; BIP32_EXTENDED_KEY_LENGTH];
+    HMAC_SHA512(seed, b"Bitcoin seed", &mut key);
+    if let Err(e) = secp256k1_ecdsa_secret_key(&key[..32]) {
+        key.zeroize();
+        return Err(e);
+    }
+    Ok(key)
+}
+
+/// Computes the BIP32 child key `k_i || c_i` of the extended private key `k || c` (CKDpriv).
+fn bip32_child_key(
+    parent: &[u8; BIP32_EXTENDED_KEY_LENGTH],
+    hardened: bool,
+    index: [u8; 4],
+) -> Result<[u8; BIP32_EXTENDED_KEY_LENGTH], crypto::Error> {
+    let parent_sk = secp256k1_ecdsa_secret_key(&parent[..32])?;
+
+    let mut data = [0u8; 1 + 32 + 4];
+    if hardened {
+        // 0x00 || ser256(k_par) || ser32(i)
+        data[1..33].copy_from_slice(&parent[..32]);
+    } else {
+        // serP(point(k_par)) || ser32(i)
+        data[..33].copy_from_slice(parent_sk.verifying_key().to_encoded_point(true).as_bytes());
+    }
+    data[33..].copy_from_slice(&index);
+
+    let mut i = [0u8; BIP32_EXTENDED_KEY_LENGTH];
+    HMAC_SHA512(&data, &parent[32..], &mut i);
+    data.zeroize();
+
+    let invalid = crypto::Error::InvalidArgumentError {
+        alg: "BIP32",
+        expected: "a valid child key index",
+    };
+    let il: Option<k256::Scalar> = k256::Scalar::from_repr(*k256::FieldBytes::from_slice(&i[..32])).into();
+    let child = match il {
+        Some(il) => il + parent_sk.as_nonzero_scalar().as_ref(),
+        None => {
+            i.zeroize();
+            return Err(invalid);
+        }
+    };
+    if bool::from(child.is_zero()) {
+        i.zeroize();
+        return Err(invalid);
+    }
+    i[..32].copy_from_slice(&child.to_bytes());
+    Ok(i)
+}
+
+/// Computes the fingerprint of an extended key, the first 4 bytes of `HASH160(serP(point(k)))`.
+fn bip32_fingerprint(key: &[u8; BIP32_EXTENDED_KEY_LENGTH]) -> Result<[u8; 4], crypto::Error> {
+    let public_key = secp256k1_ecdsa_secret_key(&key[..32])?
+        .verifying_key()
+        .to_encoded_point(true);
+    let hash = Ripemd160::digest(Sha256::digest(public_key.as_bytes()));
+    let mut fingerprint = [0u8; 4];
+    fingerprint.copy_from_slice(&hash[..4]);
+    Ok(fingerprint)
 }
 
 fn x25519_secret_key(raw: Ref<u8>) -> Result<x25519::SecretKey, crypto::Error> {
@@ -534,6 +955,33 @@ fn ed25519_secret_key(raw: Ref<u8>) -> Result<ed25519::SecretKey, crypto::Error>
     Ok(ed25519::SecretKey::from_bytes(bs))
 }
 
+fn secp256k1_ecdsa_secret_key(raw: &[u8]) -> Result<ecdsa::SigningKey, crypto::Error> {
+    if raw.len() < SECP256K1_ECDSA_SECRET_KEY_LENGTH {
+        let e = crypto::Error::BufferSize {
+            has: raw.len(),
+            needs: SECP256K1_ECDSA_SECRET_KEY_LENGTH,
+            name: "data buffer",
+        };
+        return Err(e);
+    }
+    ecdsa::SigningKey::from_slice(&raw[..SECP256K1_ECDSA_SECRET_KEY_LENGTH]).map_err(|_| crypto::Error::ConvertError {
+        from: "bytes",
+        to: "secp256k1 secret key",
+    })
+}
+
+fn secp256k1_ecdsa_generate() -> Result<Vec<u8>, crypto::Error> {
+    // A random scalar is out of range with negligible probability, in which case we simply retry.
+    let mut bs = [0u8; SECP256K1_ECDSA_SECRET_KEY_LENGTH];
+    loop {
+        fill(&mut bs)?;
+        if let Ok(sk) = ecdsa::SigningKey::from_slice(&bs) {
+            bs.zeroize();
+            return Ok(sk.to_bytes().to_vec());
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenerateKey {
     pub ty: KeyType,
@@ -547,6 +995,7 @@ impl GenerateSecret for GenerateKey {
         let secret = match self.ty {
             KeyType::Ed25519 => ed25519::SecretKey::generate().map(|sk| sk.to_bytes().to_vec())?,
             KeyType::X25519 => x25519::SecretKey::generate().map(|sk| sk.to_bytes().to_vec())?,
+            KeyType::Secp256k1Ecdsa => secp256k1_ecdsa_generate()?,
         };
         Ok(Products { secret, output: () })
     }
@@ -554,10 +1003,17 @@ impl GenerateSecret for GenerateKey {
     fn target(&self) -> &Location {
         &self.output
     }
+
+    fn key_type(&self) -> Option<KeyType> {
+        Some(self.ty.clone())
+    }
 }
 
-/// Derive an Ed25519 public key from the corresponding private key stored at the specified
+/// Derive a public key from the corresponding private key stored at the specified
 /// location
+///
+/// Ed25519 and X25519 public keys are returned as 32 raw bytes, secp256k1 public keys
+/// in the compressed SEC1 encoding. Use [`Secp256k1EcdsaPublicKey`] for other secp256k1 encodings.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublicKey {
     pub ty: KeyType,
@@ -566,17 +1022,21 @@ pub struct PublicKey {
 }
 
 impl UseSecret<1> for PublicKey {
-    type Output = [u8; 32];
+    type Output = Vec<u8>;
 
     fn use_secret(self, guards: [Buffer<u8>; 1]) -> Result<Self::Output, FatalProcedureError> {
         match self.ty {
             KeyType::Ed25519 => {
                 let sk = ed25519_secret_key(guards[0].borrow())?;
-                Ok(sk.public_key().to_bytes())
+                Ok(sk.public_key().to_bytes().to_vec())
             }
             KeyType::X25519 => {
                 let sk = x25519_secret_key(guards[0].borrow())?;
-                Ok(sk.public_key().to_bytes())
+                Ok(sk.public_key().to_bytes().to_vec())
+            }
+            KeyType::Secp256k1Ecdsa => {
+                let sk = secp256k1_ecdsa_secret_key(&guards[0].borrow())?;
+                Ok(sk.verifying_key().to_encoded_point(true).as_bytes().to_vec())
             }
         }
     }
@@ -584,6 +1044,10 @@ impl UseSecret<1> for PublicKey {
     fn source(&self) -> [Location; 1] {
         [self.private_key.clone()]
     }
+
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::empty()]
+    }
 }
 
 /// Use the specified Ed25519 compatible key to sign the given message
@@ -609,6 +1073,385 @@ impl UseSecret<1> for Ed25519Sign {
     fn source(&self) -> [Location; 1] {
         [self.private_key.clone()]
     }
+
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::SIGN]
+    }
+}
+
+/// Signatures created by [`Ed25519SignBatch`], in the order of the messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ed25519Signatures(pub Vec<[u8; ed25519::SIGNATURE_LENGTH]>);
+
+impl From<Ed25519Signatures> for ProcedureOutput {
+    fn from(signatures: Ed25519Signatures) -> Self {
+        signatures.0.concat().into()
+    }
+}
+
+impl TryFrom<ProcedureOutput> for Ed25519Signatures {
+    type Error = FatalProcedureError;
+
+    fn try_from(value: ProcedureOutput) -> Result<Self, Self::Error> {
+        let bytes = Vec::<u8>::from(value);
+        if bytes.len() % ed25519::SIGNATURE_LENGTH != 0 {
+            return Err(format!("invalid length of signature list: {}", bytes.len()).into());
+        }
+        let signatures = bytes
+            .chunks_exact(ed25519::SIGNATURE_LENGTH)
+            .map(|sig| sig.try_into().expect("chunk has the length of a signature"))
+            .collect();
+        Ok(Ed25519Signatures(signatures))
+    }
+}
+
+/// Use the specified Ed25519 compatible key to sign each of the given messages. The key is only unlocked once for
+/// all messages.
+///
+/// Compatible keys are any record that contain the desired key material in the first 32 bytes,
+/// in particular SLIP10 keys are compatible.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ed25519SignBatch {
+    pub msgs: Vec<Vec<u8>>,
+
+    pub private_key: Location,
+}
+
+impl UseSecret<1> for Ed25519SignBatch {
+    type Output = Ed25519Signatures;
+
+    fn use_secret(self, guards: [Buffer<u8>; 1]) -> Result<Self::Output, FatalProcedureError> {
+        let sk = ed25519_secret_key(guards[0].borrow())?;
+        let signatures = self.msgs.iter().map(|msg| sk.sign(msg).to_bytes()).collect();
+        Ok(Ed25519Signatures(signatures))
+    }
+
+    fn source(&self) -> [Location; 1] {
+        [self.private_key.clone()]
+    }
+
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::SIGN]
+    }
+}
+
+/// Use the specified Ed25519 compatible key to create an Ed25519ph signature (RFC 8032) of a message, of which only
+/// the SHA-512 `prehash` is passed to the procedure. The optional `context` is at most
+/// [`ED25519PH_MAX_CONTEXT_LENGTH`] bytes long.
+///
+/// Compatible keys are any record that contain the desired key material in the first 32 bytes,
+/// in particular SLIP10 keys are compatible.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ed25519SignPrehashed {
+    pub prehash: Vec<u8>,
+
+    pub context: Vec<u8>,
+
+    pub private_key: Location,
+}
+
+impl UseSecret<1> for Ed25519SignPrehashed {
+    type Output = [u8; ed25519::SIGNATURE_LENGTH];
+
+    fn use_secret(self, guards: [Buffer<u8>; 1]) -> Result<Self::Output, FatalProcedureError> {
+        check_ed25519ph_input(&self.prehash, &self.context)?;
+        let sk = ed25519_secret_key(guards[0].borrow())?;
+        Ok(ed25519ph_sign(&sk, &self.context, &self.prehash))
+    }
+
+    fn source(&self) -> [Location; 1] {
+        [self.private_key.clone()]
+    }
+
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::SIGN]
+    }
+}
+
+fn check_ed25519ph_input(prehash: &[u8], context: &[u8]) -> Result<(), FatalProcedureError> {
+    if prehash.len() != ED25519PH_PREHASH_LENGTH {
+        return Err(format!(
+            "invalid prehash length {}, expected {}",
+            prehash.len(),
+            ED25519PH_PREHASH_LENGTH
+        )
+        .into());
+    }
+    if context.len() > ED25519PH_MAX_CONTEXT_LENGTH {
+        return Err(format!(
+            "context is {} bytes long, at most {} are allowed",
+            context.len(),
+            ED25519PH_MAX_CONTEXT_LENGTH
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Writes `dom2(1, context)` of RFC 8032 into `hasher`.
+fn ed25519ph_dom2(hasher: &mut Sha512, context: &[u8]) {
+    hasher.update(ED25519PH_DOM2_PREFIX);
+    hasher.update([1, context.len() as u8]);
+    hasher.update(context);
+}
+
+fn sha512_scalar(hasher: Sha512) -> Scalar {
+    let mut wide = [0u8; SHA512_LEN];
+    wide.copy_from_slice(&hasher.finalize());
+    let scalar = Scalar::from_bytes_mod_order_wide(&wide);
+    wide.zeroize();
+    scalar
+}
+
+/// Ed25519ph signing of RFC 8032 section 5.1.6, with the prehash already computed by the caller.
+fn ed25519ph_sign(sk: &ed25519::SecretKey, context: &[u8], prehash: &[u8]) -> [u8; ed25519::SIGNATURE_LENGTH] {
+    let mut expanded = [0u8; SHA512_LEN];
+    expanded.copy_from_slice(&Sha512::digest(sk.as_slice()));
+    let mut bits = [0u8; 32];
+    bits.copy_from_slice(&expanded[..32]);
+    bits[0] &= 248;
+    bits[31] &= 127;
+    bits[31] |= 64;
+    let mut a = Scalar::from_bits(bits);
+    let public_key = (&a * &ED25519_BASEPOINT_TABLE).compress();
+
+    let mut hasher = Sha512::new();
+    ed25519ph_dom2(&mut hasher, context);
+    hasher.update(&expanded[32..]);
+    hasher.update(prehash);
+    let mut r = sha512_scalar(hasher);
+    let big_r = (&r * &ED25519_BASEPOINT_TABLE).compress();
+
+    let mut hasher = Sha512::new();
+    ed25519ph_dom2(&mut hasher, context);
+    hasher.update(big_r.as_bytes());
+    hasher.update(public_key.as_bytes());
+    hasher.update(prehash);
+    let k = sha512_scalar(hasher);
+    let s = r + k * a;
+
+    expanded.zeroize();
+    bits.zeroize();
+    a.zeroize();
+    r.zeroize();
+
+    let mut signature = [0u8; ed25519::SIGNATURE_LENGTH];
+    signature[..32].copy_from_slice(big_r.as_bytes());
+    signature[32..].copy_from_slice(s.as_bytes());
+    signature
+}
+
+/// Verifies the Ed25519 `signature` of `msg` with the `public_key`.
+///
+/// The output is `false` if the signature is invalid or malformed, an invalid public key fails the procedure.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ed25519Verify {
+    pub msg: Vec<u8>,
+
+    pub signature: Vec<u8>,
+
+    pub public_key: [u8; ed25519::PUBLIC_KEY_LENGTH],
+}
+
+impl Procedure for Ed25519Verify {
+    type Output = bool;
+
+    fn execute<R: Runner>(self, _runner: &R) -> Result<Self::Output, ProcedureError> {
+        let pk = ed25519::PublicKey::try_from_bytes(self.public_key).map_err(FatalProcedureError::from)?;
+        let sig = match <[u8; ed25519::SIGNATURE_LENGTH]>::try_from(self.signature.as_slice()) {
+            Ok(sig) => ed25519::Signature::from_bytes(sig),
+            Err(_) => return Ok(false),
+        };
+        Ok(pk.verify(&sig, &self.msg))
+    }
+}
+
+/// Verifies the Ed25519ph `signature` (RFC 8032) of the message with the SHA-512 `prehash` under `context` with the
+/// `public_key`, see [`Ed25519SignPrehashed`].
+///
+/// The output is `false` if the signature is invalid or malformed, an invalid public key, prehash or context fails
+/// the procedure.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ed25519VerifyPrehashed {
+    pub prehash: Vec<u8>,
+
+    pub context: Vec<u8>,
+
+    pub signature: Vec<u8>,
+
+    pub public_key: [u8; ed25519::PUBLIC_KEY_LENGTH],
+}
+
+impl Procedure for Ed25519VerifyPrehashed {
+    type Output = bool;
+
+    fn execute<R: Runner>(self, _runner: &R) -> Result<Self::Output, ProcedureError> {
+        check_ed25519ph_input(&self.prehash, &self.context)?;
+        let public_key = CompressedEdwardsY(self.public_key)
+            .decompress()
+            .ok_or_else(|| FatalProcedureError::from("invalid Ed25519 public key".to_string()))?;
+        if self.signature.len() != ed25519::SIGNATURE_LENGTH {
+            return Ok(false);
+        }
+        let (r, s) = self.signature.split_at(32);
+        let s = match Scalar::from_canonical_bytes(s.try_into().expect("signature has the correct length")) {
+            Some(s) => s,
+            None => return Ok(false),
+        };
+
+        let mut hasher = Sha512::new();
+        ed25519ph_dom2(&mut hasher, &self.context);
+        hasher.update(r);
+        hasher.update(self.public_key);
+        hasher.update(&self.prehash);
+        let k = sha512_scalar(hasher);
+        let expected_r = EdwardsPoint::vartime_double_scalar_mul_basepoint(&k, &(-public_key), &s);
+        Ok(expected_r.compress().as_bytes() == r)
+    }
+}
+
+/// The encodings in which a secp256k1 public key can be exported.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Secp256k1EcdsaPublicKeyEncoding {
+    /// SEC1 compressed point (33 bytes).
+    Compressed,
+    /// SEC1 uncompressed point (65 bytes).
+    Uncompressed,
+    /// EVM address: the last 20 bytes of the Keccak-256 hash of the uncompressed point without its prefix.
+    EvmAddress,
+}
+
+/// The last 20 bytes of the Keccak-256 hash of the uncompressed point without its prefix.
+fn secp256k1_ecdsa_evm_address(pk: &ecdsa::VerifyingKey) -> Vec<u8> {
+    let point = pk.to_encoded_point(false);
+    // skip the `0x04` tag of the uncompressed point
+    let hash = Keccak256::digest(&point.as_bytes()[1..]);
+    hash[hash.len() - SECP256K1_ECDSA_EVM_ADDRESS_LENGTH..].to_vec()
+}
+
+/// Derive the public key of the secp256k1 private key stored at the specified location and return it
+/// in the requested encoding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Secp256k1EcdsaPublicKey {
+    pub encoding: Secp256k1EcdsaPublicKeyEncoding,
+
+    pub private_key: Location,
+}
+
+impl UseSecret<1> for Secp256k1EcdsaPublicKey {
+    type Output = Vec<u8>;
+
+    fn use_secret(self, guards: [Buffer<u8>; 1]) -> Result<Self::Output, FatalProcedureError> {
+        let sk = secp256k1_ecdsa_secret_key(&guards[0].borrow())?;
+        let pk = sk.verifying_key();
+        let output = match self.encoding {
+            Secp256k1EcdsaPublicKeyEncoding::Compressed => pk.to_encoded_point(true).as_bytes().to_vec(),
+            Secp256k1EcdsaPublicKeyEncoding::Uncompressed => pk.to_encoded_point(false).as_bytes().to_vec(),
+            Secp256k1EcdsaPublicKeyEncoding::EvmAddress => secp256k1_ecdsa_evm_address(pk),
+        };
+        Ok(output)
+    }
+
+    fn source(&self) -> [Location; 1] {
+        [self.private_key.clone()]
+    }
+
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::empty()]
+    }
+}
+
+/// Use the specified secp256k1 key to sign the given 32-byte prehash (e.g. the Keccak-256 hash of an
+/// EVM transaction)
+///
+/// Compatible keys are any record that contain the desired key material in the first 32 bytes,
+/// in particular keys derived with [`Bip32Derive`] are compatible.
+///
+/// The signature is deterministic (RFC 6979), normalized to a low `s` value and returned in its recoverable
+/// form `r || s || v`, where `v` is the recovery id (`0` or `1`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Secp256k1EcdsaSign {
+    pub prehash: [u8; SECP256K1_ECDSA_PREHASH_LENGTH],
+
+    pub private_key: Location,
+}
+
+impl UseSecret<1> for Secp256k1EcdsaSign {
+    type Output = [u8; SECP256K1_ECDSA_SIGNATURE_LENGTH];
+
+    fn use_secret(self, guards: [Buffer<u8>; 1]) -> Result<Self::Output, FatalProcedureError> {
+        let sk = secp256k1_ecdsa_secret_key(&guards[0].borrow())?;
+        let (sig, recid) = sk
+            .sign_prehash_recoverable(&self.prehash)
+            .map_err(|e| FatalProcedureError::from(format!("secp256k1 signing failed: {}", e)))?;
+        let mut output = [0u8; SECP256K1_ECDSA_SIGNATURE_LENGTH];
+        output[..SECP256K1_ECDSA_SIGNATURE_LENGTH - 1].copy_from_slice(&sig.to_bytes());
+        output[SECP256K1_ECDSA_SIGNATURE_LENGTH - 1] = recid.to_byte();
+        Ok(output)
+    }
+
+    fn source(&self) -> [Location; 1] {
+        [self.private_key.clone()]
+    }
+
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::SIGN]
+    }
+}
+
+/// Verifies the secp256k1 ECDSA `signature` of the `prehash` with the `public_key` in the given `encoding`, see
+/// [`Secp256k1EcdsaSign`]. The signature is `r || s`, optionally followed by the recovery id `v`. An EVM address as
+/// public key requires the recovery id, the key is recovered from the signature and compared to the address.
+///
+/// The output is `false` if the signature is invalid or malformed, an invalid public key fails the procedure.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Secp256k1EcdsaVerify {
+    pub prehash: [u8; SECP256K1_ECDSA_PREHASH_LENGTH],
+
+    pub signature: Vec<u8>,
+
+    pub encoding: Secp256k1EcdsaPublicKeyEncoding,
+
+    pub public_key: Vec<u8>,
+}
+
+impl Procedure for Secp256k1EcdsaVerify {
+    type Output = bool;
+
+    fn execute<R: Runner>(self, _runner: &R) -> Result<Self::Output, ProcedureError> {
+        let (sig, recid) = match self.signature.len() {
+            n if n == SECP256K1_ECDSA_SIGNATURE_LENGTH - 1 => (&self.signature[..], None),
+            SECP256K1_ECDSA_SIGNATURE_LENGTH => (
+                &self.signature[..SECP256K1_ECDSA_SIGNATURE_LENGTH - 1],
+                Some(self.signature[SECP256K1_ECDSA_SIGNATURE_LENGTH - 1]),
+            ),
+            _ => return Ok(false),
+        };
+        let sig = match ecdsa::Signature::from_slice(sig) {
+            Ok(sig) => sig,
+            Err(_) => return Ok(false),
+        };
+        match self.encoding {
+            Secp256k1EcdsaPublicKeyEncoding::Compressed | Secp256k1EcdsaPublicKeyEncoding::Uncompressed => {
+                let pk = ecdsa::VerifyingKey::from_sec1_bytes(&self.public_key)
+                    .map_err(|_| FatalProcedureError::from("invalid secp256k1 public key".to_string()))?;
+                Ok(pk.verify_prehash(&self.prehash, &sig).is_ok())
+            }
+            Secp256k1EcdsaPublicKeyEncoding::EvmAddress => {
+                if self.public_key.len() != SECP256K1_ECDSA_EVM_ADDRESS_LENGTH {
+                    return Err(FatalProcedureError::from("invalid EVM address".to_string()).into());
+                }
+                let recid = match recid.and_then(ecdsa::RecoveryId::from_byte) {
+                    Some(recid) => recid,
+                    None => return Ok(false),
+                };
+                match ecdsa::VerifyingKey::recover_from_prehash(&self.prehash, &sig, recid) {
+                    Ok(pk) => Ok(secp256k1_ecdsa_evm_address(&pk) == self.public_key),
+                    Err(_) => Ok(false),
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -638,6 +1481,10 @@ impl DeriveSecret<1> for X25519DiffieHellman {
         [self.private_key.clone()]
     }
 
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::DERIVE]
+    }
+
     fn target(&self) -> &Location {
         &self.shared_key
     }
@@ -656,28 +1503,66 @@ impl UseSecret<1> for Hmac {
     type Output = Vec<u8>;
 
     fn use_secret(self, guards: [Buffer<u8>; 1]) -> Result<Self::Output, FatalProcedureError> {
-        match self.hash_type {
-            Sha2Hash::Sha256 => {
-                let mut mac = [0; SHA256_LEN];
-                HMAC_SHA256(&self.msg, &guards[0].borrow(), &mut mac);
-                Ok(mac.to_vec())
-            }
-            Sha2Hash::Sha384 => {
-                let mut mac = [0; SHA384_LEN];
-                HMAC_SHA384(&self.msg, &guards[0].borrow(), &mut mac);
-                Ok(mac.to_vec())
-            }
-            Sha2Hash::Sha512 => {
-                let mut mac = [0; SHA512_LEN];
-                HMAC_SHA512(&self.msg, &guards[0].borrow(), &mut mac);
-                Ok(mac.to_vec())
-            }
+        Ok(hmac(&self.hash_type, &self.msg, &guards[0].borrow()))
+    }
+
+    fn source(&self) -> [Location; 1] {
+        [self.key.clone()]
+    }
+
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::SIGN]
+    }
+}
+
+pub(crate) fn hmac(hash_type: &Sha2Hash, msg: &[u8], key: &[u8]) -> Vec<u8> {
+    match hash_type {
+        Sha2Hash::Sha256 => {
+            let mut mac = [0; SHA256_LEN];
+            HMAC_SHA256(msg, key, &mut mac);
+            mac.to_vec()
+        }
+        Sha2Hash::Sha384 => {
+            let mut mac = [0; SHA384_LEN];
+            HMAC_SHA384(msg, key, &mut mac);
+            mac.to_vec()
+        }
+        Sha2Hash::Sha512 => {
+            let mut mac = [0; SHA512_LEN];
+            HMAC_SHA512(msg, key, &mut mac);
+            mac.to_vec()
         }
     }
+}
+
+/// Verifies the HMAC `mac` of `msg` with the key stored at `key`, without the key leaving the vault. The
+/// comparison is constant-time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HmacVerify {
+    pub hash_type: Sha2Hash,
+
+    pub msg: Vec<u8>,
+
+    pub mac: Vec<u8>,
+
+    pub key: Location,
+}
+
+impl UseSecret<1> for HmacVerify {
+    type Output = bool;
+
+    fn use_secret(self, guards: [Buffer<u8>; 1]) -> Result<Self::Output, FatalProcedureError> {
+        let expected = hmac(&self.hash_type, &self.msg, &guards[0].borrow());
+        Ok(expected.ct_eq(&self.mac).into())
+    }
 
     fn source(&self) -> [Location; 1] {
         [self.key.clone()]
     }
+
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::SIGN]
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -723,6 +1608,10 @@ impl DeriveSecret<1> for Hkdf {
         [self.ikm.clone()]
     }
 
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::DERIVE]
+    }
+
     fn target(&self) -> &Location {
         &self.okm
     }
@@ -777,6 +1666,8 @@ impl Drop for Pbkdf2Hmac {
     }
 }
 
+/// Encrypts the `plaintext` with the key at `key`. The output is `tag || ciphertext`, prefixed with the nonce if the
+/// [`AeadNonce`] is generated by the procedure.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AeadEncrypt {
     pub cipher: AeadCipher,
@@ -785,43 +1676,42 @@ pub struct AeadEncrypt {
 
     pub plaintext: Vec<u8>,
 
-    /// **Note**: The nonce is required to have length [`Aes256Gcm::NONCE_LENGTH`] /
-    /// [`XChaCha20Poly1305::NONCE_LENGTH`], (depending on the [`AeadCipher`])
-    pub nonce: Vec<u8>,
+    /// **Note**: An explicit nonce is required to have length [`AeadCipher::nonce_length`].
+    pub nonce: AeadNonce,
 
     pub key: Location,
 }
 
-impl UseSecret<1> for AeadEncrypt {
+impl Procedure for AeadEncrypt {
     type Output = Vec<u8>;
 
-    fn use_secret(self, guards: [Buffer<u8>; 1]) -> Result<Self::Output, FatalProcedureError> {
-        let mut ctx = vec![0; self.plaintext.len()];
-
+    fn execute<R: Runner>(self, runner: &R) -> Result<Self::Output, ProcedureError> {
+        let nonce = self.nonce.to_bytes(self.cipher, &self.key, runner)?;
         let f = match self.cipher {
             AeadCipher::Aes256Gcm => Aes256Gcm::try_encrypt,
             AeadCipher::XChaCha20Poly1305 => XChaCha20Poly1305::try_encrypt,
+            AeadCipher::Aes256GcmSiv => Aes256GcmSiv::try_encrypt,
+            AeadCipher::ChaCha20Poly1305 => ChaCha20Poly1305::try_encrypt,
         };
-        let mut t = match self.cipher {
-            AeadCipher::Aes256Gcm => Tag::<Aes256Gcm>::default(),
-            AeadCipher::XChaCha20Poly1305 => Tag::<XChaCha20Poly1305>::default(),
-        };
-        f(
-            &guards[0].borrow(),
-            &self.nonce,
-            &self.associated_data,
-            &self.plaintext,
-            &mut ctx,
-            &mut t,
-        )?;
-        let mut output = Vec::with_capacity(t.len() + ctx.len());
-        output.extend(t);
-        output.extend(ctx);
-        Ok(output)
-    }
-
-    fn source(&self) -> [Location; 1] {
-        [self.key.clone()]
+        runner.get_guards([self.key.clone()], [KeyUsage::ENCRYPT], |[key]| {
+            let mut ctx = vec![0; self.plaintext.len()];
+            let mut t = vec![0; self.cipher.tag_length()];
+            f(
+                &key.borrow(),
+                &nonce,
+                &self.associated_data,
+                &self.plaintext,
+                &mut ctx,
+                &mut t,
+            )?;
+            let mut output = Vec::with_capacity(nonce.len() + t.len() + ctx.len());
+            if !matches!(self.nonce, AeadNonce::Explicit(_)) {
+                output.extend(nonce);
+            }
+            output.extend(t);
+            output.extend(ctx);
+            Ok(output)
+        })
     }
 }
 
@@ -849,6 +1739,8 @@ impl UseSecret<1> for AeadDecrypt {
         let f = match self.cipher {
             AeadCipher::Aes256Gcm => Aes256Gcm::try_decrypt,
             AeadCipher::XChaCha20Poly1305 => XChaCha20Poly1305::try_decrypt,
+            AeadCipher::Aes256GcmSiv => Aes256GcmSiv::try_decrypt,
+            AeadCipher::ChaCha20Poly1305 => ChaCha20Poly1305::try_decrypt,
         };
         f(
             &guards[0].borrow(),
@@ -864,6 +1756,10 @@ impl UseSecret<1> for AeadDecrypt {
     fn source(&self) -> [Location; 1] {
         [self.key.clone()]
     }
+
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::ENCRYPT]
+    }
 }
 
 /// Executes the concat KDF as defined in Section 5.8.1 of NIST.800-56A.
@@ -915,6 +1811,10 @@ impl DeriveSecret<1> for ConcatKdf {
         [self.shared_secret.clone()]
     }
 
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::DERIVE]
+    }
+
     fn target(&self) -> &Location {
         &self.output
     }
@@ -1001,6 +1901,10 @@ impl UseSecret<2> for AesKeyWrapEncrypt {
     fn source(&self) -> [Location; 2] {
         [self.encryption_key.clone(), self.wrap_key.clone()]
     }
+
+    fn usage(&self) -> [KeyUsage; 2] {
+        [KeyUsage::ENCRYPT, KeyUsage::EXPORT]
+    }
 }
 
 impl AesKeyWrapEncrypt {
@@ -1044,6 +1948,10 @@ impl DeriveSecret<1> for AesKeyWrapDecrypt {
         [self.decryption_key.clone()]
     }
 
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::ENCRYPT]
+    }
+
     fn target(&self) -> &Location {
         &self.output
     }
@@ -1098,9 +2006,14 @@ impl UseSecret<1> for CompareSecret {
     fn source(&self) -> [Location; 1] {
         [self.location.clone()]
     }
+
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::EXPORT]
+    }
 }
 
-/// Concatenates two secrets and stores the result at a new location
+/// Concatenates two secrets and stores the result at a new location. As the result contains both secrets, both
+/// records have to permit [`KeyUsage::EXPORT`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConcatSecret {
     /// The location of the first secret to be concatenated
@@ -1133,6 +2046,10 @@ impl DeriveSecret<2> for ConcatSecret {
         [self.location_a.clone(), self.location_b.clone()]
     }
 
+    fn usage(&self) -> [KeyUsage; 2] {
+        [KeyUsage::EXPORT, KeyUsage::EXPORT]
+    }
+
     fn target(&self) -> &Location {
         &self.output_location
     }
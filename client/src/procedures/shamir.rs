@@ -0,0 +1,396 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Shamir secret sharing over GF(256), as specified by SLIP-39. The secret is the value of the polynomial at
+//! x = 255, the value at x = 254 holds a digest of the secret, which is verified on recovery.
+
+use crypto::{
+    ciphers::{chacha::XChaCha20Poly1305, traits::Aead},
+    hashes::sha::Sha256,
+    keys::x25519,
+    macs::hmac::HMAC_SHA256,
+    utils::rand::fill,
+};
+use engine::runtime::memories::buffer::Buffer;
+use serde::{Deserialize, Serialize};
+use stronghold_utils::GuardDebug;
+use zeroize::Zeroizing;
+
+use super::{
+    slip39::{combine_mnemonics, split_mnemonics},
+    types::*,
+};
+use crate::{KeyUsage, Location};
+
+const SECRET_INDEX: u8 = 255;
+const DIGEST_INDEX: u8 = 254;
+const DIGEST_LENGTH: usize = 4;
+
+/// Info string of the key derivation for shares that are encrypted to a recipient.
+const SHARE_ENCRYPTION_INFO: &[u8] = b"stronghold-shamir-share";
+
+/// A share with its x-coordinate.
+pub(crate) type Share = (u8, Zeroizing<Vec<u8>>);
+
+/// Exponent and logarithm tables of GF(256) with the Rijndael polynomial x^8 + x^4 + x^3 + x + 1.
+fn tables() -> ([u8; 255], [u8; 256]) {
+    let mut exp = [0u8; 255];
+    let mut log = [0u8; 256];
+    let mut poly = 1u16;
+    for (i, e) in exp.iter_mut().enumerate() {
+        *e = poly as u8;
+        log[poly as usize] = i as u8;
+        // multiply by the generator x + 1
+        poly = (poly << 1) ^ poly;
+        if poly & 0x100 != 0 {
+            poly ^= 0x11b;
+        }
+    }
+    (exp, log)
+}
+
+/// Evaluates the polynomial through `shares` at `x` with Lagrange interpolation.
+fn interpolate(shares: &[(u8, &[u8])], x: u8) -> Result<Zeroizing<Vec<u8>>, FatalProcedureError> {
+    let len = shares
+        .first()
+        .map(|(_, value)| value.len())
+        .ok_or_else(|| FatalProcedureError::from("no shares given".to_string()))?;
+    if shares.iter().any(|(_, value)| value.len() != len) {
+        return Err("shares have different lengths".to_string().into());
+    }
+    for (i, (xi, _)) in shares.iter().enumerate() {
+        if shares[..i].iter().any(|(xj, _)| xi == xj) {
+            return Err("shares have duplicate indices".to_string().into());
+        }
+    }
+    if let Some((_, value)) = shares.iter().find(|(xi, _)| *xi == x) {
+        return Ok(Zeroizing::new(value.to_vec()));
+    }
+
+    let (exp, log) = tables();
+    let log_prod: u32 = shares.iter().map(|(xi, _)| log[(xi ^ x) as usize] as u32).sum();
+    let mut result = Zeroizing::new(vec![0u8; len]);
+    for (xi, value) in shares {
+        let log_others: u32 = shares
+            .iter()
+            .filter(|(xj, _)| xj != xi)
+            .map(|(xj, _)| log[(xi ^ xj) as usize] as u32)
+            .sum();
+        let log_basis = (log_prod + 255 * shares.len() as u32 - log[(xi ^ x) as usize] as u32 - log_others) % 255;
+        for (r, &y) in result.iter_mut().zip(value.iter()) {
+            if y != 0 {
+                *r ^= exp[((log[y as usize] as u32 + log_basis) % 255) as usize];
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn digest(random: &[u8], secret: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut mac = [0u8; 32];
+    HMAC_SHA256(secret, random, &mut mac);
+    let mut digest = [0u8; DIGEST_LENGTH];
+    digest.copy_from_slice(&mac[..DIGEST_LENGTH]);
+    digest
+}
+
+/// Splits `secret` into `share_count` shares with indices `0..share_count`, of which `threshold` are required to
+/// recover it.
+pub(crate) fn split_secret(threshold: u8, share_count: u8, secret: &[u8]) -> Result<Vec<Share>, FatalProcedureError> {
+    if threshold == 0 || threshold > share_count {
+        return Err("threshold has to be between 1 and the number of shares"
+            .to_string()
+            .into());
+    }
+    if share_count > DIGEST_INDEX {
+        return Err(format!("at most {} shares are supported", DIGEST_INDEX).into());
+    }
+    if secret.len() <= DIGEST_LENGTH {
+        return Err(format!("secret has to be longer than {} bytes", DIGEST_LENGTH).into());
+    }
+    if threshold == 1 {
+        return Ok((0..share_count).map(|i| (i, Zeroizing::new(secret.to_vec()))).collect());
+    }
+
+    let random_count = threshold - 2;
+    let mut shares: Vec<Share> = Vec::with_capacity(share_count as usize);
+    for i in 0..random_count {
+        let mut value = Zeroizing::new(vec![0u8; secret.len()]);
+        fill(&mut value)?;
+        shares.push((i, value));
+    }
+
+    let mut digest_share = Zeroizing::new(vec![0u8; secret.len()]);
+    fill(&mut digest_share[DIGEST_LENGTH..])?;
+    let digest = digest(&digest_share[DIGEST_LENGTH..], secret);
+    digest_share[..DIGEST_LENGTH].copy_from_slice(&digest);
+
+    let mut base: Vec<(u8, &[u8])> = shares.iter().map(|(x, value)| (*x, value.as_slice())).collect();
+    base.push((DIGEST_INDEX, &digest_share));
+    base.push((SECRET_INDEX, secret));
+    let derived = (random_count..share_count)
+        .map(|x| interpolate(&base, x).map(|value| (x, value)))
+        .collect::<Result<Vec<Share>, _>>()?;
+    shares.extend(derived);
+    Ok(shares)
+}
+
+/// Recovers the secret from at least `threshold` shares and verifies its digest.
+pub(crate) fn recover_secret(threshold: u8, shares: &[Share]) -> Result<Zeroizing<Vec<u8>>, FatalProcedureError> {
+    if shares.len() < threshold as usize || shares.is_empty() {
+        return Err(format!("at least {} shares are required", threshold).into());
+    }
+    let shares: Vec<(u8, &[u8])> = shares.iter().map(|(x, value)| (*x, value.as_slice())).collect();
+    if threshold == 1 {
+        return Ok(Zeroizing::new(shares[0].1.to_vec()));
+    }
+
+    let secret = interpolate(&shares, SECRET_INDEX)?;
+    let digest_share = interpolate(&shares, DIGEST_INDEX)?;
+    if digest_share[..DIGEST_LENGTH] != digest(&digest_share[DIGEST_LENGTH..], &secret) {
+        return Err("share digest verification failed".to_string().into());
+    }
+    Ok(secret)
+}
+
+/// Derives the key that encrypts a share for a recipient from the Diffie-Hellman shared secret.
+fn share_encryption_key(shared: &[u8], ephemeral: &[u8], recipient: &[u8]) -> Zeroizing<Vec<u8>> {
+    let mut salt = Vec::with_capacity(ephemeral.len() + recipient.len());
+    salt.extend_from_slice(ephemeral);
+    salt.extend_from_slice(recipient);
+    let mut key = Zeroizing::new(vec![0u8; XChaCha20Poly1305::KEY_LENGTH]);
+    hkdf::Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(SHARE_ENCRYPTION_INFO, &mut key)
+        .expect("key is the correct length");
+    key
+}
+
+/// Encrypts `share` to the X25519 public key `recipient`. The output is
+/// `ephemeral public key || nonce || tag || ciphertext`.
+fn encrypt_share(share: &[u8], recipient: &[u8; x25519::PUBLIC_KEY_LENGTH]) -> Result<Vec<u8>, FatalProcedureError> {
+    let ephemeral = x25519::SecretKey::generate()?;
+    let ephemeral_public = ephemeral.public_key();
+    let shared = ephemeral.diffie_hellman(&x25519::PublicKey::from_bytes(*recipient));
+    let key = share_encryption_key(shared.as_bytes(), ephemeral_public.as_slice(), recipient);
+
+    let mut nonce = [0u8; XChaCha20Poly1305::NONCE_LENGTH];
+    fill(&mut nonce)?;
+    let mut tag = [0u8; XChaCha20Poly1305::TAG_LENGTH];
+    let mut ciphertext = vec![0u8; share.len()];
+    XChaCha20Poly1305::try_encrypt(&key, &nonce, &[], share, &mut ciphertext, &mut tag)?;
+
+    let mut output = ephemeral_public.to_bytes().to_vec();
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&tag);
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
+}
+
+/// Decrypts a share that has been encrypted to the public key of `secret_key` by [`ShamirSplit`].
+pub fn decrypt_share(secret_key: &x25519::SecretKey, encrypted: &[u8]) -> Result<Vec<u8>, FatalProcedureError> {
+    let header = x25519::PUBLIC_KEY_LENGTH + XChaCha20Poly1305::NONCE_LENGTH + XChaCha20Poly1305::TAG_LENGTH;
+    if encrypted.len() < header {
+        return Err("encrypted share is too short".to_string().into());
+    }
+    let (ephemeral, rest) = encrypted.split_at(x25519::PUBLIC_KEY_LENGTH);
+    let (nonce, rest) = rest.split_at(XChaCha20Poly1305::NONCE_LENGTH);
+    let (tag, ciphertext) = rest.split_at(XChaCha20Poly1305::TAG_LENGTH);
+
+    let shared = secret_key.diffie_hellman(&x25519::PublicKey::try_from_slice(ephemeral)?);
+    let key = share_encryption_key(shared.as_bytes(), ephemeral, secret_key.public_key().as_slice());
+    let mut share = vec![0u8; ciphertext.len()];
+    XChaCha20Poly1305::try_decrypt(&key, nonce, &[], &mut share, ciphertext, tag)?;
+    Ok(share)
+}
+
+/// Encoding of the share records written by [`ShamirSplit`] and read by [`ShamirCombine`].
+#[derive(Clone, GuardDebug, Serialize, Deserialize)]
+pub enum ShareEncoding {
+    /// `threshold || index || value` of the share.
+    Raw,
+
+    /// SLIP-39 mnemonic of a single group as UTF-8 string. The secret is encrypted with `passphrase` before
+    /// splitting, the PBKDF2 iteration count is `10000 << iteration_exponent`.
+    Slip39 {
+        passphrase: Vec<u8>,
+        iteration_exponent: u8,
+    },
+}
+
+/// Splits the secret at `secret` into `outputs.len()` shares, of which `threshold` are required to recover it
+/// with [`ShamirCombine`]. Each share is written into the respective output location.
+///
+/// If `recipients` is not empty, it has to contain one X25519 public key per output. Each share is then
+/// additionally encrypted to the respective recipient and returned, see [`decrypt_share`].
+#[derive(Clone, GuardDebug, Serialize, Deserialize)]
+pub struct ShamirSplit {
+    pub secret: Location,
+
+    pub threshold: u8,
+
+    pub outputs: Vec<Location>,
+
+    pub recipients: Vec<[u8; x25519::PUBLIC_KEY_LENGTH]>,
+
+    pub encoding: ShareEncoding,
+}
+
+/// Encoded shares of a [`ShamirSplit`], and their encryption to the recipients.
+pub struct ShamirShares {
+    shares: Vec<Zeroizing<Vec<u8>>>,
+    encrypted: Vec<Vec<u8>>,
+}
+
+/// Shares of a [`ShamirSplit`] encrypted to the recipients, in the order of the recipients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedShares(pub Vec<Vec<u8>>);
+
+impl From<EncryptedShares> for ProcedureOutput {
+    fn from(shares: EncryptedShares) -> Self {
+        bincode::serialize(&shares)
+            .expect("serializing a list of byte strings can't fail")
+            .into()
+    }
+}
+
+impl TryFrom<ProcedureOutput> for EncryptedShares {
+    type Error = bincode::Error;
+
+    fn try_from(value: ProcedureOutput) -> Result<Self, Self::Error> {
+        bincode::deserialize(&Vec::<u8>::from(value))
+    }
+}
+
+impl UseSecret<1> for ShamirSplit {
+    type Output = ShamirShares;
+
+    fn use_secret(self, guards: [Buffer<u8>; 1]) -> Result<Self::Output, FatalProcedureError> {
+        let share_count: u8 = self
+            .outputs
+            .len()
+            .try_into()
+            .map_err(|_| FatalProcedureError::from("too many shares".to_string()))?;
+        if !self.recipients.is_empty() && self.recipients.len() != self.outputs.len() {
+            return Err("number of recipients has to match the number of outputs"
+                .to_string()
+                .into());
+        }
+
+        let secret = guards[0].borrow();
+        let shares: Vec<Zeroizing<Vec<u8>>> = match &self.encoding {
+            ShareEncoding::Raw => split_secret(self.threshold, share_count, &secret)?
+                .into_iter()
+                .map(|(x, value)| {
+                    let mut share = Zeroizing::new(Vec::with_capacity(value.len() + 2));
+                    share.extend_from_slice(&[self.threshold, x]);
+                    share.extend_from_slice(&value);
+                    share
+                })
+                .collect(),
+            ShareEncoding::Slip39 {
+                passphrase,
+                iteration_exponent,
+            } => split_mnemonics(&secret, passphrase, *iteration_exponent, self.threshold, share_count)?
+                .into_iter()
+                .map(|mnemonic| Zeroizing::new(mnemonic.as_bytes().to_vec()))
+                .collect(),
+        };
+
+        let encrypted = self
+            .recipients
+            .iter()
+            .zip(shares.iter())
+            .map(|(recipient, share)| encrypt_share(share, recipient))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ShamirShares { shares, encrypted })
+    }
+
+    fn source(&self) -> [Location; 1] {
+        [self.secret.clone()]
+    }
+
+    fn usage(&self) -> [KeyUsage; 1] {
+        [KeyUsage::EXPORT]
+    }
+}
+
+impl Procedure for ShamirSplit {
+    type Output = EncryptedShares;
+
+    fn execute<R: Runner>(self, runner: &R) -> Result<Self::Output, ProcedureError> {
+        let outputs = self.outputs.clone();
+        let ShamirShares { shares, encrypted } = self.exec(runner)?;
+        for (i, (location, share)) in outputs.iter().zip(shares.iter()).enumerate() {
+            if let Err(e) = runner.write_to_vault(location, share.to_vec()) {
+                for location in &outputs[..i] {
+                    let _ = runner.revoke_data(location);
+                }
+                return Err(e.into());
+            }
+        }
+        Ok(EncryptedShares(encrypted))
+    }
+}
+
+/// Recovers a secret from the share records at `shares`, which have been written by [`ShamirSplit`] with the same
+/// `encoding`, and writes it into `output`.
+#[derive(Clone, GuardDebug, Serialize, Deserialize)]
+pub struct ShamirCombine {
+    pub shares: Vec<Location>,
+
+    pub encoding: ShareEncoding,
+
+    pub output: Location,
+}
+
+impl ShamirCombine {
+    fn combine(&self, shares: &[Zeroizing<Vec<u8>>]) -> Result<Zeroizing<Vec<u8>>, FatalProcedureError> {
+        match &self.encoding {
+            ShareEncoding::Raw => {
+                let threshold = shares
+                    .first()
+                    .and_then(|share| share.first())
+                    .copied()
+                    .ok_or_else(|| FatalProcedureError::from("no shares given".to_string()))?;
+                let shares = shares
+                    .iter()
+                    .map(|share| match share.as_slice() {
+                        [t, x, value @ ..] if *t == threshold => Ok((*x, Zeroizing::new(value.to_vec()))),
+                        _ => Err(FatalProcedureError::from("invalid share".to_string())),
+                    })
+                    .collect::<Result<Vec<Share>, _>>()?;
+                recover_secret(threshold, &shares)
+            }
+            ShareEncoding::Slip39 { passphrase, .. } => {
+                let mnemonics = shares
+                    .iter()
+                    .map(|share| std::str::from_utf8(share))
+                    .collect::<Result<Vec<&str>, _>>()
+                    .map_err(|e| FatalProcedureError::from(e.to_string()))?;
+                combine_mnemonics(&mnemonics, passphrase)
+            }
+        }
+    }
+}
+
+impl Procedure for ShamirCombine {
+    type Output = ();
+
+    fn execute<R: Runner>(self, runner: &R) -> Result<Self::Output, ProcedureError> {
+        let mut shares = Vec::with_capacity(self.shares.len());
+        for location in &self.shares {
+            let share = runner.get_guards([location.clone()], [KeyUsage::DERIVE], |[guard]| {
+                Ok(Zeroizing::new(guard.borrow().to_vec()))
+            })?;
+            shares.push(share);
+        }
+
+        runner.exec_proc([], [], &self.output, None, |_| {
+            let secret = self.combine(&shares)?;
+            Ok(Products {
+                secret: secret.to_vec(),
+                output: (),
+            })
+        })
+    }
+}
@@ -0,0 +1,463 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! SLIP-39 mnemonic encoding of Shamir shares, see <https://github.com/satoshilabs/slips/blob/master/slip-0039.md>.
+
+use std::{collections::BTreeMap, num::NonZeroU32};
+
+use crypto::keys::pbkdf::PBKDF2_HMAC_SHA256;
+use zeroize::{Zeroize, Zeroizing};
+
+use super::shamir::{recover_secret, split_secret};
+use crate::procedures::FatalProcedureError;
+
+const WORDLIST: &str = include_str!("wordlists/slip39_english.txt");
+
+/// Number of bits encoded by a single word.
+const RADIX_BITS: usize = 10;
+/// Number of bits of the share identifier.
+const ID_BITS: usize = 15;
+/// Number of words of the share identifier, extendable flag and iteration exponent.
+const ID_EXP_WORDS: usize = 2;
+/// Number of words of the identifier, iteration exponent, group and member parameters.
+const METADATA_WORDS: usize = ID_EXP_WORDS + 2;
+/// Number of words of the checksum.
+const CHECKSUM_WORDS: usize = 3;
+/// Minimum length of the master secret in bytes.
+const MIN_SECRET_LENGTH: usize = 16;
+/// Maximum number of shares of a group and of groups.
+const MAX_SHARE_COUNT: usize = 16;
+
+const BASE_ITERATION_COUNT: u32 = 10000;
+const ROUND_COUNT: u8 = 4;
+
+const CUSTOMIZATION_STRING: &[u8] = b"shamir";
+const CUSTOMIZATION_STRING_EXTENDABLE: &[u8] = b"shamir_extendable";
+
+/// A single share of a SLIP-39 mnemonic.
+struct Share {
+    identifier: u16,
+    extendable: bool,
+    iteration_exponent: u8,
+    group_index: u8,
+    group_threshold: u8,
+    group_count: u8,
+    member_index: u8,
+    member_threshold: u8,
+    value: Zeroizing<Vec<u8>>,
+}
+
+fn wordlist() -> impl Iterator<Item = &'static str> {
+    WORDLIST.lines()
+}
+
+fn word_index(word: &str) -> Option<u16> {
+    wordlist().position(|w| w == word).map(|i| i as u16)
+}
+
+fn rs1024_polymod(values: impl Iterator<Item = u16>) -> u32 {
+    const GEN: [u32; 10] = [
+        0xe0e040, 0x1c1c080, 0x3838100, 0x7070200, 0xe0e0009, 0x1c0c2412, 0x38086c24, 0x3090fc48, 0x21b1f890, 0x3f3f120,
+    ];
+    let mut chk = 1u32;
+    for v in values {
+        let b = chk >> 20;
+        chk = ((chk & 0xfffff) << 10) ^ v as u32;
+        for (i, gen) in GEN.iter().enumerate() {
+            if (b >> i) & 1 == 1 {
+                chk ^= gen;
+            }
+        }
+    }
+    chk
+}
+
+fn customization(extendable: bool) -> &'static [u8] {
+    if extendable {
+        CUSTOMIZATION_STRING_EXTENDABLE
+    } else {
+        CUSTOMIZATION_STRING
+    }
+}
+
+fn rs1024_checksum(data: &[u16], extendable: bool) -> [u16; CHECKSUM_WORDS] {
+    let values = customization(extendable)
+        .iter()
+        .map(|&c| c as u16)
+        .chain(data.iter().copied())
+        .chain([0; CHECKSUM_WORDS]);
+    let polymod = rs1024_polymod(values) ^ 1;
+    let mut checksum = [0u16; CHECKSUM_WORDS];
+    for (i, word) in checksum.iter_mut().enumerate() {
+        *word = ((polymod >> (RADIX_BITS * (CHECKSUM_WORDS - 1 - i))) & 1023) as u16;
+    }
+    checksum
+}
+
+fn rs1024_verify(data: &[u16], extendable: bool) -> bool {
+    let values = customization(extendable)
+        .iter()
+        .map(|&c| c as u16)
+        .chain(data.iter().copied());
+    rs1024_polymod(values) == 1
+}
+
+fn salt(identifier: u16, extendable: bool) -> Vec<u8> {
+    if extendable {
+        Vec::new()
+    } else {
+        let mut salt = CUSTOMIZATION_STRING.to_vec();
+        salt.extend_from_slice(&identifier.to_be_bytes());
+        salt
+    }
+}
+
+/// Runs the Feistel network that encrypts or decrypts the master secret with the passphrase.
+fn feistel(
+    input: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+    rounds: impl Iterator<Item = u8>,
+) -> Zeroizing<Vec<u8>> {
+    let half = input.len() / 2;
+    let mut l = Zeroizing::new(input[..half].to_vec());
+    let mut r = Zeroizing::new(input[half..].to_vec());
+    let salt = salt(identifier, extendable);
+    let iterations = (BASE_ITERATION_COUNT << iteration_exponent) / ROUND_COUNT as u32;
+    let iterations = NonZeroU32::new(iterations).expect("iteration count is not zero");
+
+    for i in rounds {
+        let mut password = Zeroizing::new(vec![i]);
+        password.extend_from_slice(passphrase);
+        let mut round_salt = salt.clone();
+        round_salt.extend_from_slice(&r);
+        let mut f = Zeroizing::new(vec![0u8; r.len()]);
+        PBKDF2_HMAC_SHA256(&password, &round_salt, iterations, &mut f);
+        let mut next = Zeroizing::new(l.iter().zip(f.iter()).map(|(a, b)| a ^ b).collect::<Vec<u8>>());
+        std::mem::swap(&mut l, &mut r);
+        std::mem::swap(&mut r, &mut next);
+    }
+
+    let mut output = Zeroizing::new(Vec::with_capacity(input.len()));
+    output.extend_from_slice(&r);
+    output.extend_from_slice(&l);
+    output
+}
+
+fn encrypt(
+    secret: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+) -> Zeroizing<Vec<u8>> {
+    feistel(
+        secret,
+        passphrase,
+        iteration_exponent,
+        identifier,
+        extendable,
+        0..ROUND_COUNT,
+    )
+}
+
+fn decrypt(
+    encrypted: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+) -> Zeroizing<Vec<u8>> {
+    feistel(
+        encrypted,
+        passphrase,
+        iteration_exponent,
+        identifier,
+        extendable,
+        (0..ROUND_COUNT).rev(),
+    )
+}
+
+/// Appends `bits` bits of `value` to the word stream.
+struct BitWriter {
+    words: Vec<u16>,
+    acc: u32,
+    len: usize,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            words: Vec::new(),
+            acc: 0,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, value: u32, bits: usize) {
+        for i in (0..bits).rev() {
+            self.acc = (self.acc << 1) | ((value >> i) & 1);
+            self.len += 1;
+            if self.len == RADIX_BITS {
+                self.words.push(self.acc as u16);
+                self.acc = 0;
+                self.len = 0;
+            }
+        }
+    }
+}
+
+impl Drop for BitWriter {
+    fn drop(&mut self) {
+        self.words.zeroize();
+        self.acc.zeroize();
+    }
+}
+
+fn encode_share(share: &Share) -> Zeroizing<String> {
+    let mut writer = BitWriter::new();
+    writer.push(share.identifier as u32, ID_BITS);
+    writer.push(share.extendable as u32, 1);
+    writer.push(share.iteration_exponent as u32, 4);
+    writer.push(share.group_index as u32, 4);
+    writer.push(share.group_threshold as u32 - 1, 4);
+    writer.push(share.group_count as u32 - 1, 4);
+    writer.push(share.member_index as u32, 4);
+    writer.push(share.member_threshold as u32 - 1, 4);
+
+    let value_bits = share.value.len() * 8;
+    let padding = (RADIX_BITS - value_bits % RADIX_BITS) % RADIX_BITS;
+    writer.push(0, padding);
+    for &byte in share.value.iter() {
+        writer.push(byte as u32, 8);
+    }
+
+    let mut words = Zeroizing::new(writer.words.clone());
+    let checksum = rs1024_checksum(&words, share.extendable);
+    words.extend(checksum);
+
+    let wordlist: Vec<&str> = wordlist().collect();
+    let mnemonic = words
+        .iter()
+        .map(|&i| wordlist[i as usize])
+        .collect::<Vec<&str>>()
+        .join(" ");
+    Zeroizing::new(mnemonic)
+}
+
+fn decode_share(mnemonic: &str) -> Result<Share, FatalProcedureError> {
+    let words = mnemonic
+        .split_whitespace()
+        .map(|word| word_index(&word.to_lowercase()))
+        .collect::<Option<Vec<u16>>>()
+        .ok_or_else(|| FatalProcedureError::from("invalid SLIP-39 mnemonic word".to_string()))?;
+    let words = Zeroizing::new(words);
+
+    let min_words = METADATA_WORDS + (MIN_SECRET_LENGTH * 8).div_ceil(RADIX_BITS) + CHECKSUM_WORDS;
+    if words.len() < min_words {
+        return Err("SLIP-39 mnemonic is too short".to_string().into());
+    }
+    let padding = (RADIX_BITS * (words.len() - METADATA_WORDS - CHECKSUM_WORDS)) % 16;
+    if padding > 8 {
+        return Err("invalid SLIP-39 mnemonic length".to_string().into());
+    }
+
+    let id_exp = ((words[0] as u32) << RADIX_BITS) | words[1] as u32;
+    let identifier = (id_exp >> 5) as u16;
+    let extendable = (id_exp >> 4) & 1 == 1;
+    let iteration_exponent = (id_exp & 0xf) as u8;
+    if !rs1024_verify(&words, extendable) {
+        return Err("invalid SLIP-39 mnemonic checksum".to_string().into());
+    }
+
+    let params = ((words[2] as u32) << RADIX_BITS) | words[3] as u32;
+    let nibble = |i: u32| ((params >> (16 - 4 * i)) & 0xf) as u8;
+    let (group_index, group_threshold, group_count) = (nibble(0), nibble(1) + 1, nibble(2) + 1);
+    let (member_index, member_threshold) = (nibble(3), nibble(4) + 1);
+    if group_count < group_threshold {
+        return Err("invalid SLIP-39 group threshold".to_string().into());
+    }
+
+    // Unpack the share value, the leading padding bits have to be zero
+    let value_words = &words[METADATA_WORDS..words.len() - CHECKSUM_WORDS];
+    let mut bits = Zeroizing::new(Vec::with_capacity(value_words.len() * RADIX_BITS));
+    for &word in value_words {
+        bits.extend((0..RADIX_BITS).rev().map(|i| ((word >> i) & 1) as u8));
+    }
+    if bits[..padding].iter().any(|&bit| bit != 0) {
+        return Err("invalid SLIP-39 mnemonic padding".to_string().into());
+    }
+    let value = bits[padding..]
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0u8, |acc, bit| (acc << 1) | bit))
+        .collect();
+
+    Ok(Share {
+        identifier,
+        extendable,
+        iteration_exponent,
+        group_index,
+        group_threshold,
+        group_count,
+        member_index,
+        member_threshold,
+        value: Zeroizing::new(value),
+    })
+}
+
+/// Splits `secret` into `share_count` SLIP-39 mnemonics of a single group, of which `threshold` are required to
+/// recover the secret. The secret is encrypted with `passphrase`, the PBKDF2 iteration count is
+/// `10000 << iteration_exponent`.
+pub(crate) fn split_mnemonics(
+    secret: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    threshold: u8,
+    share_count: u8,
+) -> Result<Vec<Zeroizing<String>>, FatalProcedureError> {
+    if secret.len() < MIN_SECRET_LENGTH || !secret.len().is_multiple_of(2) {
+        return Err(format!(
+            "SLIP-39 secrets have to be an even number of at least {} bytes",
+            MIN_SECRET_LENGTH
+        )
+        .into());
+    }
+    if share_count as usize > MAX_SHARE_COUNT {
+        return Err(format!("SLIP-39 supports at most {} shares", MAX_SHARE_COUNT).into());
+    }
+    if iteration_exponent > 15 {
+        return Err("SLIP-39 iteration exponent exceeds 15".to_string().into());
+    }
+
+    let mut random = [0u8; 2];
+    crypto::utils::rand::fill(&mut random)?;
+    let identifier = u16::from_be_bytes(random) >> 1;
+
+    let encrypted = encrypt(secret, passphrase, iteration_exponent, identifier, false);
+    let shares = split_secret(threshold, share_count, &encrypted)?;
+    let mnemonics = shares
+        .into_iter()
+        .map(|(member_index, value)| {
+            encode_share(&Share {
+                identifier,
+                extendable: false,
+                iteration_exponent,
+                group_index: 0,
+                group_threshold: 1,
+                group_count: 1,
+                member_index,
+                member_threshold: threshold,
+                value,
+            })
+        })
+        .collect();
+    Ok(mnemonics)
+}
+
+/// Recovers the secret from SLIP-39 `mnemonics`, decrypting it with `passphrase`.
+pub(crate) fn combine_mnemonics<S: AsRef<str>>(
+    mnemonics: &[S],
+    passphrase: &[u8],
+) -> Result<Zeroizing<Vec<u8>>, FatalProcedureError> {
+    let shares = mnemonics
+        .iter()
+        .map(|m| decode_share(m.as_ref()))
+        .collect::<Result<Vec<Share>, _>>()?;
+    let first = shares
+        .first()
+        .ok_or_else(|| FatalProcedureError::from("no SLIP-39 mnemonics given".to_string()))?;
+
+    let mut groups: BTreeMap<u8, Vec<&Share>> = BTreeMap::new();
+    for share in &shares {
+        if (share.identifier, share.extendable, share.iteration_exponent)
+            != (first.identifier, first.extendable, first.iteration_exponent)
+            || (share.group_threshold, share.group_count) != (first.group_threshold, first.group_count)
+        {
+            return Err("SLIP-39 mnemonics belong to different secrets".to_string().into());
+        }
+        groups.entry(share.group_index).or_default().push(share);
+    }
+    if groups.len() < first.group_threshold as usize {
+        return Err("not enough SLIP-39 groups".to_string().into());
+    }
+
+    let mut group_shares = Vec::with_capacity(groups.len());
+    for (group_index, members) in groups {
+        let threshold = members[0].member_threshold;
+        if members.iter().any(|share| share.member_threshold != threshold) {
+            return Err("SLIP-39 mnemonics of a group have different thresholds"
+                .to_string()
+                .into());
+        }
+        let members: Vec<(u8, Zeroizing<Vec<u8>>)> = members
+            .iter()
+            .map(|share| (share.member_index, share.value.clone()))
+            .collect();
+        let group_secret = recover_secret(threshold, &members)?;
+        group_shares.push((group_index, group_secret));
+    }
+    let encrypted = recover_secret(first.group_threshold, &group_shares)?;
+
+    Ok(decrypt(
+        &encrypted,
+        passphrase,
+        first.iteration_exponent,
+        first.identifier,
+        first.extendable,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slip39_vector() {
+        // Test vector 1 of SLIP-39: valid mnemonic without sharing (128 bits)
+        let mnemonic = "duckling enlarge academic academic agency result length solution fridge kidney coal piece \
+                        deal husband erode duke ajar critical decision keyboard";
+        let secret = combine_mnemonics(&[mnemonic], b"TREZOR").unwrap();
+        assert_eq!(
+            secret.as_slice(),
+            [0xbb, 0x54, 0xaa, 0xc4, 0xb8, 0x9d, 0xc8, 0x68, 0xba, 0x37, 0xd9, 0xcc, 0x21, 0xb2, 0xce, 0xce]
+        );
+
+        // A changed word fails the checksum
+        let invalid = mnemonic.replace("keyboard", "kidney");
+        assert!(combine_mnemonics(&[invalid], b"TREZOR").is_err());
+    }
+
+    #[test]
+    fn test_slip39_vector_sharing() {
+        // Test vector 4 of SLIP-39: basic sharing 2-of-3 (128 bits)
+        let mnemonics = [
+            "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view \
+             short owner flip making coding armed",
+            "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind \
+             craft early superior advocate guest smoking",
+        ];
+        let secret = combine_mnemonics(&mnemonics, b"TREZOR").unwrap();
+        assert_eq!(
+            secret.as_slice(),
+            [0xb4, 0x3c, 0xeb, 0x7e, 0x57, 0xa0, 0xea, 0x87, 0x66, 0x22, 0x16, 0x24, 0xd0, 0x1b, 0x08, 0x64]
+        );
+
+        // A single share is below the member threshold
+        assert!(combine_mnemonics(&mnemonics[..1], b"TREZOR").is_err());
+    }
+
+    #[test]
+    fn test_slip39_roundtrip() {
+        let secret = [0x42u8; 32];
+        let mnemonics = split_mnemonics(&secret, b"passphrase", 0, 3, 5).unwrap();
+        assert_eq!(mnemonics.len(), 5);
+        assert_eq!(mnemonics[0].split(' ').count(), METADATA_WORDS + 26 + CHECKSUM_WORDS);
+
+        let recovered = combine_mnemonics(&[&mnemonics[4], &mnemonics[0], &mnemonics[2]], b"passphrase").unwrap();
+        assert_eq!(recovered.as_slice(), secret);
+
+        assert!(combine_mnemonics(&[&mnemonics[4], &mnemonics[0]], b"passphrase").is_err());
+    }
+}
@@ -0,0 +1,218 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Transfer of single records between Strongholds.
+//!
+//! A record is exported into an envelope that is encrypted to the X25519 public key of the recipient and
+//! authenticated by the X25519 key of the sender. The envelope has the layout
+//!
+//! `magic || version || ephemeral public key || sender public key || nonce || tag || ciphertext`
+//!
+//! The encryption key is derived with HKDF-SHA256 from the Diffie-Hellman shared secrets of the ephemeral key and of
+//! the sender key with the recipient key. The header and the recipient public key are authenticated as associated
+//! data of the XChaCha20Poly1305 encryption. The plaintext contains the secret, hint and [`RecordMetadata`] of the
+//! record.
+
+use crypto::{
+    ciphers::{chacha::XChaCha20Poly1305, traits::Aead},
+    hashes::sha::Sha256,
+    keys::x25519,
+    utils::rand::fill,
+};
+use engine::vault::RecordHint;
+use serde::{Deserialize, Serialize};
+use stronghold_utils::GuardDebug;
+use zeroize::{Zeroize, Zeroizing};
+
+use super::types::*;
+use crate::{KeyUsage, Location, RecordMetadata};
+
+/// Magic bytes at the start of an envelope created by [`ExportRecord`].
+pub const RECORD_ENVELOPE_MAGIC: [u8; 4] = *b"SHRE";
+
+/// Current version of the envelope format.
+pub const RECORD_ENVELOPE_VERSION: u8 = 1;
+
+/// Info string of the key derivation for the envelope encryption.
+const RECORD_ENVELOPE_INFO: &[u8] = b"stronghold-record-envelope";
+
+/// Length of `magic || version || ephemeral public key || sender public key`.
+const HEADER_LENGTH: usize = RECORD_ENVELOPE_MAGIC.len() + 1 + 2 * x25519::PUBLIC_KEY_LENGTH;
+
+/// Content of an envelope.
+#[derive(Serialize, Deserialize)]
+struct RecordPayload {
+    secret: Vec<u8>,
+    hint: RecordHint,
+    metadata: Option<RecordMetadata>,
+}
+
+impl Drop for RecordPayload {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+/// Derives the envelope key from the shared secrets of the ephemeral and the sender key with the recipient.
+fn envelope_key(ephemeral_shared: &[u8], sender_shared: &[u8], associated_data: &[u8]) -> Zeroizing<Vec<u8>> {
+    let mut ikm = Zeroizing::new(Vec::with_capacity(ephemeral_shared.len() + sender_shared.len()));
+    ikm.extend_from_slice(ephemeral_shared);
+    ikm.extend_from_slice(sender_shared);
+    let mut key = Zeroizing::new(vec![0u8; XChaCha20Poly1305::KEY_LENGTH]);
+    hkdf::Hkdf::<Sha256>::new(Some(associated_data), &ikm)
+        .expand(RECORD_ENVELOPE_INFO, &mut key)
+        .expect("key is the correct length");
+    key
+}
+
+/// Encrypts `payload` to `recipient`, authenticated by `sender`.
+fn seal(
+    payload: &RecordPayload,
+    sender: &x25519::SecretKey,
+    recipient: &[u8; x25519::PUBLIC_KEY_LENGTH],
+) -> Result<Vec<u8>, FatalProcedureError> {
+    let recipient_key = x25519::PublicKey::from_bytes(*recipient);
+    let ephemeral = x25519::SecretKey::generate()?;
+
+    let mut envelope = Vec::with_capacity(HEADER_LENGTH);
+    envelope.extend_from_slice(&RECORD_ENVELOPE_MAGIC);
+    envelope.push(RECORD_ENVELOPE_VERSION);
+    envelope.extend_from_slice(ephemeral.public_key().as_slice());
+    envelope.extend_from_slice(sender.public_key().as_slice());
+    let mut associated_data = envelope.clone();
+    associated_data.extend_from_slice(recipient);
+
+    let key = envelope_key(
+        ephemeral.diffie_hellman(&recipient_key).as_bytes(),
+        sender.diffie_hellman(&recipient_key).as_bytes(),
+        &associated_data,
+    );
+    let plaintext = Zeroizing::new(
+        bincode::serialize(payload).map_err(|e| FatalProcedureError::from(format!("invalid record: {}", e)))?,
+    );
+    let mut nonce = [0u8; XChaCha20Poly1305::NONCE_LENGTH];
+    fill(&mut nonce)?;
+    let mut tag = [0u8; XChaCha20Poly1305::TAG_LENGTH];
+    let mut ciphertext = vec![0u8; plaintext.len()];
+    XChaCha20Poly1305::try_encrypt(&key, &nonce, &associated_data, &plaintext, &mut ciphertext, &mut tag)?;
+
+    envelope.extend_from_slice(&nonce);
+    envelope.extend_from_slice(&tag);
+    envelope.extend_from_slice(&ciphertext);
+    Ok(envelope)
+}
+
+/// Decrypts the `envelope` with the key of the `recipient` and verifies that it has been created by `sender`.
+fn open(
+    envelope: &[u8],
+    recipient: &x25519::SecretKey,
+    sender: &[u8; x25519::PUBLIC_KEY_LENGTH],
+) -> Result<RecordPayload, FatalProcedureError> {
+    if envelope.len() < HEADER_LENGTH + XChaCha20Poly1305::NONCE_LENGTH + XChaCha20Poly1305::TAG_LENGTH
+        || envelope[..RECORD_ENVELOPE_MAGIC.len()] != RECORD_ENVELOPE_MAGIC
+    {
+        return Err("not a record envelope".to_string().into());
+    }
+    let (header, rest) = envelope.split_at(HEADER_LENGTH);
+    let (version, keys) = header[RECORD_ENVELOPE_MAGIC.len()..].split_at(1);
+    if version[0] != RECORD_ENVELOPE_VERSION {
+        return Err(format!("unsupported record envelope version {}", version[0]).into());
+    }
+    let (ephemeral, envelope_sender) = keys.split_at(x25519::PUBLIC_KEY_LENGTH);
+    if envelope_sender != sender {
+        return Err("record envelope has not been created by the expected sender"
+            .to_string()
+            .into());
+    }
+    let (nonce, rest) = rest.split_at(XChaCha20Poly1305::NONCE_LENGTH);
+    let (tag, ciphertext) = rest.split_at(XChaCha20Poly1305::TAG_LENGTH);
+
+    let mut associated_data = header.to_vec();
+    associated_data.extend_from_slice(recipient.public_key().as_slice());
+    let key = envelope_key(
+        recipient
+            .diffie_hellman(&x25519::PublicKey::try_from_slice(ephemeral)?)
+            .as_bytes(),
+        recipient
+            .diffie_hellman(&x25519::PublicKey::from_bytes(*sender))
+            .as_bytes(),
+        &associated_data,
+    );
+    let mut plaintext = Zeroizing::new(vec![0u8; ciphertext.len()]);
+    XChaCha20Poly1305::try_decrypt(&key, nonce, &associated_data, &mut plaintext, ciphertext, tag)?;
+    bincode::deserialize(&plaintext).map_err(|e| format!("invalid record envelope content: {}", e).into())
+}
+
+fn x25519_secret_key(bytes: &[u8]) -> Result<x25519::SecretKey, FatalProcedureError> {
+    x25519::SecretKey::try_from_slice(bytes).map_err(|e| e.into())
+}
+
+/// Exports the record at `record` into an envelope for the X25519 public key `recipient`. The envelope is
+/// authenticated by the X25519 secret key at `sender` and contains the secret, hint and [`RecordMetadata`] of the
+/// record. It can be imported by the recipient with [`ImportRecord`].
+///
+/// The record has to permit [`KeyUsage::EXPORT`], the sender key [`KeyUsage::DERIVE`].
+#[derive(Clone, GuardDebug, Serialize, Deserialize)]
+pub struct ExportRecord {
+    pub record: Location,
+
+    pub sender: Location,
+
+    pub recipient: [u8; x25519::PUBLIC_KEY_LENGTH],
+}
+
+impl Procedure for ExportRecord {
+    type Output = Vec<u8>;
+
+    fn execute<R: Runner>(self, runner: &R) -> Result<Self::Output, ProcedureError> {
+        let (hint, metadata) = runner.record_info(&self.record)?;
+        let recipient = self.recipient;
+        runner.get_guards(
+            [self.record, self.sender],
+            [KeyUsage::EXPORT, KeyUsage::DERIVE],
+            |[secret, sender]| {
+                let payload = RecordPayload {
+                    secret: secret.borrow().to_vec(),
+                    hint,
+                    metadata,
+                };
+                seal(&payload, &x25519_secret_key(&sender.borrow())?, &recipient)
+            },
+        )
+    }
+}
+
+/// Imports a record from an `envelope` that has been created by [`ExportRecord`] for the X25519 secret key at
+/// `recipient` and writes it into `output`. The envelope has to be authenticated by the X25519 public key `sender`.
+///
+/// The hint and [`RecordMetadata`] of the exported record are kept, including its policy.
+#[derive(Clone, GuardDebug, Serialize, Deserialize)]
+pub struct ImportRecord {
+    pub envelope: Vec<u8>,
+
+    pub recipient: Location,
+
+    pub sender: [u8; x25519::PUBLIC_KEY_LENGTH],
+
+    pub output: Location,
+}
+
+impl Procedure for ImportRecord {
+    type Output = ();
+
+    fn execute<R: Runner>(self, runner: &R) -> Result<Self::Output, ProcedureError> {
+        let ImportRecord {
+            envelope,
+            recipient,
+            sender,
+            output,
+        } = self;
+        let mut payload = runner.get_guards([recipient], [KeyUsage::DERIVE], |[recipient]| {
+            open(&envelope, &x25519_secret_key(&recipient.borrow())?, &sender)
+        })?;
+        let secret = std::mem::take(&mut payload.secret);
+        let metadata = payload.metadata.take().unwrap_or_else(|| RecordMetadata::new(None));
+        runner.write_record(&output, secret, payload.hint, metadata)?;
+        Ok(())
+    }
+}
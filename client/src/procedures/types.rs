@@ -1,10 +1,13 @@
 // Copyright 2020-2021 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{FatalEngineError, Location, Provider, RecordError, VaultError};
+use crate::{
+    procedures::KeyType, FatalEngineError, KeyUsage, Location, PermissionError, Provider, RecordError, RecordMetadata,
+    RecordPolicy, VaultError,
+};
 use engine::{
     runtime::memories::buffer::Buffer,
-    vault::{BoxProvider, VaultId},
+    vault::{BoxProvider, RecordHint, VaultId},
 };
 use serde::{Deserialize, Serialize};
 use std::{fmt::Debug, string::FromUtf8Error};
@@ -12,28 +15,51 @@ use thiserror::Error as DeriveError;
 
 /// Bridge to the engine that is required for using / writing / revoking secrets in the vault.
 pub trait Runner {
-    /// Applies `f` to the buffers from the given `locations`.
+    /// Applies `f` to the buffers from the given `locations`. The [`RecordPolicy`] of each record has to permit
+    /// the respective `usage`.
     fn get_guards<F, T, const N: usize>(
         &self,
         locations: [Location; N],
+        usage: [KeyUsage; N],
         f: F,
-    ) -> Result<T, VaultError<FatalProcedureError>>
+    ) -> Result<T, ProcedureError>
     where
         F: FnOnce([Buffer<u8>; N]) -> Result<T, FatalProcedureError>;
 
-    // Execute a function that uses the secret stored at `source_locations`. From the returned `Products` the secret is
-    // written into `target_location` and the output is returned.
+    // Execute a function that uses the secret stored at `source_locations`, with the policies of the source records
+    // checked for the respective `usage`. From the returned `Products` the secret is written into `target_location`
+    // and the output is returned. The `key_type` is stored in the metadata of the target record.
     fn exec_proc<F, T, const N: usize>(
         &self,
         source_locations: [Location; N],
+        usage: [KeyUsage; N],
         target_location: &Location,
+        key_type: Option<KeyType>,
         f: F,
-    ) -> Result<T, VaultError<FatalProcedureError>>
+    ) -> Result<T, ProcedureError>
     where
         F: FnOnce([Buffer<u8>; N]) -> Result<Products<T>, FatalProcedureError>;
 
     fn write_to_vault(&self, location1: &Location, value: Vec<u8>) -> Result<(), RecordError>;
 
+    /// Returns the hint and the [`RecordMetadata`] of the record at `location` without decrypting its secret.
+    fn record_info(&self, location: &Location) -> Result<(RecordHint, Option<RecordMetadata>), RecordError>;
+
+    /// Writes `value` to `location` with the given `hint` and `metadata`, e.g. for a record that has been
+    /// transferred from another Stronghold.
+    fn write_record(
+        &self,
+        location: &Location,
+        value: Vec<u8>,
+        hint: RecordHint,
+        metadata: RecordMetadata,
+    ) -> Result<(), RecordError>;
+
+    /// Marks `counter`, or the [`RecordMetadata::next_nonce_counter`] of the key at `location` if it is `None`, as
+    /// used for the nonces of the key and returns it. Fails if `counter` has already been passed, or if the key is
+    /// exportable and could thus be copied together with its counter.
+    fn next_nonce_counter(&self, location: &Location, counter: Option<u64>) -> Result<u64, ProcedureError>;
+
     fn revoke_data(&self, location: &Location) -> Result<(), RecordError>;
 
     fn garbage_collect(&self, vault_id: VaultId) -> Result<bool, VaultError<FatalProcedureError>>;
@@ -66,11 +92,17 @@ pub trait GenerateSecret: Sized {
 
     fn target(&self) -> &Location;
 
+    /// The type of the generated key, if the secret is a key.
+    fn key_type(&self) -> Option<KeyType> {
+        None
+    }
+
     fn exec<R: Runner>(self, runner: &R) -> Result<Self::Output, ProcedureError> {
         let target = self.target();
         let target = target.clone();
-        let Products { output, secret } = self.generate()?;
-        runner.write_to_vault(&target, secret)?;
+        let key_type = self.key_type();
+        let f = |_| self.generate();
+        let output = runner.exec_proc([], [], &target, key_type, f)?;
         Ok(output)
     }
 }
@@ -83,14 +115,24 @@ pub trait DeriveSecret<const N: usize>: Sized {
 
     fn source(&self) -> [Location; N];
 
+    /// The usage of each source record, checked against the record's [`RecordPolicy`].
+    fn usage(&self) -> [KeyUsage; N];
+
     fn target(&self) -> &Location;
 
+    /// The type of the derived key, if the secret is a key.
+    fn key_type(&self) -> Option<KeyType> {
+        None
+    }
+
     fn exec<R: Runner>(self, runner: &R) -> Result<Self::Output, ProcedureError> {
         let sources: [Location; N] = self.source();
+        let usage = self.usage();
         let target = self.target();
         let target = target.clone();
+        let key_type = self.key_type();
         let f = |guard| self.derive(guard);
-        let output = runner.exec_proc(sources, &target, f)?;
+        let output = runner.exec_proc(sources, usage, &target, key_type, f)?;
         Ok(output)
     }
 }
@@ -103,10 +145,14 @@ pub trait UseSecret<const N: usize>: Sized {
 
     fn source(&self) -> [Location; N];
 
+    /// The usage of each source record, checked against the record's [`RecordPolicy`].
+    fn usage(&self) -> [KeyUsage; N];
+
     fn exec<R: Runner>(self, runner: &R) -> Result<Self::Output, ProcedureError> {
         let source: [Location; N] = self.source();
+        let usage = self.usage();
         let f = |guard| self.use_secret(guard);
-        let output = runner.get_guards(source, f)?;
+        let output = runner.get_guards(source, usage, f)?;
         Ok(output)
     }
 }
@@ -164,6 +210,24 @@ impl<const N: usize> TryFrom<ProcedureOutput> for [u8; N] {
     }
 }
 
+impl From<bool> for ProcedureOutput {
+    fn from(b: bool) -> Self {
+        vec![b.into()].into()
+    }
+}
+
+impl TryFrom<ProcedureOutput> for bool {
+    type Error = FatalProcedureError;
+
+    fn try_from(value: ProcedureOutput) -> Result<Self, Self::Error> {
+        match value.0.as_slice() {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            _ => Err("invalid boolean output".to_string().into()),
+        }
+    }
+}
+
 /// Error on procedure execution.
 #[derive(DeriveError, Debug, Clone, Serialize, Deserialize)]
 pub enum ProcedureError {
@@ -174,6 +238,14 @@ pub enum ProcedureError {
     /// Operation on the vault failed.
     #[error("procedure: {0}")]
     Procedure(#[from] FatalProcedureError),
+
+    /// The policy of an input record does not permit the operation.
+    #[error("policy: {0}")]
+    Policy(#[from] PolicyError),
+
+    /// The permissions of a restricted client do not permit the procedure.
+    #[error("permission: {0}")]
+    Permission(#[from] PermissionError),
 }
 
 impl<T> From<VaultError<T>> for ProcedureError
@@ -194,6 +266,22 @@ impl From<RecordError> for ProcedureError {
     }
 }
 
+/// Violation of a [`RecordPolicy`].
+#[derive(DeriveError, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PolicyError {
+    #[error("usage {0:?} is not allowed for the record")]
+    UsageNotAllowed(KeyUsage),
+
+    #[error("record has expired")]
+    Expired,
+
+    #[error("record has already been used the maximum number of {0} times")]
+    UsesExhausted(u64),
+
+    #[error("record must not be exportable")]
+    Exportable,
+}
+
 /// Execution of the procedure failed.
 #[derive(DeriveError, Debug, Clone, Serialize, Deserialize)]
 #[error("fatal procedure error {0}")]
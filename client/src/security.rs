@@ -3,7 +3,9 @@
 
 mod keyprovider;
 mod keystore;
+mod permissions;
 
 // re-export modules
 pub use keyprovider::KeyProvider;
 pub use keystore::KeyStore;
+pub use permissions::*;
@@ -8,13 +8,14 @@ use engine::{
         memories::buffer::{Buffer, Ref},
         Bytes, MemoryError,
     },
+    snapshot::{read_header_from, SnapshotKdf},
     vault::NCKey,
 };
 use std::ops::Deref;
 use stronghold_utils::GuardDebug;
 use zeroize::Zeroize;
 
-use crate::{internal::Provider, ClientError};
+use crate::{internal::Provider, ClientError, SnapshotError, SnapshotPath};
 
 /// This constant will be used to truncate a supplied passphrase
 const KEY_SIZE_HASHED: usize = 32;
@@ -25,6 +26,9 @@ const KEY_SIZE_HASHED: usize = 32;
 #[derive(GuardDebug)]
 pub struct KeyProvider {
     inner: engine::vault::NCKey<Provider>,
+
+    /// How the key was derived, this is written into the header of snapshots encrypted with the key.
+    kdf: SnapshotKdf,
 }
 
 impl TryFrom<Vec<u8>> for KeyProvider {
@@ -32,7 +36,10 @@ impl TryFrom<Vec<u8>> for KeyProvider {
 
     fn try_from(data: Vec<u8>) -> Result<Self, MemoryError> {
         match NCKey::load(data) {
-            Some(inner) => Ok(Self { inner }),
+            Some(inner) => Ok(Self {
+                inner,
+                kdf: SnapshotKdf::None,
+            }),
             None => Err(MemoryError::NCSizeNotAllowed),
         }
     }
@@ -91,7 +98,9 @@ impl KeyProvider {
                 key[i] = *value;
             });
 
-        let result = Self::try_from(key.to_vec()).map_err(|e| ClientError::Inner(e.to_string()));
+        let result = Self::try_from(key.to_vec())
+            .map(|provider| provider.with_kdf(SnapshotKdf::Truncated))
+            .map_err(|e| ClientError::Inner(e.to_string()));
         key.zeroize();
         passphrase.zeroize();
 
@@ -213,6 +222,7 @@ impl KeyProvider {
         P: AsRef<[u8]> + Zeroize,
     {
         Self::with_passphrase_hashed(passphrase, crypto::hashes::blake2b::Blake2b256::new())
+            .map(|provider| provider.with_kdf(SnapshotKdf::Blake2b))
     }
 
     /// Creates a new [``KeyProvider] from a passphrase, that will be hashed with `argon2`.
@@ -258,20 +268,106 @@ impl KeyProvider {
     ///
     /// assert_eq!(key, expected);
     /// ```
-    pub fn with_passphrase_hashed_argon2<P>(mut passphrase: P, mut salt: P) -> Result<Self, ClientError>
+    pub fn with_passphrase_hashed_argon2<P>(passphrase: P, mut salt: P) -> Result<Self, ClientError>
     where
         P: AsRef<[u8]> + Zeroize,
     {
         let config = argon2::Config::default();
+        let kdf = SnapshotKdf::Argon2 {
+            variant: config.variant.as_u32(),
+            version: config.version.as_u32(),
+            memory: config.mem_cost,
+            iterations: config.time_cost,
+            lanes: config.lanes,
+            salt: salt.as_ref().to_vec(),
+        };
+        salt.zeroize();
 
-        let key = argon2::hash_raw(passphrase.as_ref(), salt.as_ref(), &config)
-            .map_err(|e| ClientError::Inner(e.to_string()))?;
+        Self::with_passphrase_and_kdf(passphrase, &kdf)
+    }
 
-        let result = Self::try_from(key.to_vec()).map_err(|e| ClientError::Inner(e.to_string()));
-        passphrase.zeroize();
-        salt.zeroize();
+    /// Creates a new [`KeyProvider`] from a passphrase, that will be turned into a key as described by `kdf`.
+    /// Returns an error for [`SnapshotKdf::None`], as the key of such snapshots can't be derived from a passphrase,
+    /// and for Argon2 parameters beyond the bounds of [`SnapshotKdf::is_within_limits`].
+    ///
+    /// # Example
+    pub fn with_passphrase_and_kdf<P>(mut passphrase: P, kdf: &SnapshotKdf) -> Result<Self, ClientError>
+    where
+        P: AsRef<[u8]> + Zeroize,
+    {
+        if !kdf.is_within_limits() {
+            passphrase.zeroize();
+            return Err(ClientError::Inner(
+                "snapshot key derivation parameters out of range".to_string(),
+            ));
+        }
+        match kdf {
+            SnapshotKdf::None => {
+                passphrase.zeroize();
+                Err(ClientError::Inner(
+                    "snapshot key was not derived from a passphrase".to_string(),
+                ))
+            }
+            SnapshotKdf::Truncated => Self::with_passphrase_truncated(passphrase),
+            SnapshotKdf::Blake2b => Self::with_passphrase_hashed_blake2b(passphrase),
+            SnapshotKdf::Argon2 {
+                variant,
+                version,
+                memory,
+                iterations,
+                lanes,
+                salt,
+            } => {
+                let config = argon2::Config {
+                    variant: argon2::Variant::from_u32(*variant).map_err(|e| ClientError::Inner(e.to_string()))?,
+                    version: argon2::Version::from_u32(*version).map_err(|e| ClientError::Inner(e.to_string()))?,
+                    mem_cost: *memory,
+                    time_cost: *iterations,
+                    lanes: *lanes,
+                    hash_length: KEY_SIZE_HASHED as u32,
+                    ..Default::default()
+                };
 
-        result
+                let key =
+                    argon2::hash_raw(passphrase.as_ref(), salt, &config).map_err(|e| ClientError::Inner(e.to_string()));
+                passphrase.zeroize();
+
+                Self::try_from(key?)
+                    .map(|provider| provider.with_kdf(kdf.clone()))
+                    .map_err(|e| ClientError::Inner(e.to_string()))
+            }
+        }
+    }
+
+    /// Creates a new [`KeyProvider`] for the snapshot at `snapshot_path` from a passphrase. The key derivation
+    /// function and its parameters are read from the header of the snapshot file, so only the passphrase
+    /// has to be known. Snapshots of version 2 don't record the key derivation and return an error.
+    ///
+    /// # Example
+    pub fn with_passphrase_for_snapshot<P>(mut passphrase: P, snapshot_path: &SnapshotPath) -> Result<Self, ClientError>
+    where
+        P: AsRef<[u8]> + Zeroize,
+    {
+        let header = read_header_from(snapshot_path.as_path()).map_err(SnapshotError::from);
+        let kdf = match header {
+            Ok(Some(header)) => header.kdf,
+            Ok(None) => {
+                passphrase.zeroize();
+                return Err(ClientError::Inner(
+                    "snapshot file does not record its key derivation".to_string(),
+                ));
+            }
+            Err(e) => {
+                passphrase.zeroize();
+                return Err(e.into());
+            }
+        };
+        Self::with_passphrase_and_kdf(passphrase, &kdf)
+    }
+
+    fn with_kdf(mut self, kdf: SnapshotKdf) -> Self {
+        self.kdf = kdf;
+        self
     }
 }
 
@@ -312,6 +408,11 @@ impl KeyProvider {
             Err(memerror) => Err(memerror),
         }
     }
+
+    /// Returns the [`SnapshotKdf`] that was used to derive the key.
+    pub fn kdf(&self) -> &SnapshotKdf {
+        &self.kdf
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,200 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error as DeriveError;
+
+use crate::procedures::{StrongholdProcedure, StrongholdProcedurePermission};
+
+/// Permission of a single request variant, with exactly one bit set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PermissionValue(u64);
+
+impl PermissionValue {
+    /// Creates the permission for the variant at `index`. Fails if `index` > 63.
+    pub fn new(index: u8) -> Result<Self, PermissionError> {
+        1u64.checked_shl(index as u32)
+            .map(PermissionValue)
+            .ok_or(PermissionError::InvalidIndex(index))
+    }
+
+    /// Returns the bit of the permission.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Assigns a [`PermissionValue`] to each variant of a request, see `stronghold_derive::RequestPermissions`.
+pub trait VariantPermission {
+    fn permission(&self) -> PermissionValue;
+}
+
+/// Maps a request to its unit permission variant, see `stronghold_derive::RequestPermissions`.
+pub trait FwRequest<Rq> {
+    fn from_request(request: &Rq) -> Self;
+}
+
+/// Violation of the [`ClientPermissions`] of a restricted client.
+#[derive(DeriveError, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PermissionError {
+    #[error("permission index {0} exceeds 63")]
+    InvalidIndex(u8),
+
+    #[error("procedure {0} is not permitted")]
+    ProcedureNotAllowed(String),
+
+    #[error("access to vault path {0:?} is not permitted")]
+    VaultNotAllowed(Vec<u8>),
+}
+
+/// Allow-list of procedures and vault path prefixes of a [`crate::RestrictedClient`].
+///
+/// # Example
+/// ```
+/// use iota_stronghold::{procedures::StrongholdProcedurePermission, ClientPermissions};
+///
+/// let signing_only = ClientPermissions::none()
+///     .allow_procedure(StrongholdProcedurePermission::Ed25519Sign)
+///     .allow_procedure(StrongholdProcedurePermission::PublicKey)
+///     .allow_vault_prefix(b"plugin/");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "ClientPermissionsRepr", from = "ClientPermissionsRepr")]
+pub struct ClientPermissions {
+    // Bits of the permitted procedures, see `VariantPermission`. The bits depend on the order of the procedure
+    // variants and are never serialized.
+    procedures: u64,
+    vault_prefixes: Option<Vec<Vec<u8>>>,
+}
+
+/// Serialized form of [`ClientPermissions`]. The permitted procedures are stored by their name, so that serialized
+/// permissions don't change their meaning when procedure variants are added or reordered.
+#[derive(Serialize, Deserialize)]
+struct ClientPermissionsRepr {
+    // Names of the permitted procedures, `None` if all procedures are permitted
+    procedures: Option<Vec<String>>,
+    vault_prefixes: Option<Vec<Vec<u8>>>,
+}
+
+impl From<ClientPermissions> for ClientPermissionsRepr {
+    fn from(permissions: ClientPermissions) -> Self {
+        let procedures = (permissions.procedures != u64::MAX).then(|| {
+            StrongholdProcedurePermission::variants()
+                .into_iter()
+                .filter(|procedure| permissions.is_procedure_allowed(*procedure))
+                .map(|procedure| procedure.name().to_string())
+                .collect()
+        });
+        ClientPermissionsRepr {
+            procedures,
+            vault_prefixes: permissions.vault_prefixes,
+        }
+    }
+}
+
+impl From<ClientPermissionsRepr> for ClientPermissions {
+    fn from(repr: ClientPermissionsRepr) -> Self {
+        // unknown procedures, e.g. of a disabled feature, are not permitted.
+        let procedures = match repr.procedures {
+            None => u64::MAX,
+            Some(names) => names
+                .iter()
+                .filter_map(|name| StrongholdProcedurePermission::from_name(name))
+                .fold(0, |procedures, procedure| procedures | procedure.permission().value()),
+        };
+        ClientPermissions {
+            procedures,
+            vault_prefixes: repr.vault_prefixes,
+        }
+    }
+}
+
+impl Default for ClientPermissions {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl ClientPermissions {
+    /// Permits all procedures on all vaults.
+    pub fn all() -> Self {
+        ClientPermissions {
+            procedures: u64::MAX,
+            vault_prefixes: None,
+        }
+    }
+
+    /// Permits nothing. Procedures and vault path prefixes have to be allowed explicitly.
+    pub fn none() -> Self {
+        ClientPermissions {
+            procedures: 0,
+            vault_prefixes: Some(Vec::new()),
+        }
+    }
+
+    /// Permits the procedure variant `procedure`.
+    pub fn allow_procedure(mut self, procedure: StrongholdProcedurePermission) -> Self {
+        self.procedures |= procedure.permission().value();
+        self
+    }
+
+    /// Permits access to all vaults whose path starts with `prefix`.
+    pub fn allow_vault_prefix<P: AsRef<[u8]>>(mut self, prefix: P) -> Self {
+        self.vault_prefixes
+            .get_or_insert_with(Vec::new)
+            .push(prefix.as_ref().to_vec());
+        self
+    }
+
+    /// Returns `true`, if the procedure variant `procedure` is permitted.
+    pub fn is_procedure_allowed(&self, procedure: StrongholdProcedurePermission) -> bool {
+        self.procedures & procedure.permission().value() != 0
+    }
+
+    /// Returns `true`, if access to the vault at `vault_path` is permitted.
+    pub fn is_vault_allowed(&self, vault_path: &[u8]) -> bool {
+        match &self.vault_prefixes {
+            None => true,
+            Some(prefixes) => prefixes.iter().any(|prefix| vault_path.starts_with(prefix)),
+        }
+    }
+
+    /// Returns the permissions that are granted by both `self` and `other`. A vault path is permitted by
+    /// the intersection, if it is permitted by both.
+    pub fn intersect(&self, other: &ClientPermissions) -> Self {
+        let vault_prefixes = match (&self.vault_prefixes, &other.vault_prefixes) {
+            (None, prefixes) | (prefixes, None) => prefixes.clone(),
+            (Some(a), Some(b)) => {
+                let mut prefixes: Vec<Vec<u8>> = a
+                    .iter()
+                    .filter(|prefix| other.is_vault_allowed(prefix))
+                    .chain(b.iter().filter(|prefix| self.is_vault_allowed(prefix)))
+                    .cloned()
+                    .collect();
+                prefixes.sort();
+                prefixes.dedup();
+                Some(prefixes)
+            }
+        };
+        ClientPermissions {
+            procedures: self.procedures & other.procedures,
+            vault_prefixes,
+        }
+    }
+
+    /// Checks that `procedure` and all vaults it accesses are permitted.
+    pub fn check(&self, procedure: &StrongholdProcedure) -> Result<(), PermissionError> {
+        let variant = StrongholdProcedurePermission::from_request(procedure);
+        if !self.is_procedure_allowed(variant) {
+            return Err(PermissionError::ProcedureNotAllowed(format!("{:?}", variant)));
+        }
+        match procedure
+            .vault_paths()
+            .into_iter()
+            .find(|vault_path| !self.is_vault_allowed(vault_path))
+        {
+            Some(vault_path) => Err(PermissionError::VaultNotAllowed(vault_path.to_vec())),
+            None => Ok(()),
+        }
+    }
+}
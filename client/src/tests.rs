@@ -1,7 +1,13 @@
 // Copyright 2020-2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(feature = "async")]
+mod async_tests;
+#[cfg(all(feature = "daemon", unix))]
+mod daemon_tests;
 mod fresh;
 mod interface_tests;
+mod permission_tests;
 mod procedure_tests;
 mod store_tests;
+mod stream_tests;
@@ -0,0 +1,143 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    procedures::{GenerateKey, KeyType, PublicKey, StrongholdProcedure},
+    tests::fresh,
+    AsyncClient, AsyncStronghold, KeyProvider, Location, SnapshotPath, SnapshotSource,
+};
+
+fn snapshot_path() -> std::path::PathBuf {
+    let filename = base64::encode(fresh::fixed_bytestring(32)).replace('/', "n");
+    std::env::temp_dir().join(filename)
+}
+
+#[tokio::test]
+async fn test_async_commit_and_load_snapshot() {
+    let client_path = b"async-client".to_vec();
+    let location = Location::const_generic(b"vault".to_vec(), b"record".to_vec());
+    let path = snapshot_path();
+    let snapshot_path = SnapshotPath::from_path(&path);
+
+    let keyprovider = Arc::new(
+        KeyProvider::with_passphrase_hashed_argon2_async(b"passphrase".to_vec(), b"salt-salt".to_vec())
+            .await
+            .unwrap(),
+    );
+
+    let stronghold = AsyncStronghold::default();
+    let client = stronghold.create_client(client_path.clone()).await.unwrap();
+    client.write_secret(location.clone(), b"secret".to_vec()).await.unwrap();
+    stronghold
+        .commit_with_keyprovider(&snapshot_path, keyprovider.clone())
+        .await
+        .unwrap();
+
+    let stronghold = AsyncStronghold::default();
+    let source = stronghold
+        .load_snapshot(keyprovider.clone(), &snapshot_path)
+        .await
+        .unwrap();
+    assert_eq!(source, SnapshotSource::Latest);
+
+    let client = stronghold.load_client(client_path.clone()).await.unwrap();
+    assert!(client.record_exists(&location).await.unwrap());
+
+    let keyprovider = KeyProvider::with_passphrase_for_snapshot_async(b"passphrase".to_vec(), &snapshot_path)
+        .await
+        .unwrap();
+    let stronghold = AsyncStronghold::default();
+    let client = stronghold
+        .load_client_from_snapshot(client_path, Arc::new(keyprovider), &snapshot_path)
+        .await
+        .unwrap();
+    assert!(client.vault_exists(b"vault").await.unwrap());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_async_concurrent_procedures() {
+    let stronghold = AsyncStronghold::default();
+    let client = stronghold.create_client(b"async-client").await.unwrap();
+
+    let tasks = (0..8)
+        .map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move {
+                let private_key = fresh::location();
+                let public_key = client
+                    .execute_procedure_chained(vec![
+                        StrongholdProcedure::GenerateKey(GenerateKey {
+                            ty: KeyType::Ed25519,
+                            output: private_key.clone(),
+                        }),
+                        StrongholdProcedure::PublicKey(PublicKey {
+                            ty: KeyType::Ed25519,
+                            private_key: private_key.clone(),
+                        }),
+                    ])
+                    .await
+                    .unwrap()
+                    .pop()
+                    .unwrap();
+                let public_key: Vec<u8> = public_key.into();
+
+                let expected = client
+                    .execute_procedure(PublicKey {
+                        ty: KeyType::Ed25519,
+                        private_key,
+                    })
+                    .await
+                    .unwrap();
+                assert_eq!(public_key, expected);
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for task in tasks {
+        task.await.unwrap();
+    }
+    assert_eq!(client.list_vaults().await.unwrap().len(), 8);
+}
+
+#[tokio::test]
+async fn test_async_client_lock() {
+    let stronghold = AsyncStronghold::default();
+    let client = stronghold.create_client(b"async-client").await.unwrap();
+    let private_key = fresh::location();
+    client
+        .execute_procedure(GenerateKey {
+            ty: KeyType::Ed25519,
+            output: private_key.clone(),
+        })
+        .await
+        .unwrap();
+
+    // the lock is shared with facades created from the same client.
+    let other = AsyncClient::from(stronghold.get_client(b"async-client").await.unwrap().inner().clone());
+    let guard = client.inner().async_lock.clone().read_owned().await;
+
+    // procedures that don't write a record are not blocked.
+    other
+        .execute_procedure(PublicKey {
+            ty: KeyType::Ed25519,
+            private_key,
+        })
+        .await
+        .unwrap();
+
+    // procedures that write a record wait for the lock.
+    let generate = other.execute_procedure(GenerateKey {
+        ty: KeyType::Ed25519,
+        output: fresh::location(),
+    });
+    tokio::pin!(generate);
+    assert!(tokio::time::timeout(Duration::from_millis(100), &mut generate)
+        .await
+        .is_err());
+    drop(guard);
+    generate.await.unwrap();
+}
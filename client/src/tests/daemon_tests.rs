@@ -0,0 +1,120 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{path::PathBuf, thread, time::Duration};
+
+use crate::{
+    daemon::{load_or_create_token, Daemon, DaemonClient},
+    procedures::{GenerateKey, KeyType, PublicKey},
+    tests::fresh,
+    DaemonError, KeyProvider, SnapshotPath, Stronghold,
+};
+
+fn temp_path(suffix: &str) -> PathBuf {
+    let filename = base64::encode(fresh::fixed_bytestring(16)).replace('/', "n");
+    std::env::temp_dir().join(format!("{}.{}", filename, suffix))
+}
+
+fn spawn_daemon(socket: &PathBuf, snapshot: &PathBuf, token: Vec<u8>) -> Daemon {
+    let keyprovider = KeyProvider::try_from(fresh::fixed_bytestring(32)).unwrap();
+    let daemon = Daemon::new(
+        Stronghold::default(),
+        keyprovider,
+        SnapshotPath::from_path(snapshot),
+        token,
+    );
+    let listener = daemon.bind(socket).unwrap();
+    let server = daemon.clone();
+    thread::spawn(move || server.serve_listener(listener));
+    daemon
+}
+
+#[test]
+fn test_daemon_sessions() {
+    let socket = temp_path("sock");
+    let snapshot = temp_path("stronghold");
+    let token_path = temp_path("token");
+    let token = load_or_create_token(&token_path).unwrap();
+    assert_eq!(load_or_create_token(&token_path).unwrap(), token);
+    spawn_daemon(&socket, &snapshot, token.clone());
+
+    let private_key = fresh::location();
+    let mut session = DaemonClient::connect(&socket, &token, b"client").unwrap();
+    session
+        .execute_procedure(GenerateKey {
+            ty: KeyType::Ed25519,
+            output: private_key.clone(),
+        })
+        .unwrap();
+    let public_key = session
+        .execute_procedure(PublicKey {
+            ty: KeyType::Ed25519,
+            private_key: private_key.clone(),
+        })
+        .unwrap();
+    assert!(session.record_exists(&private_key).unwrap());
+
+    // a second session of the same client shares its secrets
+    let mut other = DaemonClient::connect(&socket, &token, b"client").unwrap();
+    let other_public_key = other
+        .execute_procedure(PublicKey {
+            ty: KeyType::Ed25519,
+            private_key: private_key.clone(),
+        })
+        .unwrap();
+    assert_eq!(public_key, other_public_key);
+
+    // a session of another client doesn't
+    let mut other = DaemonClient::connect(&socket, &token, b"other client").unwrap();
+    assert!(!other.record_exists(&private_key).unwrap());
+
+    session.commit().unwrap();
+    assert!(snapshot.exists());
+
+    let _ = std::fs::remove_file(&socket);
+    let _ = std::fs::remove_file(&snapshot);
+    let _ = std::fs::remove_file(&token_path);
+}
+
+#[test]
+fn test_daemon_rejects_invalid_token() {
+    let socket = temp_path("sock");
+    let snapshot = temp_path("stronghold");
+    spawn_daemon(&socket, &snapshot, fresh::fixed_bytestring(32));
+
+    let result = DaemonClient::connect(&socket, &fresh::fixed_bytestring(32), b"client");
+    assert!(matches!(result, Err(DaemonError::AuthenticationFailed)));
+
+    let _ = std::fs::remove_file(&socket);
+}
+
+#[test]
+fn test_daemon_max_connections() {
+    let socket = temp_path("sock");
+    let snapshot = temp_path("stronghold");
+    let token = fresh::fixed_bytestring(32);
+    let daemon = spawn_daemon(&socket, &snapshot, token.clone());
+    daemon.set_max_connections(1);
+
+    let mut session = DaemonClient::connect(&socket, &token, b"client").unwrap();
+    let result = DaemonClient::connect(&socket, &token, b"client");
+    assert!(matches!(result, Err(DaemonError::TooManyConnections)));
+    assert!(!session.vault_exists(b"vault").unwrap());
+
+    // the slot is released once the session is closed.
+    drop(session);
+    let mut reconnected = None;
+    for _ in 0..100 {
+        match DaemonClient::connect(&socket, &token, b"client") {
+            Ok(session) => {
+                reconnected = Some(session);
+                break;
+            }
+            Err(DaemonError::TooManyConnections) => thread::sleep(Duration::from_millis(10)),
+            Err(e) => panic!("unexpected error {:?}", e),
+        }
+    }
+    assert!(reconnected.is_some());
+
+    let _ = std::fs::remove_file(&socket);
+}
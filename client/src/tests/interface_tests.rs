@@ -9,10 +9,18 @@ use std::{
 };
 
 use crate::{
-    procedures::{GenerateKey, KeyType, StrongholdProcedure},
-    Client, ClientError, ClientVault, KeyProvider, Location, Snapshot, SnapshotPath, Store, Stronghold,
+    procedures::{
+        AeadCipher, AeadEncrypt, AeadNonce, GenerateKey, KeyType, Slip10Derive, Slip10DeriveInput, Slip10Generate,
+        StrongholdProcedure,
+    },
+    tests::fresh,
+    Client, ClientError, ClientVault, KeyProvider, KeyUsage, Location, RecordPolicy, Snapshot, SnapshotPath,
+    SnapshotSource, Store, Stronghold,
+};
+use engine::{
+    snapshot::{SnapshotKdf, MAGIC, VERSION},
+    vault::RecordHint,
 };
-use engine::vault::RecordHint;
 use regex::Replacer;
 use stronghold_utils::random as rand;
 use zeroize::Zeroize;
@@ -711,3 +719,499 @@ fn test_load_unload_client() {
     assert!(stronghold.unload_client(client).is_ok());
     assert!(stronghold.load_client(client_path).is_ok());
 }
+
+#[test]
+fn test_record_metadata() {
+    let client_path = "my-metadata-client-path";
+    let stronghold = Stronghold::default();
+    let client = stronghold.create_client(client_path).unwrap();
+
+    let key = fresh::location();
+    client
+        .execute_procedure(GenerateKey {
+            ty: KeyType::Ed25519,
+            output: key.clone(),
+        })
+        .unwrap();
+
+    let metadata = client.record_metadata(&key).unwrap().unwrap();
+    assert_eq!(metadata.key_type, Some(KeyType::Ed25519));
+    assert_eq!(metadata.created, metadata.updated);
+    assert_eq!(metadata.label, None);
+    assert_eq!(metadata.policy, RecordPolicy::default());
+    assert_eq!(metadata.uses, 0);
+
+    client.set_record_label(&key, Some("signing key".into())).unwrap();
+    client
+        .set_record_policy(&key, RecordPolicy::new(KeyUsage::SIGN))
+        .unwrap();
+
+    // overwriting the record keeps creation time, label and policy.
+    client
+        .vault(key.vault_path())
+        .write_secret(key.clone(), fixed_random_bytes(32))
+        .unwrap();
+    let updated = client.record_metadata(&key).unwrap().unwrap();
+    assert_eq!(updated.created, metadata.created);
+    assert!(updated.updated >= metadata.updated);
+    assert_eq!(updated.key_type, None);
+    assert_eq!(updated.label.as_deref(), Some("signing key"));
+    assert_eq!(updated.policy, RecordPolicy::new(KeyUsage::SIGN));
+
+    // derived keys are tagged with their key type.
+    let seed = fresh::location();
+    let derived = fresh::location();
+    client
+        .execute_procedure(Slip10Generate {
+            output: seed.clone(),
+            size_bytes: None,
+        })
+        .unwrap();
+    client
+        .execute_procedure(Slip10Derive {
+            chain: fresh::hd_path().1,
+            input: Slip10DeriveInput::Seed(seed.clone()),
+            output: derived.clone(),
+        })
+        .unwrap();
+    assert_eq!(client.record_metadata(&seed).unwrap().unwrap().key_type, None);
+    assert_eq!(
+        client.record_metadata(&derived).unwrap().unwrap().key_type,
+        Some(KeyType::Ed25519)
+    );
+
+    // metadata of non-existing records can't be read or written.
+    assert!(client.record_metadata(&fresh::location()).is_err());
+    let missing = Location::const_generic(key.vault_path().to_vec(), b"missing".to_vec());
+    assert!(client.record_metadata(&missing).is_err());
+    assert!(client.set_record_label(&missing, None).is_err());
+
+    // metadata is persisted in the snapshot.
+    let mut snapshot_path = std::env::temp_dir();
+    snapshot_path.push(base64::encode(fixed_random_bytes(32)).replace('/', "n"));
+    let defer = Defer::from((snapshot_path, |path: &'_ PathBuf| {
+        let _ = std::fs::remove_file(path);
+    }));
+    let snapshot = SnapshotPath::from_path(&*defer);
+    let key_provider = KeyProvider::try_from(fixed_random_bytes(32)).unwrap();
+    stronghold.commit_with_keyprovider(&snapshot, &key_provider).unwrap();
+    stronghold.unload_client(client).unwrap();
+
+    let client = stronghold
+        .load_client_from_snapshot(client_path, &key_provider, &snapshot)
+        .unwrap();
+    assert_eq!(client.record_metadata(&key).unwrap(), Some(updated));
+}
+
+#[test]
+fn test_nonce_counter_snapshot() {
+    let client_path = "my-nonce-counter-client-path";
+    let stronghold = Stronghold::default();
+    let client = stronghold.create_client(client_path).unwrap();
+
+    let key = fresh::location();
+    client
+        .vault(key.vault_path())
+        .write_secret(key.clone(), fixed_random_bytes(32))
+        .unwrap();
+    client
+        .set_record_policy(&key, RecordPolicy::new(KeyUsage::ENCRYPT))
+        .unwrap();
+    let encrypt = |client: &Client, nonce: AeadNonce| {
+        client.execute_procedure(AeadEncrypt {
+            cipher: AeadCipher::Aes256Gcm,
+            associated_data: vec![],
+            plaintext: vec![],
+            nonce,
+            key: key.clone(),
+        })
+    };
+    encrypt(&client, AeadNonce::StoredCounter).unwrap();
+    encrypt(&client, AeadNonce::Counter(3)).unwrap();
+
+    // the counter continues after loading the snapshot
+    let mut snapshot_path = std::env::temp_dir();
+    snapshot_path.push(base64::encode(fixed_random_bytes(32)).replace('/', "n"));
+    let defer = Defer::from((snapshot_path, |path: &'_ PathBuf| {
+        let _ = std::fs::remove_file(path);
+    }));
+    let snapshot = SnapshotPath::from_path(&*defer);
+    let key_provider = KeyProvider::try_from(fixed_random_bytes(32)).unwrap();
+    stronghold.commit_with_keyprovider(&snapshot, &key_provider).unwrap();
+    stronghold.unload_client(client).unwrap();
+
+    let stronghold = Stronghold::default();
+    let client = stronghold
+        .load_client_from_snapshot(client_path, &key_provider, &snapshot)
+        .unwrap();
+    assert!(encrypt(&client, AeadNonce::Counter(3)).is_err());
+    let output = encrypt(&client, AeadNonce::StoredCounter).unwrap();
+    assert_eq!(output[4..12], 4u64.to_be_bytes());
+}
+
+#[test]
+fn test_list_vaults_and_records() {
+    let client = Client::default();
+    let vault_path = b"listed-vault".to_vec();
+    let vault = client.vault(&vault_path);
+
+    assert!(client.list_vaults().unwrap().is_empty());
+    assert!(vault.list_records().is_err());
+
+    // records written while the index is disabled can't be traced back to their path.
+    let unindexed = Location::generic(vault_path.clone(), b"unindexed".to_vec());
+    vault.write_secret(unindexed.clone(), fixed_random_bytes(32)).unwrap();
+
+    client.set_record_path_index(true);
+    let record_paths: Vec<Vec<u8>> = (0..3).map(|i| format!("record-{}", i).into_bytes()).collect();
+    for record_path in &record_paths {
+        let location = Location::generic(vault_path.clone(), record_path.clone());
+        vault.write_secret(location, fixed_random_bytes(32)).unwrap();
+    }
+    let counter = Location::counter(vault_path.clone(), 0usize);
+    vault.write_secret(counter.clone(), fixed_random_bytes(32)).unwrap();
+
+    let other = fresh::location();
+    client
+        .vault(other.vault_path())
+        .write_secret(other.clone(), fixed_random_bytes(32))
+        .unwrap();
+
+    let mut vaults = client.list_vaults().unwrap();
+    vaults.sort();
+    let mut expected = vec![vault.id(), other.resolve().0];
+    expected.sort();
+    assert_eq!(vaults, expected);
+
+    let records = vault.list_records().unwrap();
+    assert_eq!(records.len(), 5);
+    assert_eq!(vault.records().unwrap().collect::<Vec<_>>(), records);
+
+    let mut recovered: Vec<Vec<u8>> = records
+        .iter()
+        .filter_map(|(record_id, _)| vault.record_path(*record_id).unwrap())
+        .collect();
+    recovered.sort();
+    assert_eq!(recovered, record_paths);
+    assert_eq!(vault.record_path(unindexed.resolve().1).unwrap(), None);
+    assert_eq!(vault.record_path(counter.resolve().1).unwrap(), None);
+
+    // revoked records are not listed.
+    vault.revoke_secret(b"record-0").unwrap();
+    let records = vault.list_records().unwrap();
+    assert_eq!(records.len(), 4);
+    assert!(records
+        .iter()
+        .all(|(record_id, _)| *record_id != Location::generic(vault_path.clone(), b"record-0".to_vec()).resolve().1));
+}
+
+#[test]
+fn test_rotate_vault_key() {
+    let client = Client::default();
+    let vault_path = b"rotated-vault".to_vec();
+    let vault = client.vault(&vault_path);
+    let vault_id = vault.id();
+
+    let payload = fixed_random_bytes(64);
+    let location = Location::generic(vault_path.clone(), b"record".to_vec());
+    vault.write_secret(location.clone(), payload.clone()).unwrap();
+    let revoked = Location::generic(vault_path.clone(), b"revoked".to_vec());
+    vault.write_secret(revoked.clone(), fixed_random_bytes(64)).unwrap();
+    vault.revoke_secret(b"revoked").unwrap();
+
+    let (_, record_id) = location.resolve();
+    let old_key = client.keystore.read().unwrap().get_key(vault_id).unwrap();
+    let blob_id = client
+        .db
+        .read()
+        .unwrap()
+        .get_blob_id(&old_key, vault_id, record_id)
+        .unwrap();
+
+    client.rotate_vault_key(&vault_path).unwrap();
+
+    let new_key = client.keystore.read().unwrap().get_key(vault_id).unwrap();
+    assert!(old_key != new_key);
+    {
+        let db = client.db.read().unwrap();
+        assert!(db.get_blob_id(&old_key, vault_id, record_id).is_err());
+        assert_eq!(db.get_blob_id(&new_key, vault_id, record_id).unwrap(), blob_id);
+    }
+    assert_eq!(vault.read_secret(b"record").unwrap(), payload);
+    assert!(!client.record_exists(&revoked).unwrap());
+
+    // revoked records can still be garbage collected with the new key.
+    let mut db = client.db.write().unwrap();
+    db.garbage_collect_vault(&new_key, vault_id);
+    assert_eq!(db.list_records(&vault_id), vec![record_id]);
+    drop(db);
+
+    assert!(client.rotate_vault_key(b"missing-vault").is_err());
+}
+
+#[test]
+fn test_rotate_snapshot_key() {
+    let client_path = b"client_path".to_vec();
+    let vault_path = b"vault_path".to_vec();
+    let payload = fixed_random_bytes(64);
+    let location = Location::const_generic(vault_path.clone(), b"record_path".to_vec());
+
+    let mut snapshot_path = std::env::temp_dir();
+    snapshot_path.push(base64::encode(fixed_random_bytes(8)).replace('/', "n"));
+    let defer = Defer::from((snapshot_path, |path: &'_ PathBuf| {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".1");
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(backup);
+    }));
+    let snapshot_path = SnapshotPath::from_path(&*defer);
+
+    let old_keyprovider = KeyProvider::try_from(fixed_random_bytes(32)).unwrap();
+    let new_keyprovider = KeyProvider::try_from(fixed_random_bytes(32)).unwrap();
+
+    let stronghold = Stronghold::default();
+    stronghold.set_snapshot_backups(1);
+    let client = stronghold.create_client(&client_path).unwrap();
+    client
+        .vault(&vault_path)
+        .write_secret(location, payload.clone())
+        .unwrap();
+    // the second commit keeps the first one as backup.
+    for _ in 0..2 {
+        stronghold
+            .commit_with_keyprovider(&snapshot_path, &old_keyprovider)
+            .unwrap();
+    }
+
+    // the snapshot can't be rotated with a wrong key.
+    assert!(stronghold
+        .rotate_snapshot_key(&new_keyprovider, &old_keyprovider, &snapshot_path)
+        .is_err());
+    stronghold
+        .rotate_snapshot_key(&old_keyprovider, &new_keyprovider, &snapshot_path)
+        .unwrap();
+
+    let stronghold = Stronghold::default();
+    assert!(stronghold.load_snapshot(&old_keyprovider, &snapshot_path).is_err());
+    let client = stronghold
+        .load_client_from_snapshot(&client_path, &new_keyprovider, &snapshot_path)
+        .unwrap();
+    assert_eq!(client.vault(&vault_path).read_secret(b"record_path").unwrap(), payload);
+
+    // the backup has been re-encrypted with the new key as well.
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(snapshot_path.as_path())
+        .unwrap();
+    file.set_len(0).unwrap();
+    let stronghold = Stronghold::default();
+    assert!(stronghold.load_snapshot(&old_keyprovider, &snapshot_path).is_err());
+    assert_eq!(
+        stronghold.load_snapshot(&new_keyprovider, &snapshot_path).unwrap(),
+        SnapshotSource::Backup(1)
+    );
+}
+
+#[test]
+fn test_load_snapshot_from_backup() {
+    let client_path = b"client_path".to_vec();
+    let vault_path = b"vault_path".to_vec();
+    let payloads: Vec<Vec<u8>> = (0..3).map(|_| fixed_random_bytes(64)).collect();
+
+    let mut snapshot_path = std::env::temp_dir();
+    snapshot_path.push(base64::encode(fixed_random_bytes(8)).replace('/', "n"));
+    let defer = Defer::from((snapshot_path, |path: &'_ PathBuf| {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".1");
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(backup);
+    }));
+    let snapshot_path = SnapshotPath::from_path(&*defer);
+    let keyprovider = KeyProvider::try_from(fixed_random_bytes(32)).unwrap();
+
+    let stronghold = Stronghold::default();
+    stronghold.set_snapshot_backups(1);
+    let client = stronghold.create_client(&client_path).unwrap();
+    for payload in &payloads {
+        client
+            .vault(&vault_path)
+            .write_secret(
+                Location::const_generic(vault_path.clone(), b"record_path".to_vec()),
+                payload.clone(),
+            )
+            .unwrap();
+        stronghold
+            .commit_with_keyprovider(&snapshot_path, &keyprovider)
+            .unwrap();
+    }
+    let mut backup = snapshot_path.as_path().as_os_str().to_owned();
+    backup.push(".1");
+    assert!(Path::new(&backup).exists());
+    backup.push(".2");
+    assert!(!Path::new(&backup).exists());
+
+    let stronghold = Stronghold::default();
+    assert_eq!(
+        stronghold.load_snapshot(&keyprovider, &snapshot_path).unwrap(),
+        SnapshotSource::Latest
+    );
+
+    // truncate the newest snapshot file, as if the file system had lost its content.
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(snapshot_path.as_path())
+        .unwrap();
+    file.set_len(0).unwrap();
+
+    let stronghold = Stronghold::default();
+    assert_eq!(
+        stronghold.load_snapshot(&keyprovider, &snapshot_path).unwrap(),
+        SnapshotSource::Backup(1)
+    );
+    let client = stronghold.load_client(&client_path).unwrap();
+    assert_eq!(
+        client.vault(&vault_path).read_secret(b"record_path").unwrap(),
+        payloads[1]
+    );
+}
+
+#[test]
+fn test_commit_with_new_key_drops_backups() {
+    let client_path = b"client_path".to_vec();
+    let vault_path = b"vault_path".to_vec();
+
+    let mut snapshot_path = std::env::temp_dir();
+    snapshot_path.push(base64::encode(fixed_random_bytes(8)).replace('/', "n"));
+    let defer = Defer::from((snapshot_path, |path: &'_ PathBuf| {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".1");
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(backup);
+    }));
+    let snapshot_path = SnapshotPath::from_path(&*defer);
+    let old_keyprovider = KeyProvider::try_from(fixed_random_bytes(32)).unwrap();
+    let new_keyprovider = KeyProvider::try_from(fixed_random_bytes(32)).unwrap();
+
+    let stronghold = Stronghold::default();
+    stronghold.set_snapshot_backups(1);
+    let client = stronghold.create_client(&client_path).unwrap();
+    client
+        .vault(&vault_path)
+        .write_secret(
+            Location::const_generic(vault_path.clone(), b"record_path".to_vec()),
+            fixed_random_bytes(64),
+        )
+        .unwrap();
+    for keyprovider in [&old_keyprovider, &old_keyprovider, &new_keyprovider] {
+        stronghold.commit_with_keyprovider(&snapshot_path, keyprovider).unwrap();
+    }
+
+    // the snapshot file written with the old key is not kept as backup.
+    let mut backup = snapshot_path.as_path().as_os_str().to_owned();
+    backup.push(".1");
+    assert!(!Path::new(&backup).exists());
+
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(snapshot_path.as_path())
+        .unwrap();
+    file.set_len(0).unwrap();
+    let stronghold = Stronghold::default();
+    assert!(stronghold.load_snapshot(&old_keyprovider, &snapshot_path).is_err());
+}
+
+#[test]
+fn test_open_snapshot_with_passphrase() {
+    let client_path = b"client_path".to_vec();
+    let vault_path = b"vault_path".to_vec();
+    let payload = fixed_random_bytes(64);
+    let passphrase = b"passphrase".to_vec();
+
+    let mut snapshot_path = std::env::temp_dir();
+    snapshot_path.push(base64::encode(fixed_random_bytes(8)).replace('/', "n"));
+    let defer = Defer::from((snapshot_path, |path: &'_ PathBuf| {
+        let _ = std::fs::remove_file(path);
+    }));
+    let snapshot_path = SnapshotPath::from_path(&*defer);
+
+    let stronghold = Stronghold::default();
+    let client = stronghold.create_client(&client_path).unwrap();
+    client
+        .vault(&vault_path)
+        .write_secret(
+            Location::const_generic(vault_path.clone(), b"record_path".to_vec()),
+            payload.clone(),
+        )
+        .unwrap();
+    let keyprovider = KeyProvider::with_passphrase_hashed_argon2(passphrase.clone(), b"saltyvalue".to_vec()).unwrap();
+    stronghold
+        .commit_with_keyprovider(&snapshot_path, &keyprovider)
+        .unwrap();
+
+    let header = Snapshot::read_header(&snapshot_path).unwrap().unwrap();
+    assert_eq!(&header.kdf, keyprovider.kdf());
+    assert_eq!(header.clients, vec![*client.id()]);
+    assert!(header.writer.starts_with("iota_stronghold"));
+
+    let stronghold = Stronghold::default();
+    let keyprovider = KeyProvider::with_passphrase_for_snapshot(passphrase, &snapshot_path).unwrap();
+    let client = stronghold
+        .load_client_from_snapshot(&client_path, &keyprovider, &snapshot_path)
+        .unwrap();
+    assert_eq!(client.vault(&vault_path).read_secret(b"record_path").unwrap(), payload);
+
+    let wrong = KeyProvider::with_passphrase_for_snapshot(b"wrong".to_vec(), &snapshot_path).unwrap();
+    assert!(Stronghold::default().load_snapshot(&wrong, &snapshot_path).is_err());
+
+    // snapshots written with a raw key can't be opened with a passphrase.
+    let keyprovider = KeyProvider::try_from(fixed_random_bytes(32)).unwrap();
+    stronghold
+        .commit_with_keyprovider(&snapshot_path, &keyprovider)
+        .unwrap();
+    assert!(KeyProvider::with_passphrase_for_snapshot(b"passphrase".to_vec(), &snapshot_path).is_err());
+}
+
+#[test]
+fn test_open_snapshot_with_tampered_kdf() {
+    let mut snapshot_path = std::env::temp_dir();
+    snapshot_path.push(base64::encode(fixed_random_bytes(8)).replace('/', "n"));
+    let defer = Defer::from((snapshot_path, |path: &'_ PathBuf| {
+        let _ = std::fs::remove_file(path);
+    }));
+    let snapshot_path = SnapshotPath::from_path(&*defer);
+
+    let stronghold = Stronghold::default();
+    stronghold.create_client(b"client_path").unwrap();
+    let keyprovider =
+        KeyProvider::with_passphrase_hashed_argon2(b"passphrase".to_vec(), b"saltyvalue".to_vec()).unwrap();
+    stronghold
+        .commit_with_keyprovider(&snapshot_path, &keyprovider)
+        .unwrap();
+
+    // raise the memory cost in the unauthenticated header, the key must not be derived with it.
+    let header = Snapshot::read_header(&snapshot_path).unwrap().unwrap();
+    let mut tampered = header.clone();
+    match &mut tampered.kdf {
+        SnapshotKdf::Argon2 { memory, .. } => *memory = u32::MAX,
+        kdf => panic!("unexpected kdf {:?}", kdf),
+    }
+    let header = bincode::serialize(&header).unwrap();
+    let tampered = bincode::serialize(&tampered).unwrap();
+    let mut bytes = std::fs::read(snapshot_path.as_path()).unwrap();
+    let start = MAGIC.len() + VERSION.len() + 4;
+    assert_eq!(&bytes[start..start + header.len()], header.as_slice());
+    bytes[start..start + tampered.len()].copy_from_slice(&tampered);
+    std::fs::write(snapshot_path.as_path(), &bytes).unwrap();
+
+    assert!(KeyProvider::with_passphrase_for_snapshot(b"passphrase".to_vec(), &snapshot_path).is_err());
+
+    let kdf = SnapshotKdf::Argon2 {
+        variant: 2,
+        version: 0x13,
+        memory: u32::MAX,
+        iterations: u32::MAX,
+        lanes: 1,
+        salt: b"saltyvalue".to_vec(),
+    };
+    assert!(KeyProvider::with_passphrase_and_kdf(b"passphrase".to_vec(), &kdf).is_err());
+}
@@ -0,0 +1,146 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    procedures::{
+        Ed25519Sign, GenerateKey, KeyType, ProcedureError, PublicKey, StrongholdProcedure,
+        StrongholdProcedurePermission,
+    },
+    ClientPermissions, FwRequest, Location, PermissionError, Stronghold, VariantPermission,
+};
+
+#[test]
+fn test_procedure_permission_values() {
+    let procedure: StrongholdProcedure = GenerateKey {
+        ty: KeyType::Ed25519,
+        output: Location::const_generic(b"vault".to_vec(), b"record".to_vec()),
+    }
+    .into();
+    let permission = StrongholdProcedurePermission::from_request(&procedure);
+    assert_eq!(permission, StrongholdProcedurePermission::GenerateKey);
+    assert_eq!(
+        permission.permission().value(),
+        1 << StrongholdProcedurePermission::GenerateKey as u32
+    );
+    assert_ne!(
+        StrongholdProcedurePermission::Ed25519Sign.permission(),
+        StrongholdProcedurePermission::PublicKey.permission()
+    );
+
+    // Variants beyond the 32nd are permitted independently.
+    let import = StrongholdProcedurePermission::ImportRecord;
+    assert!(import.permission().value() > u32::MAX as u64);
+    let permissions = ClientPermissions::none().allow_procedure(import);
+    assert!(permissions.is_procedure_allowed(import));
+    assert!(!permissions.is_procedure_allowed(StrongholdProcedurePermission::WriteVault));
+    assert!(ClientPermissions::all().is_procedure_allowed(import));
+}
+
+#[test]
+fn test_serialize_permissions() {
+    let permissions = ClientPermissions::none()
+        .allow_procedure(StrongholdProcedurePermission::Ed25519Sign)
+        .allow_procedure(StrongholdProcedurePermission::ImportRecord)
+        .allow_vault_prefix(b"plugin/");
+    let serialized = bincode::serialize(&permissions).unwrap();
+    assert_eq!(
+        bincode::deserialize::<ClientPermissions>(&serialized).unwrap(),
+        permissions
+    );
+
+    // procedures are serialized by name instead of their position in the enum.
+    let contains = |name: &str| serialized.windows(name.len()).any(|window| window == name.as_bytes());
+    assert!(contains("Ed25519Sign"));
+    assert!(contains("ImportRecord"));
+    assert!(!contains("PublicKey"));
+
+    let all = bincode::serialize(&ClientPermissions::all()).unwrap();
+    assert_eq!(
+        bincode::deserialize::<ClientPermissions>(&all).unwrap(),
+        ClientPermissions::all()
+    );
+
+    for procedure in StrongholdProcedurePermission::variants() {
+        assert_eq!(
+            StrongholdProcedurePermission::from_name(procedure.name()),
+            Some(procedure)
+        );
+    }
+    assert_eq!(StrongholdProcedurePermission::from_name("Unknown"), None);
+}
+
+#[test]
+fn test_restricted_client() {
+    let stronghold = Stronghold::default();
+    let client = stronghold.create_client(b"client").unwrap();
+    let key = Location::const_generic(b"plugin/keys".to_vec(), b"signing".to_vec());
+    let other_key = Location::const_generic(b"wallet".to_vec(), b"signing".to_vec());
+    for output in [key.clone(), other_key.clone()] {
+        client
+            .execute_procedure(GenerateKey {
+                ty: KeyType::Ed25519,
+                output,
+            })
+            .unwrap();
+    }
+
+    let signer = client.restrict(
+        ClientPermissions::none()
+            .allow_procedure(StrongholdProcedurePermission::Ed25519Sign)
+            .allow_procedure(StrongholdProcedurePermission::PublicKey)
+            .allow_vault_prefix(b"plugin/"),
+    );
+
+    assert!(signer
+        .execute_procedure(Ed25519Sign {
+            msg: b"message".to_vec(),
+            private_key: key.clone(),
+        })
+        .is_ok());
+    assert!(signer.record_exists(&key).unwrap());
+
+    let result = signer.execute_procedure(GenerateKey {
+        ty: KeyType::Ed25519,
+        output: Location::const_generic(b"plugin/keys".to_vec(), b"new".to_vec()),
+    });
+    assert!(matches!(
+        result,
+        Err(ProcedureError::Permission(PermissionError::ProcedureNotAllowed(_)))
+    ));
+
+    let result = signer.execute_procedure(Ed25519Sign {
+        msg: b"message".to_vec(),
+        private_key: other_key.clone(),
+    });
+    assert!(matches!(
+        result,
+        Err(ProcedureError::Permission(PermissionError::VaultNotAllowed(_)))
+    ));
+    assert!(signer.record_exists(&other_key).is_err());
+
+    // narrowing can't grant permissions that the handle doesn't have
+    let narrowed = signer.restrict(
+        ClientPermissions::all()
+            .allow_procedure(StrongholdProcedurePermission::GenerateKey)
+            .allow_vault_prefix(b""),
+    );
+    assert_eq!(narrowed.permissions(), signer.permissions());
+
+    let public_key_only = signer.restrict(
+        ClientPermissions::none()
+            .allow_procedure(StrongholdProcedurePermission::PublicKey)
+            .allow_vault_prefix(b"plugin/keys"),
+    );
+    assert!(public_key_only
+        .execute_procedure(PublicKey {
+            ty: KeyType::Ed25519,
+            private_key: key.clone(),
+        })
+        .is_ok());
+    assert!(public_key_only
+        .execute_procedure(Ed25519Sign {
+            msg: b"message".to_vec(),
+            private_key: key,
+        })
+        .is_err());
+}
@@ -8,18 +8,30 @@ use crate::procedures::CompareSecret;
 
 use crate::{
     procedures::{
-        AeadCipher, AeadDecrypt, AeadEncrypt, AesKeyWrapCipher, AesKeyWrapDecrypt, AesKeyWrapEncrypt, BIP39Generate,
-        BIP39Recover, ConcatKdf, CopyRecord, DeriveSecret, Ed25519Sign, GenerateKey, GenerateSecret, Hkdf, KeyType,
-        MnemonicLanguage, PublicKey, Sha2Hash, Slip10Derive, Slip10DeriveInput, Slip10Generate, StrongholdProcedure,
-        WriteVault, X25519DiffieHellman,
+        decrypt_share, AeadCipher, AeadDecrypt, AeadEncrypt, AeadNonce, Aes256GcmSiv, AesKeyWrapCipher,
+        AesKeyWrapDecrypt, AesKeyWrapEncrypt, Argon2Derive, Argon2Params, Argon2Password, Argon2Verify, BIP39Generate,
+        BIP39Recover, Bip32Derive, ChaCha20Poly1305, ConcatKdf, ConcatSecret, CopyRecord, DeriveSecret, Ed25519Sign,
+        Ed25519SignBatch, Ed25519SignPrehashed, Ed25519Signatures, Ed25519Verify, Ed25519VerifyPrehashed, ExportRecord,
+        GenerateKey, GenerateSecret, Hkdf, Hmac, HmacVerify, HotpGenerate, HpkeAead, HpkeOpen, HpkeSeal, ImportRecord,
+        KeyType, MnemonicLanguage, OtpHash, OtpKind, PepperedHash, PepperedHashVerify, PolicyError, ProcedureError,
+        PublicKey, Secp256k1EcdsaPublicKey, Secp256k1EcdsaPublicKeyEncoding, Secp256k1EcdsaSign, Secp256k1EcdsaVerify,
+        Sha2Hash, ShamirCombine, ShamirSplit, ShareEncoding, Slip10Derive, Slip10DeriveInput, Slip10Generate,
+        StrongholdProcedure, TotpGenerate, WriteVault, X25519DiffieHellman, ED25519PH_MAX_CONTEXT_LENGTH,
+        ED25519PH_PREHASH_LENGTH, HPKE_ENC_LENGTH, RECORD_ENVELOPE_MAGIC, RECORD_ENVELOPE_VERSION,
+        SECP256K1_ECDSA_PREHASH_LENGTH, SECP256K1_ECDSA_SIGNATURE_LENGTH,
     },
     tests::fresh,
-    Client, Location, Stronghold,
+    Client, ClientError, KeyUsage, Location, RecordPolicy, Stronghold,
 };
+use std::time::SystemTime;
 
 use crypto::{
     ciphers::{aes_gcm::Aes256Gcm, chacha::XChaCha20Poly1305},
-    keys::slip10::ChainCode,
+    keys::{
+        slip10::{Chain, ChainCode},
+        x25519,
+    },
+    macs::hmac::HMAC_SHA256,
     signatures::ed25519,
 };
 use stronghold_utils::random;
@@ -270,7 +282,7 @@ async fn usecase_ed25519() -> Result<(), Box<dyn std::error::Error>> {
         private_key: key.clone(),
         ty: KeyType::Ed25519,
     };
-    let pk: [u8; ed25519::PUBLIC_KEY_LENGTH] = client.execute_procedure(ed25519_pk).unwrap();
+    let pk: [u8; ed25519::PUBLIC_KEY_LENGTH] = client.execute_procedure(ed25519_pk).unwrap().try_into().unwrap();
 
     let msg = fresh::variable_bytestring(4096);
 
@@ -287,6 +299,805 @@ async fn usecase_ed25519() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn usecase_ed25519_sign_batch() {
+    let stronghold: Stronghold = Stronghold::default();
+    let client: Client = stronghold.create_client(b"client_path").unwrap();
+
+    let key = fresh::location();
+    let generate = GenerateKey {
+        ty: KeyType::Ed25519,
+        output: key.clone(),
+    };
+    client.execute_procedure(generate).unwrap();
+    let pk: [u8; ed25519::PUBLIC_KEY_LENGTH] = client
+        .execute_procedure(PublicKey {
+            ty: KeyType::Ed25519,
+            private_key: key.clone(),
+        })
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let pk = ed25519::PublicKey::try_from_bytes(pk).unwrap();
+
+    let msgs: Vec<Vec<u8>> = (0..16).map(|_| fresh::variable_bytestring(512)).collect();
+    let sign_batch = Ed25519SignBatch {
+        msgs: msgs.clone(),
+        private_key: key.clone(),
+    };
+    let Ed25519Signatures(sigs) = client.execute_procedure(sign_batch.clone()).unwrap();
+    assert_eq!(sigs.len(), msgs.len());
+    for (msg, sig) in msgs.iter().zip(sigs.iter()) {
+        assert!(pk.verify(&ed25519::Signature::from_bytes(*sig), msg));
+        let single: [u8; ed25519::SIGNATURE_LENGTH] = client
+            .execute_procedure(Ed25519Sign {
+                msg: msg.clone(),
+                private_key: key.clone(),
+            })
+            .unwrap();
+        assert_eq!(&single, sig);
+    }
+
+    // The signatures are returned concatenated when executed as part of a chain.
+    let output = client
+        .execute_procedure_chained(vec![sign_batch.into()])
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert_eq!(Ed25519Signatures::try_from(output).unwrap().0, sigs);
+
+    let Ed25519Signatures(sigs) = client
+        .execute_procedure(Ed25519SignBatch {
+            msgs: vec![],
+            private_key: key,
+        })
+        .unwrap();
+    assert!(sigs.is_empty());
+}
+
+#[test]
+fn usecase_ed25519_sign_prehashed() {
+    use crypto::hashes::{sha::Sha512, Digest};
+
+    let stronghold: Stronghold = Stronghold::default();
+    let client: Client = stronghold.create_client(b"client_path").unwrap();
+
+    // Test vector "abc" of Ed25519ph from RFC 8032 section 7.3.
+    let sk = hex::decode("833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42").unwrap();
+    let expected = hex::decode(
+        "98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae41\
+         31f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406",
+    )
+    .unwrap();
+    let key = fresh::location();
+    client.vault(key.vault_path()).write_secret(key.clone(), sk).unwrap();
+
+    let prehash = Sha512::digest(b"abc").to_vec();
+    let sig: [u8; ed25519::SIGNATURE_LENGTH] = client
+        .execute_procedure(Ed25519SignPrehashed {
+            prehash: prehash.clone(),
+            context: vec![],
+            private_key: key.clone(),
+        })
+        .unwrap();
+    assert_eq!(sig.to_vec(), expected);
+
+    // A different context results in a different signature.
+    let sig_ctx: [u8; ed25519::SIGNATURE_LENGTH] = client
+        .execute_procedure(Ed25519SignPrehashed {
+            prehash: prehash.clone(),
+            context: b"context".to_vec(),
+            private_key: key.clone(),
+        })
+        .unwrap();
+    assert_ne!(sig, sig_ctx);
+
+    let invalid_prehash = Ed25519SignPrehashed {
+        prehash: prehash[..ED25519PH_PREHASH_LENGTH - 1].to_vec(),
+        context: vec![],
+        private_key: key.clone(),
+    };
+    assert!(matches!(
+        client.execute_procedure(invalid_prehash),
+        Err(ProcedureError::Procedure(_))
+    ));
+    let invalid_context = Ed25519SignPrehashed {
+        prehash,
+        context: vec![0; ED25519PH_MAX_CONTEXT_LENGTH + 1],
+        private_key: key,
+    };
+    assert!(matches!(
+        client.execute_procedure(invalid_context),
+        Err(ProcedureError::Procedure(_))
+    ));
+}
+
+#[test]
+fn usecase_secp256k1_ecdsa() {
+    use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+    let stronghold: Stronghold = Stronghold::default();
+    let client: Client = stronghold.create_client(b"client_path").unwrap();
+
+    let key = fresh::location();
+    let generate = GenerateKey {
+        ty: KeyType::Secp256k1Ecdsa,
+        output: key.clone(),
+    };
+    let get_pk = PublicKey {
+        ty: KeyType::Secp256k1Ecdsa,
+        private_key: key.clone(),
+    };
+    let get_uncompressed_pk = Secp256k1EcdsaPublicKey {
+        encoding: Secp256k1EcdsaPublicKeyEncoding::Uncompressed,
+        private_key: key.clone(),
+    };
+
+    let prehash: [u8; 32] = random::random();
+    let sign = Secp256k1EcdsaSign {
+        prehash,
+        private_key: key,
+    };
+
+    let procedures = vec![generate.into(), get_pk.into(), get_uncompressed_pk.into(), sign.into()];
+    let output = client.execute_procedure_chained(procedures).unwrap();
+
+    let compressed: Vec<u8> = output[1].clone().into();
+    let uncompressed: Vec<u8> = output[2].clone().into();
+    let sig: [u8; 65] = output[3].clone().try_into().unwrap();
+    assert_eq!(compressed.len(), 33);
+    assert_eq!(uncompressed.len(), 65);
+
+    let pk = VerifyingKey::from_sec1_bytes(&compressed).unwrap();
+    assert_eq!(pk, VerifyingKey::from_sec1_bytes(&uncompressed).unwrap());
+
+    let signature = Signature::from_slice(&sig[..64]).unwrap();
+    let recid = RecoveryId::from_byte(sig[64]).unwrap();
+    let recovered = VerifyingKey::recover_from_prehash(&prehash, &signature, recid).unwrap();
+    assert_eq!(pk, recovered);
+}
+
+#[test]
+fn test_secp256k1_ecdsa_evm_address() {
+    // Test vector from the web3.js `eth.accounts` documentation.
+    let client: Client = Client::default();
+    let key = fresh::location();
+    let secret = hex::decode("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318").unwrap();
+    client
+        .execute_procedure(WriteVault {
+            data: secret,
+            location: key.clone(),
+        })
+        .unwrap();
+
+    let address = client
+        .execute_procedure(Secp256k1EcdsaPublicKey {
+            encoding: Secp256k1EcdsaPublicKeyEncoding::EvmAddress,
+            private_key: key,
+        })
+        .unwrap();
+    assert_eq!(hex::encode(address), "2c7536e3605d9c16a7a3d7b1898e529396a65c23");
+}
+
+#[test]
+fn usecase_ed25519_verify() {
+    use crypto::hashes::{sha::Sha512, Digest};
+
+    let client: Client = Client::default();
+    let key = fresh::location();
+    client
+        .execute_procedure(GenerateKey {
+            ty: KeyType::Ed25519,
+            output: key.clone(),
+        })
+        .unwrap();
+    let public_key: [u8; ed25519::PUBLIC_KEY_LENGTH] = client
+        .execute_procedure(PublicKey {
+            ty: KeyType::Ed25519,
+            private_key: key.clone(),
+        })
+        .unwrap()
+        .try_into()
+        .unwrap();
+
+    let msg = fresh::variable_bytestring(4096);
+    let sig: [u8; ed25519::SIGNATURE_LENGTH] = client
+        .execute_procedure(Ed25519Sign {
+            msg: msg.clone(),
+            private_key: key.clone(),
+        })
+        .unwrap();
+    let verify = |msg: &[u8], signature: &[u8]| {
+        client
+            .execute_procedure(Ed25519Verify {
+                msg: msg.to_vec(),
+                signature: signature.to_vec(),
+                public_key,
+            })
+            .unwrap()
+    };
+    assert!(verify(&msg, &sig));
+    assert!(!verify(b"other message", &sig));
+    let mut tampered = sig;
+    tampered[0] ^= 1;
+    assert!(!verify(&msg, &tampered));
+    assert!(!verify(&msg, &sig[1..]));
+
+    let prehash = Sha512::digest(&msg).to_vec();
+    let sig: [u8; ed25519::SIGNATURE_LENGTH] = client
+        .execute_procedure(Ed25519SignPrehashed {
+            prehash: prehash.clone(),
+            context: b"context".to_vec(),
+            private_key: key,
+        })
+        .unwrap();
+    let verify_prehashed = |prehash: &[u8], context: &[u8], signature: &[u8]| {
+        client.execute_procedure(Ed25519VerifyPrehashed {
+            prehash: prehash.to_vec(),
+            context: context.to_vec(),
+            signature: signature.to_vec(),
+            public_key,
+        })
+    };
+    assert!(verify_prehashed(&prehash, b"context", &sig).unwrap());
+    assert!(!verify_prehashed(&prehash, b"", &sig).unwrap());
+    assert!(!verify_prehashed(&Sha512::digest(b"other"), b"context", &sig).unwrap());
+    // The Ed25519ph signature is not a valid signature of the message itself.
+    assert!(!verify(&msg, &sig));
+    assert!(matches!(
+        verify_prehashed(&prehash[1..], b"context", &sig),
+        Err(ProcedureError::Procedure(_))
+    ));
+}
+
+#[test]
+fn usecase_secp256k1_ecdsa_verify() {
+    let client: Client = Client::default();
+    let key = fresh::location();
+    let other_key = fresh::location();
+    for output in [key.clone(), other_key.clone()] {
+        client
+            .execute_procedure(GenerateKey {
+                ty: KeyType::Secp256k1Ecdsa,
+                output,
+            })
+            .unwrap();
+    }
+    let public_key = |private_key: &Location, encoding: Secp256k1EcdsaPublicKeyEncoding| {
+        client
+            .execute_procedure(Secp256k1EcdsaPublicKey {
+                encoding,
+                private_key: private_key.clone(),
+            })
+            .unwrap()
+    };
+
+    let prehash: [u8; SECP256K1_ECDSA_PREHASH_LENGTH] = random::random();
+    let sig: [u8; SECP256K1_ECDSA_SIGNATURE_LENGTH] = client
+        .execute_procedure(Secp256k1EcdsaSign {
+            prehash,
+            private_key: key.clone(),
+        })
+        .unwrap();
+    let verify = |prehash: [u8; SECP256K1_ECDSA_PREHASH_LENGTH],
+                  signature: &[u8],
+                  encoding: Secp256k1EcdsaPublicKeyEncoding,
+                  public_key: Vec<u8>| {
+        client
+            .execute_procedure(Secp256k1EcdsaVerify {
+                prehash,
+                signature: signature.to_vec(),
+                encoding,
+                public_key,
+            })
+            .unwrap()
+    };
+
+    for encoding in [
+        Secp256k1EcdsaPublicKeyEncoding::Compressed,
+        Secp256k1EcdsaPublicKeyEncoding::Uncompressed,
+        Secp256k1EcdsaPublicKeyEncoding::EvmAddress,
+    ] {
+        assert!(verify(prehash, &sig, encoding, public_key(&key, encoding)));
+        assert!(!verify(prehash, &sig, encoding, public_key(&other_key, encoding)));
+        assert!(!verify(random::random(), &sig, encoding, public_key(&key, encoding)));
+    }
+
+    // Without the recovery id, the signature can only be verified with a public key.
+    let compressed = public_key(&key, Secp256k1EcdsaPublicKeyEncoding::Compressed);
+    assert!(verify(
+        prehash,
+        &sig[..64],
+        Secp256k1EcdsaPublicKeyEncoding::Compressed,
+        compressed.clone()
+    ));
+    let address = public_key(&key, Secp256k1EcdsaPublicKeyEncoding::EvmAddress);
+    assert!(!verify(
+        prehash,
+        &sig[..64],
+        Secp256k1EcdsaPublicKeyEncoding::EvmAddress,
+        address
+    ));
+    assert!(!verify(
+        prehash,
+        &sig[..10],
+        Secp256k1EcdsaPublicKeyEncoding::Compressed,
+        compressed
+    ));
+
+    let invalid_public_key = Secp256k1EcdsaVerify {
+        prehash,
+        signature: sig.to_vec(),
+        encoding: Secp256k1EcdsaPublicKeyEncoding::Compressed,
+        public_key: vec![0; 33],
+    };
+    assert!(matches!(
+        client.execute_procedure(invalid_public_key),
+        Err(ProcedureError::Procedure(_))
+    ));
+}
+
+#[test]
+fn usecase_hmac_verify() {
+    let client: Client = Client::default();
+    let key = fresh::location();
+    client
+        .execute_procedure(WriteVault {
+            data: fresh::variable_bytestring(64),
+            location: key.clone(),
+        })
+        .unwrap();
+
+    let msg = fresh::variable_bytestring(1024);
+    for hash_type in [Sha2Hash::Sha256, Sha2Hash::Sha384, Sha2Hash::Sha512] {
+        let mac = client
+            .execute_procedure(Hmac {
+                hash_type: hash_type.clone(),
+                msg: msg.clone(),
+                key: key.clone(),
+            })
+            .unwrap();
+        let verify = |msg: &[u8], mac: &[u8]| {
+            client
+                .execute_procedure(HmacVerify {
+                    hash_type: hash_type.clone(),
+                    msg: msg.to_vec(),
+                    mac: mac.to_vec(),
+                    key: key.clone(),
+                })
+                .unwrap()
+        };
+        assert!(verify(&msg, &mac));
+        assert!(!verify(b"other message", &mac));
+        assert!(!verify(&msg, &mac[..mac.len() - 1]));
+        let mut tampered = mac.clone();
+        tampered[0] ^= 1;
+        assert!(!verify(&msg, &tampered));
+    }
+
+    // Verifying a MAC requires the same key usage as creating it.
+    let restricted = fresh::location();
+    client
+        .execute_procedure_with_policy(
+            WriteVault {
+                data: fresh::variable_bytestring(64),
+                location: restricted.clone(),
+            },
+            RecordPolicy::new(KeyUsage::EXPORT),
+        )
+        .unwrap();
+    let result = client.execute_procedure(HmacVerify {
+        hash_type: Sha2Hash::Sha256,
+        msg,
+        mac: vec![0; 32],
+        key: restricted,
+    });
+    assert!(matches!(result, Err(ProcedureError::Policy(_))));
+}
+
+#[test]
+fn usecase_otp() {
+    let client: Client = Client::default();
+
+    // RFC 6238, Appendix B
+    let totp = fresh::location();
+    let (write, parameters) = WriteVault::from_otpauth_uri(
+        "otpauth://totp/Example:alice@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Example&digits=8",
+        totp.clone(),
+    )
+    .unwrap();
+    assert_eq!(parameters.kind, OtpKind::Totp { period: 30 });
+    client.execute_procedure(write).unwrap();
+    for (timestamp, code) in [(59, "94287082"), (1111111109, "07081804"), (20000000000, "65353130")] {
+        let generate = parameters.totp(totp.clone(), timestamp).unwrap();
+        assert_eq!(client.execute_procedure(generate).unwrap(), code);
+    }
+    let code = client
+        .execute_procedure(TotpGenerate {
+            hash: OtpHash::Sha1,
+            digits: 6,
+            period: 60,
+            timestamp: 119,
+            key: totp.clone(),
+        })
+        .unwrap();
+    assert_eq!(code, "287082");
+    let result = client.execute_procedure(TotpGenerate {
+        hash: OtpHash::Sha1,
+        digits: 6,
+        period: 0,
+        timestamp: 59,
+        key: totp,
+    });
+    assert!(matches!(result, Err(ProcedureError::Procedure(_))));
+
+    // RFC 4226, Appendix D
+    let hotp = fresh::location();
+    let (write, parameters) = WriteVault::from_otpauth_uri(
+        "otpauth://hotp/alice?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&counter=3",
+        hotp.clone(),
+    )
+    .unwrap();
+    client.execute_procedure(write).unwrap();
+    let generate = parameters.hotp(hotp.clone()).unwrap();
+    assert_eq!(client.execute_procedure(generate).unwrap(), "969429");
+    let result = client.execute_procedure(HotpGenerate {
+        hash: OtpHash::Sha256,
+        digits: 4,
+        counter: 0,
+        key: hotp,
+    });
+    assert!(matches!(result, Err(ProcedureError::Procedure(_))));
+
+    // The shared secret can't be used if its policy doesn't allow MACs.
+    let restricted = fresh::location();
+    let (write, _) = WriteVault::from_otpauth_uri("otpauth://totp/bob?secret=MZXW6YTBOI", restricted.clone()).unwrap();
+    client
+        .execute_procedure_with_policy(write, RecordPolicy::new(KeyUsage::EXPORT))
+        .unwrap();
+    let result = client.execute_procedure(TotpGenerate {
+        hash: OtpHash::Sha1,
+        digits: 6,
+        period: 30,
+        timestamp: 0,
+        key: restricted,
+    });
+    assert!(matches!(result, Err(ProcedureError::Policy(_))));
+}
+
+#[test]
+fn usecase_argon2() {
+    let client: Client = Client::default();
+    let params = Argon2Params {
+        memory_cost: 64,
+        time_cost: 1,
+        parallelism: 1,
+    };
+    let config = argon2::Config {
+        variant: argon2::Variant::Argon2id,
+        mem_cost: params.memory_cost,
+        time_cost: params.time_cost,
+        lanes: params.parallelism,
+        hash_length: 32,
+        ..Default::default()
+    };
+    let password = b"correct horse battery staple".to_vec();
+    let salt = random::fixed_bytestring(16);
+    let expected = argon2::hash_raw(&password, &salt, &config).unwrap();
+
+    let stored_password = fresh::location();
+    client
+        .execute_procedure(WriteVault {
+            data: password.clone(),
+            location: stored_password.clone(),
+        })
+        .unwrap();
+
+    // Derive the same key from the supplied and the stored password.
+    let mac = |key: &Location| {
+        client
+            .execute_procedure(Hmac {
+                hash_type: Sha2Hash::Sha256,
+                msg: b"message".to_vec(),
+                key: key.clone(),
+            })
+            .unwrap()
+    };
+    let mut expected_mac = [0; 32];
+    HMAC_SHA256(b"message", &expected, &mut expected_mac);
+    for password in [
+        Argon2Password::Supplied(password.clone()),
+        Argon2Password::Stored(stored_password.clone()),
+    ] {
+        let output = fresh::location();
+        client
+            .execute_procedure(Argon2Derive {
+                password,
+                salt: salt.clone(),
+                params,
+                key_length: 32,
+                output: output.clone(),
+            })
+            .unwrap();
+        assert_eq!(mac(&output), expected_mac);
+    }
+
+    let result = client.execute_procedure(Argon2Derive {
+        password: Argon2Password::Supplied(password.clone()),
+        salt: vec![0; 4],
+        params,
+        key_length: 32,
+        output: fresh::location(),
+    });
+    assert!(matches!(result, Err(ProcedureError::Procedure(_))));
+
+    // Parameters beyond the limits are rejected before hashing.
+    let excessive = [
+        Argon2Params {
+            memory_cost: u32::MAX,
+            ..params
+        },
+        Argon2Params {
+            time_cost: u32::MAX,
+            ..params
+        },
+        Argon2Params {
+            parallelism: u32::MAX,
+            ..params
+        },
+    ];
+    for params in excessive {
+        assert!(!params.is_within_limits());
+        let result = client.execute_procedure(Argon2Derive {
+            password: Argon2Password::Supplied(password.clone()),
+            salt: salt.clone(),
+            params,
+            key_length: 32,
+            output: fresh::location(),
+        });
+        assert!(matches!(result, Err(ProcedureError::Procedure(_))));
+    }
+    let result = client.execute_procedure(Argon2Derive {
+        password: Argon2Password::Supplied(password.clone()),
+        salt: salt.clone(),
+        params,
+        key_length: u32::MAX,
+        output: fresh::location(),
+    });
+    assert!(matches!(result, Err(ProcedureError::Procedure(_))));
+
+    // Verify the password against a PHC string.
+    let hash = argon2::hash_encoded(&password, &salt, &config).unwrap();
+    let verify = |password: Argon2Password, hash: &str| {
+        client.execute_procedure(Argon2Verify {
+            password,
+            hash: hash.to_string(),
+        })
+    };
+    assert!(verify(Argon2Password::Supplied(password.clone()), &hash).unwrap());
+    assert!(verify(Argon2Password::Stored(stored_password.clone()), &hash).unwrap());
+    assert!(!verify(Argon2Password::Supplied(b"wrong password".to_vec()), &hash).unwrap());
+    assert!(matches!(
+        verify(Argon2Password::Supplied(password.clone()), "$argon2id$invalid"),
+        Err(ProcedureError::Procedure(_))
+    ));
+    let excessive = hash.replace("m=64,", &format!("m={},", u32::MAX));
+    assert!(matches!(
+        verify(Argon2Password::Supplied(password.clone()), &excessive),
+        Err(ProcedureError::Procedure(_))
+    ));
+
+    // A stored password can't be used if its policy doesn't allow derivation.
+    let restricted = fresh::location();
+    client
+        .execute_procedure_with_policy(
+            WriteVault {
+                data: password,
+                location: restricted.clone(),
+            },
+            RecordPolicy::new(KeyUsage::SIGN),
+        )
+        .unwrap();
+    assert!(matches!(
+        verify(Argon2Password::Stored(restricted), &hash),
+        Err(ProcedureError::Policy(_))
+    ));
+}
+
+#[test]
+fn usecase_peppered_hash() {
+    let client: Client = Client::default();
+    let params = Argon2Params {
+        memory_cost: 64,
+        time_cost: 1,
+        parallelism: 1,
+    };
+    let password = b"correct horse battery staple".to_vec();
+    let salt = random::fixed_bytestring(16);
+    let pepper_secret = random::fixed_bytestring(32);
+
+    let pepper = fresh::location();
+    client
+        .execute_procedure_with_policy(
+            WriteVault {
+                data: pepper_secret.clone(),
+                location: pepper.clone(),
+            },
+            RecordPolicy::new(KeyUsage::SIGN),
+        )
+        .unwrap();
+
+    let peppered_hash = |hash_type: Sha2Hash, password: &[u8], pepper: &Location| {
+        client.execute_procedure(PepperedHash {
+            hash_type,
+            password: password.to_vec(),
+            salt: salt.clone(),
+            params,
+            pepper: pepper.clone(),
+        })
+    };
+    let hash = peppered_hash(Sha2Hash::Sha256, &password, &pepper).unwrap();
+
+    let config = argon2::Config {
+        variant: argon2::Variant::Argon2id,
+        mem_cost: params.memory_cost,
+        time_cost: params.time_cost,
+        lanes: params.parallelism,
+        hash_length: 32,
+        ..Default::default()
+    };
+    let hashed = argon2::hash_raw(&password, &salt, &config).unwrap();
+    let mut expected = [0; 32];
+    HMAC_SHA256(&hashed, &pepper_secret, &mut expected);
+    assert_eq!(hash, expected);
+    assert_eq!(peppered_hash(Sha2Hash::Sha512, &password, &pepper).unwrap().len(), 64);
+
+    let verify = |password: &[u8], hash: &[u8]| {
+        client
+            .execute_procedure(PepperedHashVerify {
+                hash_type: Sha2Hash::Sha256,
+                password: password.to_vec(),
+                salt: salt.clone(),
+                params,
+                pepper: pepper.clone(),
+                hash: hash.to_vec(),
+            })
+            .unwrap()
+    };
+    assert!(verify(&password, &hash));
+    assert!(!verify(b"wrong password", &hash));
+    assert!(!verify(&password, &hash[..31]));
+    let mut tampered = hash.clone();
+    tampered[0] ^= 1;
+    assert!(!verify(&password, &tampered));
+
+    // The pepper can neither be copied out of its record nor be used if the record is exportable.
+    let result = client.execute_procedure(CopyRecord {
+        source: pepper,
+        target: fresh::location(),
+    });
+    assert!(matches!(result, Err(ProcedureError::Policy(_))));
+    let exportable = fresh::location();
+    client
+        .execute_procedure(WriteVault {
+            data: pepper_secret.clone(),
+            location: exportable.clone(),
+        })
+        .unwrap();
+    assert!(matches!(
+        peppered_hash(Sha2Hash::Sha256, &password, &exportable),
+        Err(ProcedureError::Policy(PolicyError::Exportable))
+    ));
+
+    let restricted = fresh::location();
+    client
+        .execute_procedure_with_policy(
+            WriteVault {
+                data: pepper_secret,
+                location: restricted.clone(),
+            },
+            RecordPolicy::new(KeyUsage::DERIVE),
+        )
+        .unwrap();
+    assert!(matches!(
+        peppered_hash(Sha2Hash::Sha256, &password, &restricted),
+        Err(ProcedureError::Policy(PolicyError::UsageNotAllowed(usage))) if usage == KeyUsage::SIGN
+    ));
+    assert!(matches!(
+        peppered_hash(Sha2Hash::Sha256, &password, &fresh::location()),
+        Err(ProcedureError::Engine(_))
+    ));
+}
+
+// Test vector 1 from https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki#test-vector-1
+#[test]
+fn test_bip32_derive_test_vector_1() {
+    use k256::ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey};
+
+    let client: Client = Client::default();
+    let seed = fresh::location();
+    client
+        .execute_procedure(WriteVault {
+            data: hex::decode("000102030405060708090a0b0c0d0e0f").unwrap(),
+            location: seed.clone(),
+        })
+        .unwrap();
+
+    let master = fresh::location();
+    let xpub = client
+        .execute_procedure(Bip32Derive {
+            chain: Chain::empty(),
+            input: Slip10DeriveInput::Seed(seed.clone()),
+            output: master.clone(),
+        })
+        .unwrap();
+    assert_eq!(
+        xpub,
+        "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8"
+    );
+
+    // m/0H/1, mixing a hardened and a non-hardened segment
+    let child = fresh::location();
+    let xpub = client
+        .execute_procedure(Bip32Derive {
+            chain: Chain::from_u32([0x8000_0000, 1]),
+            input: Slip10DeriveInput::Seed(seed),
+            output: child.clone(),
+        })
+        .unwrap();
+    assert_eq!(
+        xpub,
+        "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ"
+    );
+    let secret = client
+        .vault(child.vault_path())
+        .read_secret(child.record_path())
+        .unwrap();
+    assert_eq!(
+        hex::encode(&secret[..32]),
+        "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368"
+    );
+
+    // Deriving step by step from the master key yields the same key.
+    let intermediate = fresh::location();
+    let stepwise = fresh::location();
+    client
+        .execute_procedure_chained(vec![
+            Bip32Derive {
+                chain: Chain::from_u32_hardened([0]),
+                input: Slip10DeriveInput::Key(master),
+                output: intermediate.clone(),
+            }
+            .into(),
+            Bip32Derive {
+                chain: Chain::from_u32([1]),
+                input: Slip10DeriveInput::Key(intermediate),
+                output: stepwise.clone(),
+            }
+            .into(),
+        ])
+        .unwrap();
+    let stepwise_secret = client
+        .vault(stepwise.vault_path())
+        .read_secret(stepwise.record_path())
+        .unwrap();
+    assert_eq!(secret, stepwise_secret);
+
+    // The derived key can be used for signing directly.
+    let prehash = [7u8; 32];
+    let signature = client
+        .execute_procedure(Secp256k1EcdsaSign {
+            prehash,
+            private_key: child,
+        })
+        .unwrap();
+    let signing_key = SigningKey::from_slice(&secret[..32]).unwrap();
+    let signature_rs = Signature::from_slice(&signature[..64]).unwrap();
+    let recid = RecoveryId::from_byte(signature[64]).unwrap();
+    let recovered = VerifyingKey::recover_from_prehash(&prehash, &signature_rs, recid).unwrap();
+    assert_eq!(&recovered, signing_key.verifying_key());
+}
+
 #[tokio::test]
 async fn usecase_slip10derive_intermediate_keys() -> Result<(), Box<dyn std::error::Error>> {
     let stronghold: Stronghold = Stronghold::default();
@@ -463,11 +1274,7 @@ async fn test_aead(
 
     let test_plaintext = random::variable_bytestring(4096);
     let test_associated_data = random::variable_bytestring(4096);
-    let nonce_len = match cipher {
-        AeadCipher::Aes256Gcm => Aes256Gcm::NONCE_LENGTH,
-        AeadCipher::XChaCha20Poly1305 => XChaCha20Poly1305::NONCE_LENGTH,
-    };
-    let mut test_nonce = Vec::with_capacity(nonce_len);
+    let mut test_nonce = Vec::with_capacity(cipher.nonce_length());
     for _ in 0..test_nonce.capacity() {
         test_nonce.push(random::random())
     }
@@ -478,13 +1285,10 @@ async fn test_aead(
         key: key_location.clone(),
         plaintext: test_plaintext.clone(),
         associated_data: test_associated_data.clone(),
-        nonce: test_nonce.clone(),
+        nonce: test_nonce.clone().into(),
     };
 
-    let tag_len = match cipher {
-        AeadCipher::Aes256Gcm => Aes256Gcm::TAG_LENGTH,
-        AeadCipher::XChaCha20Poly1305 => XChaCha20Poly1305::TAG_LENGTH,
-    };
+    let tag_len = cipher.tag_length();
     let mut output = client.execute_procedure(aead).unwrap();
     let out_tag: Vec<u8> = output.drain(..tag_len).collect();
     let out_ciphertext = output;
@@ -495,6 +1299,8 @@ async fn test_aead(
     let f = match cipher {
         AeadCipher::Aes256Gcm => Aes256Gcm::try_encrypt,
         AeadCipher::XChaCha20Poly1305 => XChaCha20Poly1305::try_encrypt,
+        AeadCipher::Aes256GcmSiv => Aes256GcmSiv::try_encrypt,
+        AeadCipher::ChaCha20Poly1305 => ChaCha20Poly1305::try_encrypt,
     };
     f(
         key,
@@ -526,6 +1332,8 @@ async fn test_aead(
     let f = match cipher {
         AeadCipher::Aes256Gcm => Aes256Gcm::try_decrypt,
         AeadCipher::XChaCha20Poly1305 => XChaCha20Poly1305::try_decrypt,
+        AeadCipher::Aes256GcmSiv => Aes256GcmSiv::try_decrypt,
+        AeadCipher::ChaCha20Poly1305 => ChaCha20Poly1305::try_decrypt,
     };
     f(
         key,
@@ -559,9 +1367,175 @@ async fn usecase_aead() -> Result<(), Box<dyn std::error::Error>> {
 
     test_aead(&client, key_location.clone(), &key, AeadCipher::Aes256Gcm).await?;
     test_aead(&client, key_location.clone(), &key, AeadCipher::XChaCha20Poly1305).await?;
+    test_aead(&client, key_location.clone(), &key, AeadCipher::Aes256GcmSiv).await?;
+    test_aead(&client, key_location.clone(), &key, AeadCipher::ChaCha20Poly1305).await?;
     Ok(())
 }
 
+#[test]
+fn usecase_aead_nonce() {
+    let client = Client::default();
+    let key = fresh::location();
+    client
+        .vault(key.vault_path())
+        .write_secret(key.clone(), random::fixed_bytestring(32))
+        .unwrap();
+    client
+        .set_record_policy(&key, RecordPolicy::new(KeyUsage::ENCRYPT))
+        .unwrap();
+    let encrypt = |cipher: AeadCipher, nonce: AeadNonce| {
+        client.execute_procedure(AeadEncrypt {
+            cipher,
+            associated_data: b"ad".to_vec(),
+            plaintext: b"plaintext".to_vec(),
+            nonce,
+            key: key.clone(),
+        })
+    };
+    let decrypt = |cipher: AeadCipher, output: &[u8]| {
+        let (nonce, output) = output.split_at(cipher.nonce_length());
+        let (tag, ciphertext) = output.split_at(cipher.tag_length());
+        client
+            .execute_procedure(AeadDecrypt {
+                cipher,
+                associated_data: b"ad".to_vec(),
+                ciphertext: ciphertext.to_vec(),
+                tag: tag.to_vec(),
+                nonce: nonce.to_vec(),
+                key: key.clone(),
+            })
+            .unwrap()
+    };
+
+    for cipher in [
+        AeadCipher::Aes256Gcm,
+        AeadCipher::XChaCha20Poly1305,
+        AeadCipher::Aes256GcmSiv,
+        AeadCipher::ChaCha20Poly1305,
+    ] {
+        // random nonces are prepended to the output
+        let first = encrypt(cipher, AeadNonce::Random).unwrap();
+        let second = encrypt(cipher, AeadNonce::Random).unwrap();
+        assert_eq!(
+            first.len(),
+            cipher.nonce_length() + cipher.tag_length() + b"plaintext".len()
+        );
+        assert_ne!(first[..cipher.nonce_length()], second[..cipher.nonce_length()]);
+        assert_eq!(decrypt(cipher, &first), b"plaintext");
+        assert_eq!(decrypt(cipher, &second), b"plaintext");
+    }
+
+    // counter nonces have to increase per key
+    let output = encrypt(AeadCipher::Aes256Gcm, AeadNonce::Counter(1)).unwrap();
+    assert_eq!(output[..12], [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    assert_eq!(decrypt(AeadCipher::Aes256Gcm, &output), b"plaintext");
+    let output = encrypt(AeadCipher::ChaCha20Poly1305, AeadNonce::Counter(5)).unwrap();
+    assert_eq!(decrypt(AeadCipher::ChaCha20Poly1305, &output), b"plaintext");
+    for counter in [5, 4, 1] {
+        let result = encrypt(AeadCipher::Aes256Gcm, AeadNonce::Counter(counter));
+        assert!(matches!(result, Err(ProcedureError::Procedure(_))));
+    }
+    assert!(encrypt(AeadCipher::Aes256Gcm, AeadNonce::Counter(6)).is_ok());
+    let metadata = client.record_metadata(&key).unwrap().unwrap();
+    assert_eq!(metadata.next_nonce_counter, 7);
+
+    // the key can't be copied together with its counter
+    let result = client.execute_procedure(CopyRecord {
+        source: key.clone(),
+        target: fresh::location(),
+    });
+    assert!(matches!(
+        result,
+        Err(ProcedureError::Policy(PolicyError::UsageNotAllowed(KeyUsage::EXPORT)))
+    ));
+
+    // the counters are tracked per key, which must not be exportable
+    let other_key = fresh::location();
+    client
+        .vault(other_key.vault_path())
+        .write_secret(other_key.clone(), random::fixed_bytestring(32))
+        .unwrap();
+    let encrypt_other = || {
+        client.execute_procedure(AeadEncrypt {
+            cipher: AeadCipher::Aes256Gcm,
+            associated_data: vec![],
+            plaintext: vec![],
+            nonce: AeadNonce::Counter(1),
+            key: other_key.clone(),
+        })
+    };
+    assert!(matches!(
+        encrypt_other(),
+        Err(ProcedureError::Policy(PolicyError::Exportable))
+    ));
+    client
+        .set_record_policy(&other_key, RecordPolicy::new(KeyUsage::ENCRYPT))
+        .unwrap();
+    assert!(encrypt_other().is_ok());
+
+    // a missing key can't be used
+    let result = client.execute_procedure(AeadEncrypt {
+        cipher: AeadCipher::Aes256Gcm,
+        associated_data: vec![],
+        plaintext: vec![],
+        nonce: AeadNonce::Counter(1),
+        key: fresh::location(),
+    });
+    assert!(result.is_err());
+}
+
+#[test]
+fn usecase_aead_stored_nonce_counter() {
+    let client = Client::default();
+    let key = fresh::location();
+    client
+        .vault(key.vault_path())
+        .write_secret(key.clone(), random::fixed_bytestring(32))
+        .unwrap();
+    let encrypt = |nonce: AeadNonce| {
+        client.execute_procedure(AeadEncrypt {
+            cipher: AeadCipher::ChaCha20Poly1305,
+            associated_data: vec![],
+            plaintext: b"plaintext".to_vec(),
+            nonce,
+            key: key.clone(),
+        })
+    };
+
+    // the key must not be exportable
+    assert!(matches!(
+        encrypt(AeadNonce::StoredCounter),
+        Err(ProcedureError::Policy(PolicyError::Exportable))
+    ));
+    client
+        .set_record_policy(&key, RecordPolicy::new(KeyUsage::ENCRYPT))
+        .unwrap();
+
+    for expected in 0u64..3 {
+        let output = encrypt(AeadNonce::StoredCounter).unwrap();
+        assert_eq!(output[4..12], expected.to_be_bytes());
+    }
+
+    // explicit counters share the counter of the key
+    assert!(matches!(
+        encrypt(AeadNonce::Counter(2)),
+        Err(ProcedureError::Procedure(_))
+    ));
+    encrypt(AeadNonce::Counter(7)).unwrap();
+    let output = encrypt(AeadNonce::StoredCounter).unwrap();
+    assert_eq!(output[4..12], 8u64.to_be_bytes());
+    let metadata = client.record_metadata(&key).unwrap().unwrap();
+    assert_eq!(metadata.next_nonce_counter, 9);
+
+    // overwriting the record keeps the counter
+    client
+        .vault(key.vault_path())
+        .write_secret(key.clone(), random::fixed_bytestring(32))
+        .unwrap();
+    let output = encrypt(AeadNonce::StoredCounter).unwrap();
+    assert_eq!(output[4..12], 9u64.to_be_bytes());
+}
+
 #[tokio::test]
 async fn usecase_diffie_hellman() -> Result<(), Box<dyn std::error::Error>> {
     let stronghold: Stronghold = Stronghold::default();
@@ -808,8 +1782,6 @@ async fn test_bip39_recover_zeroize() -> Result<(), Box<dyn std::error::Error>>
 #[test]
 #[cfg(feature = "insecure")]
 fn test_usecase_concatkdf() {
-    use crate::procedures::ConcatSecret;
-
     let client_path = b"client-path";
 
     let location_a = Location::const_generic(b"first-loc".to_vec(), b"first-loc".to_vec());
@@ -852,3 +1824,566 @@ fn test_usecase_concatkdf() {
     let result = result.unwrap();
     assert!(result[0] == 1, "failed: ({:?})", result);
 }
+
+#[test]
+fn test_record_policy() {
+    let client = Client::default();
+
+    // a sign-only key can't be used for MACs of arbitrary data or to derive other keys.
+    let key = fresh::location();
+    client
+        .execute_procedure_with_policy(
+            GenerateKey {
+                ty: KeyType::Ed25519,
+                output: key.clone(),
+            },
+            RecordPolicy::new(KeyUsage::SIGN),
+        )
+        .unwrap();
+    client
+        .execute_procedure(Ed25519Sign {
+            msg: b"msg".to_vec(),
+            private_key: key.clone(),
+        })
+        .unwrap();
+    client
+        .execute_procedure(PublicKey {
+            ty: KeyType::Ed25519,
+            private_key: key.clone(),
+        })
+        .unwrap();
+    let result = client.execute_procedure(X25519DiffieHellman {
+        private_key: key.clone(),
+        public_key: [0; 32],
+        shared_key: fresh::location(),
+    });
+    assert!(matches!(
+        result,
+        Err(ProcedureError::Policy(PolicyError::UsageNotAllowed(usage))) if usage == KeyUsage::DERIVE
+    ));
+    let copy = fresh::location();
+    let result = client.execute_procedure(CopyRecord {
+        source: key.clone(),
+        target: copy.clone(),
+    });
+    assert!(matches!(result, Err(ProcedureError::Policy(_))));
+    assert!(!client.record_exists(&copy).unwrap());
+
+    // a derive-only seed can only be used to derive keys, the derived key is not restricted.
+    let seed = fresh::location();
+    let derived = fresh::location();
+    client
+        .execute_procedure_with_policy(
+            Slip10Generate {
+                output: seed.clone(),
+                size_bytes: None,
+            },
+            RecordPolicy::new(KeyUsage::DERIVE),
+        )
+        .unwrap();
+    client
+        .execute_procedure(Slip10Derive {
+            chain: fresh::hd_path().1,
+            input: Slip10DeriveInput::Seed(seed.clone()),
+            output: derived.clone(),
+        })
+        .unwrap();
+    let result = client.execute_procedure(Hmac {
+        hash_type: Sha2Hash::Sha256,
+        msg: b"msg".to_vec(),
+        key: seed,
+    });
+    assert!(matches!(result, Err(ProcedureError::Policy(_))));
+    client
+        .execute_procedure(Hmac {
+            hash_type: Sha2Hash::Sha256,
+            msg: b"msg".to_vec(),
+            key: derived,
+        })
+        .unwrap();
+
+    // a derive-only key can't be copied by a derivation along an empty chain or by a concatenation.
+    let seed = fresh::location();
+    let parent = fresh::location();
+    client
+        .execute_procedure(Slip10Generate {
+            output: seed.clone(),
+            size_bytes: None,
+        })
+        .unwrap();
+    client
+        .execute_procedure(Slip10Derive {
+            chain: Chain::from_u32_hardened([0]),
+            input: Slip10DeriveInput::Seed(seed),
+            output: parent.clone(),
+        })
+        .unwrap();
+    client
+        .set_record_policy(&parent, RecordPolicy::new(KeyUsage::DERIVE))
+        .unwrap();
+    let empty = fresh::location();
+    client
+        .execute_procedure(WriteVault {
+            data: Vec::new(),
+            location: empty.clone(),
+        })
+        .unwrap();
+    let copy = fresh::location();
+    let is_export_violation = |result: Result<(), ProcedureError>| {
+        matches!(
+            result,
+            Err(ProcedureError::Policy(PolicyError::UsageNotAllowed(usage))) if usage == KeyUsage::EXPORT
+        )
+    };
+    assert!(is_export_violation(
+        client
+            .execute_procedure(Slip10Derive {
+                chain: Chain::empty(),
+                input: Slip10DeriveInput::Key(parent.clone()),
+                output: copy.clone(),
+            })
+            .map(drop)
+    ));
+    assert!(is_export_violation(
+        client
+            .execute_procedure(Bip32Derive {
+                chain: Chain::empty(),
+                input: Slip10DeriveInput::Key(parent.clone()),
+                output: copy.clone(),
+            })
+            .map(drop)
+    ));
+    assert!(is_export_violation(client.execute_procedure(ConcatSecret {
+        location_a: parent.clone(),
+        location_b: empty,
+        output_location: copy.clone(),
+    })));
+    assert!(!client.record_exists(&copy).unwrap());
+    client
+        .execute_procedure(Slip10Derive {
+            chain: Chain::from_u32_hardened([1]),
+            input: Slip10DeriveInput::Key(parent),
+            output: fresh::location(),
+        })
+        .unwrap();
+
+    // uses are counted and exhausted.
+    let limited = fresh::location();
+    client
+        .vault(limited.vault_path())
+        .write_secret(limited.clone(), random::fixed_bytestring(16))
+        .unwrap();
+    client
+        .set_record_policy(&limited, RecordPolicy::default().with_max_uses(2))
+        .unwrap();
+    // failed operations don't count as use.
+    assert!(client
+        .execute_procedure(PublicKey {
+            ty: KeyType::Ed25519,
+            private_key: limited.clone(),
+        })
+        .is_err());
+    assert!(matches!(
+        client.aead_stream(&limited),
+        Err(ClientError::IllegalKeySize(_))
+    ));
+    assert_eq!(client.record_metadata(&limited).unwrap().unwrap().uses, 0);
+    let hmac = Hmac {
+        hash_type: Sha2Hash::Sha256,
+        msg: b"msg".to_vec(),
+        key: limited.clone(),
+    };
+    client.execute_procedure(hmac.clone()).unwrap();
+    client.execute_procedure(hmac.clone()).unwrap();
+    assert_eq!(client.record_metadata(&limited).unwrap().unwrap().uses, 2);
+    assert!(matches!(
+        client.execute_procedure(hmac.clone()),
+        Err(ProcedureError::Policy(PolicyError::UsesExhausted(2)))
+    ));
+    client.set_record_policy(&limited, RecordPolicy::default()).unwrap();
+    client.execute_procedure(hmac).unwrap();
+
+    // expired records can't be used.
+    let expired = fresh::location();
+    client
+        .execute_procedure_with_policy(
+            GenerateKey {
+                ty: KeyType::X25519,
+                output: expired.clone(),
+            },
+            RecordPolicy::default().with_expiry(SystemTime::now()),
+        )
+        .unwrap();
+    assert!(matches!(
+        client.execute_procedure(PublicKey {
+            ty: KeyType::X25519,
+            private_key: expired.clone(),
+        }),
+        Err(ProcedureError::Policy(PolicyError::Expired))
+    ));
+    assert!(matches!(
+        client.aead_stream(&expired),
+        Err(ClientError::Procedure(ProcedureError::Policy(PolicyError::Expired)))
+    ));
+}
+
+#[test]
+fn usecase_shamir_split_combine_bip39_seed() {
+    let stronghold = Stronghold::default();
+    let client = stronghold.create_client(b"client_path").unwrap();
+
+    let seed = fresh::location();
+    client
+        .execute_procedure(BIP39Generate {
+            language: MnemonicLanguage::English,
+            passphrase: None,
+            output: seed.clone(),
+        })
+        .unwrap();
+
+    let encoding = ShareEncoding::Slip39 {
+        passphrase: b"backup".to_vec(),
+        iteration_exponent: 0,
+    };
+    let shares: Vec<Location> = (0..5).map(|_| fresh::location()).collect();
+    let encrypted = client
+        .execute_procedure(ShamirSplit {
+            secret: seed.clone(),
+            threshold: 3,
+            outputs: shares.clone(),
+            recipients: Vec::new(),
+            encoding: encoding.clone(),
+        })
+        .unwrap();
+    assert!(encrypted.0.is_empty());
+    assert!(shares.iter().all(|share| client.record_exists(share).unwrap()));
+
+    let (_path, chain) = fresh::hd_path();
+    let public_key = |seed: &Location| {
+        let key = fresh::location();
+        client
+            .execute_procedure(Slip10Derive {
+                chain: chain.clone(),
+                input: Slip10DeriveInput::Seed(seed.clone()),
+                output: key.clone(),
+            })
+            .unwrap();
+        client
+            .execute_procedure(PublicKey {
+                ty: KeyType::Ed25519,
+                private_key: key,
+            })
+            .unwrap()
+    };
+
+    let recovered = fresh::location();
+    client
+        .execute_procedure(ShamirCombine {
+            shares: vec![shares[4].clone(), shares[1].clone(), shares[2].clone()],
+            encoding: encoding.clone(),
+            output: recovered.clone(),
+        })
+        .unwrap();
+    assert_eq!(public_key(&seed), public_key(&recovered));
+
+    // too few shares
+    let result = client.execute_procedure(ShamirCombine {
+        shares: vec![shares[0].clone(), shares[3].clone()],
+        encoding,
+        output: fresh::location(),
+    });
+    assert!(matches!(result, Err(ProcedureError::Procedure(_))));
+}
+
+#[test]
+fn usecase_shamir_split_to_recipients() {
+    let stronghold = Stronghold::default();
+    let client = stronghold.create_client(b"client_path").unwrap();
+
+    let secret = fresh::location();
+    client
+        .execute_procedure(WriteVault {
+            data: random::fixed_bytestring(32),
+            location: secret.clone(),
+        })
+        .unwrap();
+
+    let recipients: Vec<x25519::SecretKey> = (0..3).map(|_| x25519::SecretKey::generate().unwrap()).collect();
+    let shares: Vec<Location> = (0..3).map(|_| fresh::location()).collect();
+    let encrypted = client
+        .execute_procedure(ShamirSplit {
+            secret: secret.clone(),
+            threshold: 2,
+            outputs: shares.clone(),
+            recipients: recipients.iter().map(|sk| sk.public_key().to_bytes()).collect(),
+            encoding: ShareEncoding::Raw,
+        })
+        .unwrap();
+    assert_eq!(encrypted.0.len(), 3);
+
+    // each recipient can decrypt its own share only
+    for (i, (sk, encrypted)) in recipients.iter().zip(&encrypted.0).enumerate() {
+        let share = decrypt_share(sk, encrypted).unwrap();
+        assert_eq!(&share[..2], &[2, i as u8]);
+    }
+    assert!(decrypt_share(&recipients[0], &encrypted.0[1]).is_err());
+
+    let recovered = fresh::location();
+    client
+        .execute_procedure(ShamirCombine {
+            shares: vec![shares[2].clone(), shares[0].clone()],
+            encoding: ShareEncoding::Raw,
+            output: recovered.clone(),
+        })
+        .unwrap();
+
+    let hmac = |key: &Location| {
+        client
+            .execute_procedure(Hmac {
+                hash_type: Sha2Hash::Sha256,
+                msg: b"message".to_vec(),
+                key: key.clone(),
+            })
+            .unwrap()
+    };
+    assert_eq!(hmac(&secret), hmac(&recovered));
+}
+
+#[test]
+fn usecase_export_import_record() {
+    let x25519_key = |client: &Client| {
+        let location = fresh::location();
+        client
+            .execute_procedure(GenerateKey {
+                ty: KeyType::X25519,
+                output: location.clone(),
+            })
+            .unwrap();
+        let public_key: [u8; 32] = client
+            .execute_procedure(PublicKey {
+                ty: KeyType::X25519,
+                private_key: location.clone(),
+            })
+            .unwrap()
+            .try_into()
+            .unwrap();
+        (location, public_key)
+    };
+    let ed25519_public_key = |client: &Client, private_key: &Location| {
+        client
+            .execute_procedure(PublicKey {
+                ty: KeyType::Ed25519,
+                private_key: private_key.clone(),
+            })
+            .unwrap()
+    };
+
+    let sender = Client::default();
+    let recipient = Client::default();
+    let (sender_key, sender_public_key) = x25519_key(&sender);
+    let (recipient_key, recipient_public_key) = x25519_key(&recipient);
+    let (other_key, other_public_key) = x25519_key(&sender);
+
+    let record = fresh::location();
+    sender
+        .execute_procedure_with_policy(
+            GenerateKey {
+                ty: KeyType::Ed25519,
+                output: record.clone(),
+            },
+            RecordPolicy::new(KeyUsage::SIGN | KeyUsage::EXPORT),
+        )
+        .unwrap();
+    sender.set_record_label(&record, Some("signing key".into())).unwrap();
+
+    let envelope = sender
+        .execute_procedure(ExportRecord {
+            record: record.clone(),
+            sender: sender_key.clone(),
+            recipient: recipient_public_key,
+        })
+        .unwrap();
+    assert_eq!(&envelope[..4], &RECORD_ENVELOPE_MAGIC);
+    assert_eq!(envelope[4], RECORD_ENVELOPE_VERSION);
+
+    // the envelope has to be authenticated by the expected sender
+    let imported = fresh::location();
+    let result = recipient.execute_procedure(ImportRecord {
+        envelope: envelope.clone(),
+        recipient: recipient_key.clone(),
+        sender: other_public_key,
+        output: imported.clone(),
+    });
+    assert!(matches!(result, Err(ProcedureError::Procedure(_))));
+    let mut tampered = envelope.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    let result = recipient.execute_procedure(ImportRecord {
+        envelope: tampered,
+        recipient: recipient_key.clone(),
+        sender: sender_public_key,
+        output: imported.clone(),
+    });
+    assert!(matches!(result, Err(ProcedureError::Procedure(_))));
+    assert!(!recipient.record_exists(&imported).unwrap());
+
+    // only the recipient can open the envelope
+    let result = sender.execute_procedure(ImportRecord {
+        envelope: envelope.clone(),
+        recipient: other_key,
+        sender: sender_public_key,
+        output: imported.clone(),
+    });
+    assert!(matches!(result, Err(ProcedureError::Procedure(_))));
+
+    recipient
+        .execute_procedure(ImportRecord {
+            envelope,
+            recipient: recipient_key,
+            sender: sender_public_key,
+            output: imported.clone(),
+        })
+        .unwrap();
+    assert_eq!(
+        ed25519_public_key(&sender, &record),
+        ed25519_public_key(&recipient, &imported)
+    );
+
+    let exported_metadata = sender.record_metadata(&record).unwrap().unwrap();
+    let imported_metadata = recipient.record_metadata(&imported).unwrap().unwrap();
+    assert_eq!(imported_metadata.label.as_deref(), Some("signing key"));
+    assert_eq!(imported_metadata.key_type, Some(KeyType::Ed25519));
+    assert_eq!(imported_metadata.created, exported_metadata.created);
+    assert_eq!(imported_metadata.policy, exported_metadata.policy);
+
+    let hints = |client: &Client, location: &Location| {
+        client
+            .vault(location.vault_path())
+            .list_records()
+            .unwrap()
+            .into_iter()
+            .map(|(_, hint)| hint)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(hints(&sender, &record), hints(&recipient, &imported));
+
+    // records that don't permit the export can't be exported
+    let sign_only = fresh::location();
+    sender
+        .execute_procedure_with_policy(
+            GenerateKey {
+                ty: KeyType::Ed25519,
+                output: sign_only.clone(),
+            },
+            RecordPolicy::new(KeyUsage::SIGN),
+        )
+        .unwrap();
+    let result = sender.execute_procedure(ExportRecord {
+        record: sign_only,
+        sender: sender_key,
+        recipient: recipient_public_key,
+    });
+    assert!(matches!(
+        result,
+        Err(ProcedureError::Policy(PolicyError::UsageNotAllowed(usage))) if usage == KeyUsage::EXPORT
+    ));
+}
+
+#[test]
+fn usecase_hpke_seal_open() {
+    let x25519_key = |client: &Client, usage: KeyUsage| {
+        let location = fresh::location();
+        client
+            .execute_procedure_with_policy(
+                GenerateKey {
+                    ty: KeyType::X25519,
+                    output: location.clone(),
+                },
+                RecordPolicy::new(usage),
+            )
+            .unwrap();
+        let public_key: [u8; 32] = client
+            .execute_procedure(PublicKey {
+                ty: KeyType::X25519,
+                private_key: location.clone(),
+            })
+            .unwrap()
+            .try_into()
+            .unwrap();
+        (location, public_key)
+    };
+
+    let sender = Client::default();
+    let recipient = Client::default();
+    let (sender_key, sender_public_key) = x25519_key(&sender, KeyUsage::DERIVE);
+    let (recipient_key, recipient_public_key) = x25519_key(&recipient, KeyUsage::DERIVE);
+    let (_, other_public_key) = x25519_key(&sender, KeyUsage::DERIVE);
+
+    let plaintext = fresh::variable_bytestring(1024);
+    for aead in [HpkeAead::Aes128Gcm, HpkeAead::Aes256Gcm, HpkeAead::ChaCha20Poly1305] {
+        for (sender_location, sender_public_key) in [(None, None), (Some(sender_key.clone()), Some(sender_public_key))]
+        {
+            let open = |sealed: &[u8], sender: Option<[u8; 32]>, aad: &[u8]| {
+                let (enc, ciphertext) = sealed.split_at(HPKE_ENC_LENGTH);
+                recipient.execute_procedure(HpkeOpen {
+                    aead,
+                    recipient: recipient_key.clone(),
+                    sender,
+                    enc: enc.try_into().unwrap(),
+                    info: b"info".to_vec(),
+                    aad: aad.to_vec(),
+                    ciphertext: ciphertext.to_vec(),
+                })
+            };
+
+            let sealed = sender
+                .execute_procedure(HpkeSeal {
+                    aead,
+                    recipient: recipient_public_key,
+                    sender: sender_location,
+                    info: b"info".to_vec(),
+                    aad: b"aad".to_vec(),
+                    plaintext: plaintext.clone(),
+                })
+                .unwrap();
+            assert_eq!(sealed.len(), HPKE_ENC_LENGTH + plaintext.len() + 16);
+            assert_eq!(open(&sealed, sender_public_key, b"aad").unwrap(), plaintext);
+
+            // the aad and the sender have to match, and base mode ciphertexts fail in the auth mode
+            assert!(matches!(
+                open(&sealed, sender_public_key, b"other"),
+                Err(ProcedureError::Procedure(_))
+            ));
+            assert!(matches!(
+                open(&sealed, Some(other_public_key), b"aad"),
+                Err(ProcedureError::Procedure(_))
+            ));
+        }
+    }
+
+    // keys that don't permit the key derivation can't be used
+    let (sign_only, sign_only_public_key) = x25519_key(&recipient, KeyUsage::SIGN);
+    let sealed = sender
+        .execute_procedure(HpkeSeal {
+            aead: HpkeAead::ChaCha20Poly1305,
+            recipient: sign_only_public_key,
+            sender: None,
+            info: vec![],
+            aad: vec![],
+            plaintext,
+        })
+        .unwrap();
+    let (enc, ciphertext) = sealed.split_at(HPKE_ENC_LENGTH);
+    let result = recipient.execute_procedure(HpkeOpen {
+        aead: HpkeAead::ChaCha20Poly1305,
+        recipient: sign_only,
+        sender: None,
+        enc: enc.try_into().unwrap(),
+        info: vec![],
+        aad: vec![],
+        ciphertext: ciphertext.to_vec(),
+    });
+    assert!(matches!(
+        result,
+        Err(ProcedureError::Policy(PolicyError::UsageNotAllowed(usage))) if usage == KeyUsage::DERIVE
+    ));
+}
@@ -0,0 +1,136 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::io::{ErrorKind, Read, Write};
+
+use crate::{
+    procedures::{GenerateKey, KeyType},
+    tests::fresh,
+    Client, ClientError, Location,
+};
+use stronghold_utils::random as rand;
+
+const CHUNK_SIZE: usize = 32;
+const HEADER_LENGTH: usize = 23;
+const TAG_LENGTH: usize = 16;
+
+fn client_with_key() -> (Client, Location) {
+    let client = Client::default();
+    let key = fresh::location();
+    client
+        .execute_procedure(GenerateKey {
+            ty: KeyType::X25519,
+            output: key.clone(),
+        })
+        .unwrap();
+    (client, key)
+}
+
+fn encrypt(client: &Client, key: &Location, plaintext: &[u8]) -> Vec<u8> {
+    let stream = client.aead_stream(key).unwrap().with_chunk_size(CHUNK_SIZE).unwrap();
+    let mut encryptor = stream.encrypt(Vec::new()).unwrap();
+    // write in uneven pieces to cover partially filled chunks
+    for piece in plaintext.chunks(7) {
+        encryptor.write_all(piece).unwrap();
+    }
+    encryptor.finish().unwrap()
+}
+
+fn decrypt(client: &Client, key: &Location, ciphertext: &[u8]) -> std::io::Result<Vec<u8>> {
+    let stream = client.aead_stream(key).unwrap();
+    let mut decryptor = stream.decrypt(ciphertext)?;
+    let mut plaintext = Vec::new();
+    decryptor.read_to_end(&mut plaintext)?;
+    Ok(plaintext)
+}
+
+#[test]
+fn test_aead_stream_roundtrip() {
+    let (client, key) = client_with_key();
+
+    for len in [0, 1, CHUNK_SIZE - 1, CHUNK_SIZE, 3 * CHUNK_SIZE, 3 * CHUNK_SIZE + 5] {
+        let plaintext = rand::fixed_bytestring(len);
+        let ciphertext = encrypt(&client, &key, &plaintext);
+        let chunks = len.div_ceil(CHUNK_SIZE);
+        assert_eq!(
+            ciphertext.len(),
+            HEADER_LENGTH + len + chunks.max(1) * TAG_LENGTH,
+            "unexpected ciphertext length for {} bytes",
+            len
+        );
+        assert_eq!(decrypt(&client, &key, &ciphertext).unwrap(), plaintext);
+    }
+}
+
+#[test]
+fn test_aead_stream_detects_manipulation() {
+    let (client, key) = client_with_key();
+    let plaintext = rand::fixed_bytestring(3 * CHUNK_SIZE + 5);
+    let ciphertext = encrypt(&client, &key, &plaintext);
+    let chunk_length = CHUNK_SIZE + TAG_LENGTH;
+
+    // flipped bit in the second chunk
+    let mut manipulated = ciphertext.clone();
+    manipulated[HEADER_LENGTH + chunk_length + 3] ^= 1;
+    let err = decrypt(&client, &key, &manipulated).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    // truncated at a chunk boundary
+    let truncated = &ciphertext[..HEADER_LENGTH + 3 * chunk_length];
+    let err = decrypt(&client, &key, truncated).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    // swapped chunks
+    let mut swapped = ciphertext[..HEADER_LENGTH].to_vec();
+    swapped.extend_from_slice(&ciphertext[HEADER_LENGTH + chunk_length..HEADER_LENGTH + 2 * chunk_length]);
+    swapped.extend_from_slice(&ciphertext[HEADER_LENGTH..HEADER_LENGTH + chunk_length]);
+    swapped.extend_from_slice(&ciphertext[HEADER_LENGTH + 2 * chunk_length..]);
+    let err = decrypt(&client, &key, &swapped).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    // manipulated header
+    let mut manipulated = ciphertext;
+    manipulated[0] ^= 1;
+    let err = decrypt(&client, &key, &manipulated).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_aead_stream_requires_finish() {
+    let (client, key) = client_with_key();
+    let stream = client.aead_stream(&key).unwrap();
+
+    let mut ciphertext = Vec::new();
+    let mut encryptor = stream.encrypt(&mut ciphertext).unwrap();
+    encryptor.write_all(b"not finished").unwrap();
+    drop(encryptor);
+
+    let err = decrypt(&client, &key, &ciphertext).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_aead_stream_wrong_key() {
+    let (client, key) = client_with_key();
+    let other_key = fresh::location();
+    client
+        .execute_procedure(GenerateKey {
+            ty: KeyType::X25519,
+            output: other_key.clone(),
+        })
+        .unwrap();
+
+    let ciphertext = encrypt(&client, &key, b"some secret backup");
+    let err = decrypt(&client, &other_key, &ciphertext).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    let short_key = fresh::location();
+    client
+        .vault(short_key.vault_path())
+        .write_secret(short_key.clone(), vec![0; 16])
+        .unwrap();
+    assert!(matches!(
+        client.aead_stream(&short_key),
+        Err(ClientError::IllegalKeySize(32))
+    ));
+}
@@ -4,19 +4,29 @@
 //! A collection of relevant interface types to interact with a Stronghold
 
 // modules
+#[cfg(feature = "async")]
+mod asynchronous;
 mod client;
 mod error;
 mod location;
+mod metadata;
+mod restricted;
 mod snapshot;
 mod store;
+mod stream;
 mod stronghold;
 mod vault;
 
 // re-export imports
+#[cfg(feature = "async")]
+pub use asynchronous::*;
 pub use client::*;
 pub use error::*;
 pub use location::*;
+pub use metadata::*;
+pub use restricted::*;
 pub use snapshot::*;
 pub use store::*;
+pub use stream::*;
 pub use stronghold::*;
 pub use vault::*;
@@ -0,0 +1,391 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Async facades for [`Stronghold`] and [`Client`].
+//!
+//! CPU-heavy and blocking operations, like snapshot commits and loading, key derivation and the execution
+//! of procedures, are offloaded to the blocking thread pool of the tokio runtime. Operations that replace
+//! the state of a [`Stronghold`] or write records of a [`Client`] are serialized by an async-aware lock, so
+//! that waiting tasks yield to the executor instead of blocking one of its threads. The lock is stored with
+//! the wrapped [`Stronghold`] or [`Client`] and shared by all facades of it. Procedures that don't write a
+//! record, e.g. signing, run concurrently.
+
+use std::sync::Arc;
+
+use engine::vault::{ClientId, RecordHint, RecordId, VaultId};
+use zeroize::Zeroize;
+
+use crate::{
+    procedures::{Procedure, ProcedureError, ProcedureOutput, StrongholdProcedure},
+    Client, ClientError, KeyProvider, Location, RecordMetadata, SnapshotPath, SnapshotSource, Store, Stronghold,
+};
+
+/// Runs `f` on the blocking thread pool and waits for its result. Fails, if the task panicked or was cancelled.
+async fn spawn_blocking<F, R>(f: F) -> Result<R, String>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| format!("blocking task failed: {}", e))
+}
+
+/// Async facade for [`Stronghold`].
+///
+/// # Example
+#[derive(Clone, Default)]
+pub struct AsyncStronghold {
+    inner: Stronghold,
+}
+
+impl From<Stronghold> for AsyncStronghold {
+    fn from(inner: Stronghold) -> Self {
+        AsyncStronghold { inner }
+    }
+}
+
+impl AsyncStronghold {
+    /// Returns the wrapped [`Stronghold`].
+    pub fn inner(&self) -> &Stronghold {
+        &self.inner
+    }
+
+    /// Returns an atomic reference to the [`Store`]
+    pub fn store(&self) -> Store {
+        self.inner.store()
+    }
+
+    /// Sets the number of backup generations that are kept on commit, see [`Stronghold::set_snapshot_backups`].
+    pub fn set_snapshot_backups(&self, backups: usize) {
+        self.inner.set_snapshot_backups(backups)
+    }
+
+    /// Creates a new, empty [`AsyncClient`], see [`Stronghold::create_client`].
+    ///
+    /// # Example
+    pub async fn create_client<P>(&self, client_path: P) -> Result<AsyncClient, ClientError>
+    where
+        P: AsRef<[u8]>,
+    {
+        let guard = self.inner.async_lock.clone().read_owned().await;
+        let inner = self.inner.clone();
+        let client_path = client_path.as_ref().to_vec();
+        spawn_blocking(move || {
+            let _guard = guard;
+            inner.create_client(client_path).map(AsyncClient::from)
+        })
+        .await
+        .map_err(ClientError::Inner)?
+    }
+
+    /// Returns an in session client, see [`Stronghold::get_client`].
+    ///
+    /// # Example
+    pub async fn get_client<P>(&self, client_path: P) -> Result<AsyncClient, ClientError>
+    where
+        P: AsRef<[u8]>,
+    {
+        let _guard = self.inner.async_lock.read().await;
+        self.inner.get_client(client_path).map(AsyncClient::from)
+    }
+
+    /// Loads a client from the loaded [`crate::Snapshot`] data, see [`Stronghold::load_client`].
+    ///
+    /// # Example
+    pub async fn load_client<P>(&self, client_path: P) -> Result<AsyncClient, ClientError>
+    where
+        P: AsRef<[u8]> + Send + 'static,
+    {
+        let guard = self.inner.async_lock.clone().write_owned().await;
+        let inner = self.inner.clone();
+        spawn_blocking(move || {
+            let _guard = guard;
+            inner.load_client(client_path).map(AsyncClient::from)
+        })
+        .await
+        .map_err(ClientError::Inner)?
+    }
+
+    /// Loads a client from the snapshot file at `snapshot_path`, see [`Stronghold::load_client_from_snapshot`].
+    ///
+    /// # Example
+    pub async fn load_client_from_snapshot<P>(
+        &self,
+        client_path: P,
+        keyprovider: Arc<KeyProvider>,
+        snapshot_path: &SnapshotPath,
+    ) -> Result<AsyncClient, ClientError>
+    where
+        P: AsRef<[u8]> + Send + 'static,
+    {
+        let guard = self.inner.async_lock.clone().write_owned().await;
+        let inner = self.inner.clone();
+        let snapshot_path = snapshot_path.clone();
+        spawn_blocking(move || {
+            let _guard = guard;
+            inner
+                .load_client_from_snapshot(client_path, &keyprovider, &snapshot_path)
+                .map(AsyncClient::from)
+        })
+        .await
+        .map_err(ClientError::Inner)?
+    }
+
+    /// Unloads the client from the clients currently managed by the [`Stronghold`] instance, see
+    /// [`Stronghold::unload_client`].
+    ///
+    /// # Example
+    pub async fn unload_client(&self, client: AsyncClient) -> Result<AsyncClient, ClientError> {
+        let guard = self.inner.async_lock.clone().write_owned().await;
+        let inner = self.inner.clone();
+        spawn_blocking(move || {
+            let _guard = guard;
+            inner.unload_client(client.inner).map(AsyncClient::from)
+        })
+        .await
+        .map_err(ClientError::Inner)?
+    }
+
+    /// Loads the state of a [`crate::Snapshot`] at given `snapshot_path`, see [`Stronghold::load_snapshot`].
+    ///
+    /// # Example
+    pub async fn load_snapshot(
+        &self,
+        keyprovider: Arc<KeyProvider>,
+        snapshot_path: &SnapshotPath,
+    ) -> Result<SnapshotSource, ClientError> {
+        let guard = self.inner.async_lock.clone().write_owned().await;
+        let inner = self.inner.clone();
+        let snapshot_path = snapshot_path.clone();
+        spawn_blocking(move || {
+            let _guard = guard;
+            inner.load_snapshot(&keyprovider, &snapshot_path)
+        })
+        .await
+        .map_err(ClientError::Inner)?
+    }
+
+    /// Writes all client states into the snapshot file, see [`Stronghold::commit_with_keyprovider`].
+    ///
+    /// # Example
+    pub async fn commit_with_keyprovider(
+        &self,
+        snapshot_path: &SnapshotPath,
+        keyprovider: Arc<KeyProvider>,
+    ) -> Result<(), ClientError> {
+        let guard = self.inner.async_lock.clone().write_owned().await;
+        let inner = self.inner.clone();
+        let snapshot_path = snapshot_path.clone();
+        spawn_blocking(move || {
+            let _guard = guard;
+            inner.commit_with_keyprovider(&snapshot_path, &keyprovider)
+        })
+        .await
+        .map_err(ClientError::Inner)?
+    }
+
+    /// Writes all client states into the snapshot file with the stored snapshot key, see
+    /// [`Stronghold::commit`].
+    ///
+    /// # Example
+    pub async fn commit(&self, snapshot_path: &SnapshotPath) -> Result<(), ClientError> {
+        let guard = self.inner.async_lock.clone().write_owned().await;
+        let inner = self.inner.clone();
+        let snapshot_path = snapshot_path.clone();
+        spawn_blocking(move || {
+            let _guard = guard;
+            inner.commit(&snapshot_path)
+        })
+        .await
+        .map_err(ClientError::Inner)?
+    }
+
+    /// Clears the runtime state of all clients and the in-memory snapshot state, see [`Stronghold::clear`].
+    ///
+    /// # Example
+    pub async fn clear(&self) -> Result<(), ClientError> {
+        let guard = self.inner.async_lock.clone().write_owned().await;
+        let inner = self.inner.clone();
+        spawn_blocking(move || {
+            let _guard = guard;
+            inner.clear()
+        })
+        .await
+        .map_err(ClientError::Inner)?
+    }
+}
+
+/// Async facade for [`Client`].
+///
+/// # Example
+#[derive(Clone, Default)]
+pub struct AsyncClient {
+    inner: Client,
+}
+
+impl From<Client> for AsyncClient {
+    fn from(inner: Client) -> Self {
+        AsyncClient { inner }
+    }
+}
+
+impl AsyncClient {
+    /// Returns the wrapped [`Client`].
+    pub fn inner(&self) -> &Client {
+        &self.inner
+    }
+
+    /// Returns the [`ClientId`] of the client
+    pub fn id(&self) -> &ClientId {
+        self.inner.id()
+    }
+
+    /// Returns an atomic reference to the [`Store`]
+    pub fn store(&self) -> Store {
+        self.inner.store()
+    }
+
+    /// Returns `true`, if a vault exists
+    ///
+    /// # Example
+    pub async fn vault_exists<P>(&self, vault_path: P) -> Result<bool, ClientError>
+    where
+        P: AsRef<[u8]>,
+    {
+        let _guard = self.inner.async_lock.read().await;
+        self.inner.vault_exists(vault_path)
+    }
+
+    /// Returns `true`, if the record exists
+    ///
+    /// # Example
+    pub async fn record_exists(&self, location: &Location) -> Result<bool, ClientError> {
+        let _guard = self.inner.async_lock.read().await;
+        self.inner.record_exists(location)
+    }
+
+    /// Returns the [`RecordMetadata`] of the record at `location`, see [`Client::record_metadata`].
+    ///
+    /// # Example
+    pub async fn record_metadata(&self, location: &Location) -> Result<Option<RecordMetadata>, ClientError> {
+        let _guard = self.inner.async_lock.read().await;
+        self.inner.record_metadata(location)
+    }
+
+    /// Returns the ids of all vaults of the client.
+    ///
+    /// # Example
+    pub async fn list_vaults(&self) -> Result<Vec<VaultId>, ClientError> {
+        let _guard = self.inner.async_lock.read().await;
+        self.inner.list_vaults()
+    }
+
+    /// Returns the ids and hints of all records in the vault at `vault_path`, see
+    /// [`crate::ClientVault::list_records`].
+    ///
+    /// # Example
+    pub async fn list_records<P>(&self, vault_path: P) -> Result<Vec<(RecordId, RecordHint)>, ClientError>
+    where
+        P: AsRef<[u8]>,
+    {
+        let _guard = self.inner.async_lock.read().await;
+        self.inner.vault(vault_path).list_records()
+    }
+
+    /// Writes `payload` into the record at `location`.
+    ///
+    /// # Example
+    pub async fn write_secret(&self, location: Location, payload: Vec<u8>) -> Result<(), ClientError> {
+        self.run_locked(true, move |client| {
+            client.vault(location.vault_path()).write_secret(location, payload)
+        })
+        .await
+        .map_err(ClientError::Inner)?
+    }
+
+    /// Executes a cryptographic [`Procedure`] on the blocking thread pool and returns its output. Procedures
+    /// that write a record are serialized, other procedures of the client run concurrently.
+    ///
+    /// # Example
+    pub async fn execute_procedure<P>(&self, procedure: P) -> Result<P::Output, ProcedureError>
+    where
+        P: Procedure + Into<StrongholdProcedure> + Send + 'static,
+        P::Output: Send + 'static,
+    {
+        let procedure: StrongholdProcedure = procedure.into();
+        let output = self.execute_procedure_chained(vec![procedure]).await?.pop().unwrap();
+        Ok(output.try_into().ok().unwrap())
+    }
+
+    /// Executes a list of cryptographic procedures sequentially on the blocking thread pool, see
+    /// [`Client::execute_procedure_chained`].
+    ///
+    /// # Example
+    pub async fn execute_procedure_chained(
+        &self,
+        procedures: Vec<StrongholdProcedure>,
+    ) -> Result<Vec<ProcedureOutput>, ProcedureError> {
+        let writes = procedures.iter().any(|procedure| procedure.output().is_some());
+        self.run_locked(writes, move |client| client.execute_procedure_chained(procedures))
+            .await
+            .map_err(|e| ProcedureError::Engine(e.into()))?
+    }
+
+    /// Runs `f` on the blocking thread pool while holding the lock of the client, exclusively if `exclusive` is
+    /// set.
+    async fn run_locked<F, R>(&self, exclusive: bool, f: F) -> Result<R, String>
+    where
+        F: FnOnce(&Client) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let inner = self.inner.clone();
+        if exclusive {
+            let guard = self.inner.async_lock.clone().write_owned().await;
+            spawn_blocking(move || {
+                let _guard = guard;
+                f(&inner)
+            })
+            .await
+        } else {
+            let guard = self.inner.async_lock.clone().read_owned().await;
+            spawn_blocking(move || {
+                let _guard = guard;
+                f(&inner)
+            })
+            .await
+        }
+    }
+}
+
+impl KeyProvider {
+    /// Creates a new [`KeyProvider`] from a passphrase hashed with `argon2` on the blocking thread pool, see
+    /// [`KeyProvider::with_passphrase_hashed_argon2`].
+    ///
+    /// # Example
+    pub async fn with_passphrase_hashed_argon2_async<P>(passphrase: P, salt: P) -> Result<Self, ClientError>
+    where
+        P: AsRef<[u8]> + Zeroize + Send + 'static,
+    {
+        spawn_blocking(move || Self::with_passphrase_hashed_argon2(passphrase, salt))
+            .await
+            .map_err(ClientError::Inner)?
+    }
+
+    /// Creates a new [`KeyProvider`] for the snapshot at `snapshot_path` from a passphrase on the blocking thread
+    /// pool, see [`KeyProvider::with_passphrase_for_snapshot`].
+    ///
+    /// # Example
+    pub async fn with_passphrase_for_snapshot_async<P>(
+        passphrase: P,
+        snapshot_path: &SnapshotPath,
+    ) -> Result<Self, ClientError>
+    where
+        P: AsRef<[u8]> + Zeroize + Send + 'static,
+    {
+        let snapshot_path = snapshot_path.clone();
+        spawn_blocking(move || Self::with_passphrase_for_snapshot(passphrase, &snapshot_path))
+            .await
+            .map_err(ClientError::Inner)?
+    }
+}
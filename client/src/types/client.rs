@@ -5,10 +5,12 @@ use super::{location, snapshot};
 use crate::{
     derive_vault_id,
     procedures::{
-        FatalProcedureError, Procedure, ProcedureError, ProcedureOutput, Products, Runner, StrongholdProcedure,
+        FatalProcedureError, PolicyRunner, Procedure, ProcedureError, ProcedureOutput, Products, Runner,
+        StrongholdProcedure,
     },
     sync::{KeyProvider, MergePolicy, SyncClients, SyncClientsConfig, SyncSnapshots, SyncSnapshotsConfig},
-    ClientError, ClientState, ClientVault, KeyStore, Location, Provider, RecordError, SnapshotError, Store, Stronghold,
+    ClientError, ClientState, ClientVault, KeyStore, Location, Provider, RecordError, RecordMetadata, RecordPolicy,
+    SnapshotError, Store, Stronghold, VaultError,
 };
 use crypto::keys::x25519;
 use engine::{
@@ -17,8 +19,12 @@ use engine::{
 };
 use std::{
     collections::HashMap,
+    convert::Infallible,
     error::Error,
-    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
     time::Duration,
 };
 use stronghold_utils::GuardDebug;
@@ -37,6 +43,13 @@ pub struct Client {
 
     // Contains the Record Ids for the most recent Record in each vault.
     pub store: Store,
+
+    // Whether record paths are stored in the metadata of written records
+    pub(crate) record_path_index: Arc<AtomicBool>,
+
+    // Serializes procedures of the `AsyncClient`s of this client that write records
+    #[cfg(feature = "async")]
+    pub(crate) async_lock: Arc<tokio::sync::RwLock<()>>,
 }
 
 impl Default for Client {
@@ -46,6 +59,9 @@ impl Default for Client {
             db: Arc::new(RwLock::new(DbView::new())),
             id: ClientId::default(),
             store: Store::default(),
+            record_path_index: Arc::new(AtomicBool::new(false)),
+            #[cfg(feature = "async")]
+            async_lock: Arc::default(),
         }
     }
 }
@@ -84,6 +100,23 @@ impl Client {
         Ok(keystore.vault_exists(vault_id))
     }
 
+    /// Returns the ids of all vaults of the client.
+    ///
+    /// # Example
+    pub fn list_vaults(&self) -> Result<Vec<VaultId>, ClientError> {
+        let db = self.db.read()?;
+        Ok(db.list_vaults())
+    }
+
+    /// Enables or disables the record path index. While enabled, the record path of each record written to a
+    /// [`Location::Generic`] is stored in the [`RecordMetadata`] of the record, so that it can be recovered with
+    /// [`ClientVault::record_path`]. Records written while the index is disabled keep their previously indexed path.
+    ///
+    /// # Example
+    pub fn set_record_path_index(&self, enabled: bool) {
+        self.record_path_index.store(enabled, Ordering::Relaxed);
+    }
+
     /// Returns Ok(true), if the record exists. Ok(false), if not. An error is being
     /// returned, if inner database could not be unlocked.
     ///
@@ -95,6 +128,84 @@ impl Client {
         Ok(contains_record)
     }
 
+    /// Returns the [`RecordMetadata`] of the record at `location`. The secret stored in the record is
+    /// not decrypted for this. Returns `Ok(None)` for records that have been written before record
+    /// metadata was introduced.
+    ///
+    /// # Example
+    pub fn record_metadata(&self, location: &Location) -> Result<Option<RecordMetadata>, ClientError> {
+        let (vault_id, record_id) = location.resolve();
+        self.record_metadata_by_id(vault_id, record_id)
+    }
+
+    pub(crate) fn record_metadata_by_id(
+        &self,
+        vault_id: VaultId,
+        record_id: RecordId,
+    ) -> Result<Option<RecordMetadata>, ClientError> {
+        let keystore = self.keystore.read()?;
+        let db = self.db.read()?;
+        let key = keystore
+            .get_key(vault_id)
+            .ok_or(VaultError::<Infallible>::VaultNotFound(vault_id))?;
+        let metadata = RecordMetadata::from_bytes(&db.get_metadata(&key, vault_id, record_id)?)?;
+        Ok(metadata)
+    }
+
+    /// Sets the label in the [`RecordMetadata`] of the record at `location`.
+    ///
+    /// # Example
+    pub fn set_record_label(&self, location: &Location, label: Option<String>) -> Result<(), ClientError> {
+        self.modify_record_metadata(location, |metadata| metadata.label = label)
+    }
+
+    /// Sets the [`RecordPolicy`] of the record at `location` and resets the number of counted uses.
+    ///
+    /// # Example
+    pub fn set_record_policy(&self, location: &Location, policy: RecordPolicy) -> Result<(), ClientError> {
+        self.modify_record_metadata(location, |metadata| {
+            metadata.policy = policy;
+            metadata.uses = 0;
+        })
+    }
+
+    fn modify_record_metadata<F>(&self, location: &Location, f: F) -> Result<(), ClientError>
+    where
+        F: FnOnce(&mut RecordMetadata),
+    {
+        let (vault_id, record_id) = location.resolve();
+        let keystore = self.keystore.read()?;
+        let mut db = self.db.write()?;
+        let key = keystore
+            .get_key(vault_id)
+            .ok_or(VaultError::<Infallible>::VaultNotFound(vault_id))?;
+        let mut metadata = RecordMetadata::from_bytes(&db.get_metadata(&key, vault_id, record_id)?)?
+            .unwrap_or_else(|| RecordMetadata::new(None));
+        f(&mut metadata);
+        db.set_metadata(&key, vault_id, record_id, &metadata.to_bytes())?;
+        Ok(())
+    }
+
+    /// Re-encrypts every record in the vault at `vault_path` under a freshly generated vault key and replaces the
+    /// key in the client's [`KeyStore`]. The [`RecordId`]s and blob ids of the records don't change.
+    ///
+    /// # Example
+    pub fn rotate_vault_key<P>(&self, vault_path: P) -> Result<(), ClientError>
+    where
+        P: AsRef<[u8]>,
+    {
+        let vault_id = derive_vault_id(vault_path);
+        let mut keystore = self.keystore.write()?;
+        let mut db = self.db.write()?;
+        let old_key = keystore
+            .get_key(vault_id)
+            .ok_or(VaultError::<Infallible>::VaultNotFound(vault_id))?;
+        let new_key = Key::random();
+        db.rotate_vault_key(&old_key, &new_key, vault_id)?;
+        keystore.insert_key(vault_id, new_key)?;
+        Ok(())
+    }
+
     /// Synchronize two vaults of the client so that records are copied from `source` to `target`.
     /// If `select_records` is `Some` only the specified records are copied, else a full sync
     /// is performed. If a record already exists at the target, the [`MergePolicy`] applies.
@@ -232,6 +343,23 @@ impl Client {
         Ok(mapped)
     }
 
+    /// Executes a cryptographic [`Procedure`] and attaches `policy` to the record written by the
+    /// procedure. Procedures that don't write a record are executed as with [`Client::execute_procedure`].
+    ///
+    /// # Example
+    pub fn execute_procedure_with_policy<P>(
+        &self,
+        procedure: P,
+        policy: RecordPolicy,
+    ) -> Result<P::Output, ProcedureError>
+    where
+        P: Procedure + Into<StrongholdProcedure>,
+    {
+        let runner = PolicyRunner::new(self, policy);
+        let output = procedure.into().execute(&runner)?;
+        Ok(output.try_into().ok().unwrap())
+    }
+
     /// Executes a list of cryptographic [`crate::procedures::Procedure`]s sequentially and returns a collected output
     ///
     /// # Example
@@ -17,7 +17,7 @@ use engine::{
 use serde::{de::Error, Deserialize, Serialize};
 use thiserror::Error as DeriveError;
 
-use crate::{Client, Provider};
+use crate::{procedures::ProcedureError, Client, Provider};
 use std::io;
 
 #[derive(Debug, DeriveError)]
@@ -61,6 +61,9 @@ pub enum ClientError {
 
     #[error("Client with id {0:?} has already been loaded before. Can not be loaded twice.")]
     ClientAlreadyLoaded(ClientId),
+
+    #[error("Procedure failed ({0})")]
+    Procedure(#[from] ProcedureError),
 }
 
 impl<T> From<TryLockError<T>> for ClientError {
@@ -186,6 +189,49 @@ pub enum RemoteMergeError {
     Vault(RemoteVaultError),
 }
 
+/// Error of a request to the Stronghold daemon.
+#[derive(DeriveError, Debug, Clone, Serialize, Deserialize)]
+pub enum DaemonError {
+    #[error("I/O error: {0}")]
+    Io(String),
+
+    #[error("invalid message: {0}")]
+    InvalidMessage(String),
+
+    #[error("authentication failed")]
+    AuthenticationFailed,
+
+    #[error("session has not been authenticated")]
+    Unauthenticated,
+
+    #[error("too many concurrent connections")]
+    TooManyConnections,
+
+    #[error("client error: {0}")]
+    Client(String),
+
+    #[error("procedure error: {0}")]
+    Procedure(#[from] ProcedureError),
+}
+
+impl From<io::Error> for DaemonError {
+    fn from(e: io::Error) -> Self {
+        DaemonError::Io(e.to_string())
+    }
+}
+
+impl From<bincode::Error> for DaemonError {
+    fn from(e: bincode::Error) -> Self {
+        DaemonError::InvalidMessage(e.to_string())
+    }
+}
+
+impl From<ClientError> for DaemonError {
+    fn from(e: ClientError) -> Self {
+        DaemonError::Client(e.to_string())
+    }
+}
+
 impl From<ClientError> for SnapshotError {
     fn from(e: ClientError) -> Self {
         SnapshotError::Inner(format!("{}", e))
@@ -220,6 +266,9 @@ impl From<EngineReadError> for SnapshotError {
     fn from(e: EngineReadError) -> Self {
         match e {
             EngineReadError::CorruptedContent(reason) => SnapshotError::CorruptedContent(reason),
+            EngineReadError::DecryptionFailed(reason) => {
+                SnapshotError::CorruptedContent(format!("Decryption failed: {}", reason))
+            }
             EngineReadError::InvalidFile => SnapshotError::InvalidFile("Not a Snapshot.".into()),
             EngineReadError::Io(io) => SnapshotError::Io(io),
             EngineReadError::UnsupportedVersion { expected, found } => SnapshotError::InvalidFile(format!(
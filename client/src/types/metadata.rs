@@ -0,0 +1,210 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metadata that is stored alongside each record.
+//!
+//! The metadata is encrypted together with the record's hint, it can be read without decrypting the secret itself.
+
+use crate::{
+    procedures::{KeyType, PolicyError},
+    RecordError,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    ops::{BitAnd, BitOr},
+    time::SystemTime,
+};
+
+/// Version of the serialized [`RecordMetadata`].
+const RECORD_METADATA_VERSION: u8 = 1;
+
+/// Set of operations a record may be used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KeyUsage(u8);
+
+impl KeyUsage {
+    /// Create signatures or MACs with the record.
+    pub const SIGN: KeyUsage = KeyUsage(1);
+
+    /// Derive new secrets from the record.
+    pub const DERIVE: KeyUsage = KeyUsage(1 << 1);
+
+    /// Encrypt or decrypt data with the record.
+    pub const ENCRYPT: KeyUsage = KeyUsage(1 << 2);
+
+    /// Export the record, e.g. by copying it to another location.
+    pub const EXPORT: KeyUsage = KeyUsage(1 << 3);
+
+    /// No usage is allowed.
+    pub const fn empty() -> Self {
+        KeyUsage(0)
+    }
+
+    /// All usages are allowed.
+    pub const fn all() -> Self {
+        KeyUsage(Self::SIGN.0 | Self::DERIVE.0 | Self::ENCRYPT.0 | Self::EXPORT.0)
+    }
+
+    /// Returns `true` if all usages in `other` are allowed by `self`.
+    pub const fn contains(&self, other: KeyUsage) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if no usage is allowed.
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl Default for KeyUsage {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl BitOr for KeyUsage {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        KeyUsage(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for KeyUsage {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        KeyUsage(self.0 & rhs.0)
+    }
+}
+
+/// Policy restricting the use of a record in procedures.
+///
+/// The policy is enforced whenever a procedure uses the record as input. A violation is reported
+/// as [`crate::procedures::ProcedureError::Policy`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordPolicy {
+    /// The operations the record may be used for.
+    pub usage: KeyUsage,
+
+    /// The maximum number of times the record may be used.
+    pub max_uses: Option<u64>,
+
+    /// The point in time after which the record can't be used anymore.
+    pub expires: Option<SystemTime>,
+}
+
+impl RecordPolicy {
+    /// Creates a policy that only allows the given `usage`.
+    pub fn new(usage: KeyUsage) -> Self {
+        RecordPolicy {
+            usage,
+            ..Default::default()
+        }
+    }
+
+    /// Limits the number of times the record may be used.
+    pub fn with_max_uses(mut self, max_uses: u64) -> Self {
+        self.max_uses = Some(max_uses);
+        self
+    }
+
+    /// Sets the point in time after which the record can't be used anymore.
+    pub fn with_expiry(mut self, expires: SystemTime) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    /// Checks if the policy permits to use a record that has already been used `uses` times for `usage`.
+    pub(crate) fn check(&self, usage: KeyUsage, uses: u64, now: SystemTime) -> Result<(), PolicyError> {
+        if !self.usage.contains(usage) {
+            return Err(PolicyError::UsageNotAllowed(usage));
+        }
+        if matches!(self.expires, Some(expires) if now >= expires) {
+            return Err(PolicyError::Expired);
+        }
+        match self.max_uses {
+            Some(max_uses) if uses >= max_uses => Err(PolicyError::UsesExhausted(max_uses)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Metadata of a record.
+///
+/// Timestamps and the key type are maintained by the [`crate::Client`] whenever the record is written.
+/// Label and policy can be set with [`crate::Client::set_record_label`] and [`crate::Client::set_record_policy`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordMetadata {
+    /// Time the record was created.
+    pub created: SystemTime,
+
+    /// Time the content of the record was last written.
+    pub updated: SystemTime,
+
+    /// Type of the key stored in the record, if it was written by a procedure that generates or derives keys.
+    pub key_type: Option<KeyType>,
+
+    /// A user defined label.
+    pub label: Option<String>,
+
+    /// The policy restricting the use of the record.
+    pub policy: RecordPolicy,
+
+    /// The number of times the record has been used. Uses are only counted if the policy limits them.
+    pub uses: u64,
+
+    /// The record path of the [`crate::Location::Generic`] the record was written to. Only set if the record path
+    /// index of the client was enabled when the record was written, see [`crate::Client::set_record_path_index`].
+    pub record_path: Option<Vec<u8>>,
+
+    /// The smallest counter that may still be used for [`crate::procedures::AeadNonce::Counter`] nonces of the key
+    /// stored in the record. It is kept when the record is overwritten, so that counter nonces are never reused.
+    pub next_nonce_counter: u64,
+}
+
+impl RecordMetadata {
+    /// Creates the metadata for a newly written record.
+    pub(crate) fn new(key_type: Option<KeyType>) -> Self {
+        let now = SystemTime::now();
+        RecordMetadata {
+            created: now,
+            updated: now,
+            key_type,
+            label: None,
+            policy: RecordPolicy::default(),
+            uses: 0,
+            record_path: None,
+            next_nonce_counter: 0,
+        }
+    }
+
+    /// Returns the metadata for a record with new content. Label, policy and nonce counter are kept.
+    pub(crate) fn updated(self, key_type: Option<KeyType>) -> Self {
+        RecordMetadata {
+            updated: SystemTime::now(),
+            key_type,
+            ..self
+        }
+    }
+
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![RECORD_METADATA_VERSION];
+        bytes.extend(bincode::serialize(self).expect("serializing record metadata failed"));
+        bytes
+    }
+
+    /// Parses the metadata stored in a record. Records written before metadata was introduced return `None`.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Option<Self>, RecordError> {
+        match bytes.split_first() {
+            None => Ok(None),
+            Some((&RECORD_METADATA_VERSION, metadata)) => bincode::deserialize(metadata)
+                .map(Some)
+                .map_err(|e| RecordError::CorruptedContent(format!("invalid record metadata: {}", e))),
+            Some((version, _)) => Err(RecordError::CorruptedContent(format!(
+                "unsupported record metadata version {}",
+                version
+            ))),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use engine::vault::ClientId;
+
+use crate::{
+    procedures::{Procedure, ProcedureError, ProcedureOutput, StrongholdProcedure},
+    Client, ClientError, ClientPermissions, Location, PermissionError,
+};
+
+/// Handle to a [`Client`] that may only execute the procedures and access the vaults that are permitted by its
+/// [`ClientPermissions`]. A [`RestrictedClient`] can be obtained with [`Client::restrict`], and can only be
+/// narrowed further, never widened.
+///
+/// # Example
+#[derive(Clone)]
+pub struct RestrictedClient {
+    client: Client,
+    permissions: ClientPermissions,
+}
+
+impl Client {
+    /// Returns a handle to this client that is restricted to `permissions`.
+    ///
+    /// # Example
+    pub fn restrict(&self, permissions: ClientPermissions) -> RestrictedClient {
+        RestrictedClient {
+            client: self.clone(),
+            permissions,
+        }
+    }
+}
+
+impl RestrictedClient {
+    /// Returns a handle that is restricted to the permissions granted by both this handle and `permissions`.
+    ///
+    /// # Example
+    pub fn restrict(&self, permissions: ClientPermissions) -> RestrictedClient {
+        RestrictedClient {
+            client: self.client.clone(),
+            permissions: self.permissions.intersect(&permissions),
+        }
+    }
+
+    /// Returns the permissions of this handle.
+    pub fn permissions(&self) -> &ClientPermissions {
+        &self.permissions
+    }
+
+    /// Returns the [`ClientId`] of the client
+    pub fn id(&self) -> &ClientId {
+        self.client.id()
+    }
+
+    /// Returns `true`, if a vault exists. Fails if access to the vault is not permitted.
+    ///
+    /// # Example
+    pub fn vault_exists<P>(&self, vault_path: P) -> Result<bool, ClientError>
+    where
+        P: AsRef<[u8]>,
+    {
+        self.check_vault(vault_path.as_ref())?;
+        self.client.vault_exists(vault_path)
+    }
+
+    /// Returns `true`, if the record exists. Fails if access to the vault is not permitted.
+    ///
+    /// # Example
+    pub fn record_exists(&self, location: &Location) -> Result<bool, ClientError> {
+        self.check_vault(location.vault_path())?;
+        self.client.record_exists(location)
+    }
+
+    /// Executes a cryptographic [`Procedure`] and returns its output, see [`Client::execute_procedure`].
+    /// Fails with [`ProcedureError::Permission`] if the procedure or any of the vaults it accesses is not
+    /// permitted.
+    ///
+    /// # Example
+    pub fn execute_procedure<P>(&self, procedure: P) -> Result<P::Output, ProcedureError>
+    where
+        P: Procedure + Into<StrongholdProcedure>,
+    {
+        let res = self.execute_procedure_chained(vec![procedure.into()]);
+        let mapped = res.map(|mut vec| vec.pop().unwrap().try_into().ok().unwrap())?;
+        Ok(mapped)
+    }
+
+    /// Executes a list of cryptographic procedures sequentially, see [`Client::execute_procedure_chained`].
+    /// No procedure is executed, if any of them is not permitted.
+    ///
+    /// # Example
+    pub fn execute_procedure_chained(
+        &self,
+        procedures: Vec<StrongholdProcedure>,
+    ) -> Result<Vec<ProcedureOutput>, ProcedureError> {
+        for procedure in &procedures {
+            self.permissions.check(procedure)?;
+        }
+        self.client.execute_procedure_chained(procedures)
+    }
+
+    fn check_vault(&self, vault_path: &[u8]) -> Result<(), ClientError> {
+        if !self.permissions.is_vault_allowed(vault_path) {
+            let e = PermissionError::VaultNotAllowed(vault_path.to_vec());
+            return Err(ClientError::Procedure(e.into()));
+        }
+        Ok(())
+    }
+}
@@ -8,7 +8,10 @@
 
 use crypto::keys::x25519;
 use engine::{
-    snapshot::{self, read, read_from as read_from_file, write, write_to as write_to_file, Key},
+    snapshot::{
+        self, read, read_from as read_from_file, read_from_with_backups, read_header_from, rekey_backups, write,
+        write_to_with_backups, Key, SnapshotHeader, SnapshotKdf,
+    },
     store::Cache,
     vault::{view::Record, BlobId, BoxProvider, ClientId, DbView, Key as PKey, RecordHint, RecordId, VaultId},
 };
@@ -125,6 +128,17 @@ impl Display for SnapshotPath {
     }
 }
 
+/// The snapshot file that state was loaded from by [`Snapshot::read_from_snapshot_with_backups`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotSource {
+    /// The newest snapshot file at the [`SnapshotPath`].
+    Latest,
+
+    /// A backup generation of the snapshot file, because the newer files could not be read. Generation `1` is
+    /// the snapshot that was written before the newest one.
+    Backup(usize),
+}
+
 #[derive(Clone, Debug)]
 pub enum UseKey {
     Key(snapshot::Key),
@@ -208,11 +222,49 @@ impl Snapshot {
         Snapshot::from_state(state, key, write_key)
     }
 
+    /// Reads state from the specified named snapshot or the specified path. If the snapshot file is corrupted, the
+    /// backup generations written by [`Snapshot::write_to_snapshot_with_backups`] are tried from newest to oldest. A
+    /// snapshot file that fails to decrypt is not replaced by a backup. The returned [`SnapshotSource`] tells which file the state was read from.
+    pub fn read_from_snapshot_with_backups(
+        snapshot_path: &SnapshotPath,
+        key: Key,
+        write_key: Option<(VaultId, RecordId)>,
+    ) -> Result<(Self, SnapshotSource), SnapshotError> {
+        let (data, generation) = read_from_with_backups(snapshot_path.as_path(), &key, &[])?;
+        let source = match generation {
+            0 => SnapshotSource::Latest,
+            generation => SnapshotSource::Backup(generation),
+        };
+
+        let state = bincode::deserialize(&data)?;
+        Ok((Snapshot::from_state(state, key, write_key)?, source))
+    }
+
     /// Writes state to the specified named snapshot or the specified path
     /// TODO: Add associated data.
     pub fn write_to_snapshot(&self, snapshot_path: &SnapshotPath, use_key: UseKey) -> Result<(), SnapshotError> {
+        self.write_to_snapshot_with_backups(snapshot_path, use_key, SnapshotKdf::None, 0)
+    }
+
+    /// Writes state to the specified named snapshot or the specified path, and keeps up to `backups` previous
+    /// snapshot files as `<snapshot>.1`, `<snapshot>.2`, ... Previous snapshot files that have been written with
+    /// another key are deleted.
+    ///
+    /// `kdf` is recorded in the [`SnapshotHeader`] of the file, together with the ids of the contained clients.
+    pub fn write_to_snapshot_with_backups(
+        &self,
+        snapshot_path: &SnapshotPath,
+        use_key: UseKey,
+        kdf: SnapshotKdf,
+        backups: usize,
+    ) -> Result<(), SnapshotError> {
         let state = self.get_snapshot_state()?;
         let data = bincode::serialize(&state)?;
+        let header = SnapshotHeader::new(
+            kdf,
+            format!("iota_stronghold {}", env!("CARGO_PKG_VERSION")),
+            state.0.keys().copied().collect(),
+        );
 
         let key = match use_key {
             UseKey::Key(k) => k,
@@ -229,7 +281,25 @@ impl Snapshot {
             }
         };
 
-        write_to_file(&data, snapshot_path.as_path(), &key, &[]).map_err(|e| e.into())
+        write_to_with_backups(&data, snapshot_path.as_path(), &key, &[], &header, backups).map_err(|e| e.into())
+    }
+
+    /// Re-encrypts the backup generations written by [`Snapshot::write_to_snapshot_with_backups`] from `old_key` to
+    /// `new_key`, and records `kdf` in their [`SnapshotHeader`]s. Backups that can't be decrypted with `old_key` are
+    /// deleted.
+    pub fn rekey_backups(
+        snapshot_path: &SnapshotPath,
+        old_key: &Key,
+        new_key: &Key,
+        kdf: &SnapshotKdf,
+    ) -> Result<(), SnapshotError> {
+        rekey_backups(snapshot_path.as_path(), old_key, new_key, &[], kdf).map_err(|e| e.into())
+    }
+
+    /// Reads the [`SnapshotHeader`] of the snapshot file at the specified path without decrypting it. Returns
+    /// `Ok(None)` for snapshot files that have been written before headers were introduced.
+    pub fn read_header(snapshot_path: &SnapshotPath) -> Result<Option<SnapshotHeader>, SnapshotError> {
+        read_header_from(snapshot_path.as_path()).map_err(|e| e.into())
     }
 
     /// Adds data to the snapshot state hashmap.
@@ -0,0 +1,323 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Chunked streaming encryption with a key stored in the vault.
+//!
+//! The construction follows the STREAM online authenticated encryption scheme with XChaCha20Poly1305 as
+//! underlying AEAD. The plaintext is split into chunks of a fixed size, each sealed separately. The nonce of every
+//! chunk is derived from a random prefix, a big-endian chunk counter and a flag marking the last chunk, so that
+//! reordered, duplicated or truncated chunks are detected when decrypting.
+//!
+//! The encrypted stream has the following layout:
+//!
+//! ```text
+//! header := nonce prefix (19 bytes) || chunk size (4 bytes, big-endian)
+//! stream := header || chunk_0 || .. || chunk_n
+//! chunk  := ciphertext (chunk size bytes, shorter for the last chunk) || tag (16 bytes)
+//! nonce  := nonce prefix || counter (4 bytes, big-endian) || last chunk flag (1 byte)
+//! ```
+//!
+//! The header is authenticated as associated data of every chunk.
+
+use crate::{Client, ClientError, KeyUsage, Location};
+use crypto::{
+    ciphers::{chacha::XChaCha20Poly1305, traits::Aead},
+    utils::rand::fill,
+};
+use engine::runtime::memories::buffer::Buffer;
+use std::io::{self, ErrorKind, Read, Write};
+use zeroize::Zeroizing;
+
+/// The default size of plaintext chunks.
+pub const AEAD_STREAM_DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The maximum size of plaintext chunks.
+pub const AEAD_STREAM_MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+const NONCE_PREFIX_LENGTH: usize = 19;
+const HEADER_LENGTH: usize = NONCE_PREFIX_LENGTH + 4;
+const LAST_CHUNK: u8 = 1;
+
+/// A streaming encryption session, holding the guarded key for as long as the session is alive.
+///
+/// A session is obtained with [`Client::aead_stream`]. The same session can be used to encrypt and decrypt any number
+/// of streams.
+#[derive(Debug)]
+pub struct AeadStream {
+    key: Buffer<u8>,
+    chunk_size: usize,
+}
+
+impl Client {
+    /// Opens a streaming encryption session with the XChaCha20Poly1305 key stored at `key`. The key
+    /// remains guarded in memory until the returned [`AeadStream`] is dropped.
+    ///
+    /// Opening the session counts as one use of the key, the policy of the record has to permit
+    /// [`KeyUsage::ENCRYPT`].
+    ///
+    /// # Example
+    pub fn aead_stream(&self, key: &Location) -> Result<AeadStream, ClientError> {
+        self.enforce_policies(std::slice::from_ref(key), &[KeyUsage::ENCRYPT], || {
+            let key = self.get_guard(key, |guard| Ok(Buffer::alloc(&guard.borrow(), guard.len())))?;
+            if key.len() != XChaCha20Poly1305::KEY_LENGTH {
+                return Err(ClientError::IllegalKeySize(XChaCha20Poly1305::KEY_LENGTH));
+            }
+            Ok(AeadStream {
+                key,
+                chunk_size: AEAD_STREAM_DEFAULT_CHUNK_SIZE,
+            })
+        })
+    }
+}
+
+impl AeadStream {
+    /// Sets the size of the plaintext chunks for encryption. The chunk size is stored in the header of the
+    /// encrypted stream, decryption therefore always uses the chunk size the stream was encrypted with.
+    ///
+    /// Fails, if `chunk_size` is zero or exceeds [`AEAD_STREAM_MAX_CHUNK_SIZE`].
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Result<Self, ClientError> {
+        if chunk_size == 0 || chunk_size > AEAD_STREAM_MAX_CHUNK_SIZE {
+            return Err(ClientError::Inner(format!("Invalid chunk size {}", chunk_size)));
+        }
+        self.chunk_size = chunk_size;
+        Ok(self)
+    }
+
+    /// Starts encrypting into `writer`. The header of the stream is written immediately.
+    ///
+    /// The returned [`AeadStreamEncryptor`] must be completed with [`AeadStreamEncryptor::finish`], otherwise
+    /// the last chunk is not written and the stream can not be decrypted.
+    pub fn encrypt<W: Write>(&self, mut writer: W) -> io::Result<AeadStreamEncryptor<'_, W>> {
+        let mut header = [0u8; HEADER_LENGTH];
+        fill(&mut header[..NONCE_PREFIX_LENGTH]).map_err(crypto_error)?;
+        header[NONCE_PREFIX_LENGTH..].copy_from_slice(&(self.chunk_size as u32).to_be_bytes());
+        writer.write_all(&header)?;
+
+        Ok(AeadStreamEncryptor {
+            key: &self.key,
+            header,
+            counter: Some(0),
+            chunk_size: self.chunk_size,
+            buffer: Zeroizing::new(Vec::with_capacity(self.chunk_size)),
+            inner: writer,
+        })
+    }
+
+    /// Starts decrypting from `reader`. The header of the stream is read immediately.
+    ///
+    /// Reading from the returned [`AeadStreamDecryptor`] fails with [`ErrorKind::InvalidData`] if the stream has
+    /// been tampered with or truncated. Plaintext is only ever returned after the chunk containing it has been
+    /// authenticated.
+    pub fn decrypt<R: Read>(&self, mut reader: R) -> io::Result<AeadStreamDecryptor<'_, R>> {
+        let mut header = [0u8; HEADER_LENGTH];
+        reader.read_exact(&mut header)?;
+
+        let mut chunk_size = [0u8; 4];
+        chunk_size.copy_from_slice(&header[NONCE_PREFIX_LENGTH..]);
+        let chunk_size = u32::from_be_bytes(chunk_size) as usize;
+        if chunk_size == 0 || chunk_size > AEAD_STREAM_MAX_CHUNK_SIZE {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "invalid chunk size in stream header",
+            ));
+        }
+
+        Ok(AeadStreamDecryptor {
+            key: &self.key,
+            header,
+            counter: Some(0),
+            chunk_size,
+            plaintext: Zeroizing::new(Vec::with_capacity(chunk_size)),
+            position: 0,
+            peeked: None,
+            finished: false,
+            inner: reader,
+        })
+    }
+}
+
+/// [`Write`] adapter encrypting everything written to it into the inner writer.
+pub struct AeadStreamEncryptor<'a, W: Write> {
+    key: &'a Buffer<u8>,
+    header: [u8; HEADER_LENGTH],
+    counter: Option<u32>,
+    chunk_size: usize,
+    buffer: Zeroizing<Vec<u8>>,
+    inner: W,
+}
+
+impl<'a, W: Write> AeadStreamEncryptor<'a, W> {
+    /// Encrypts the remaining buffered plaintext as last chunk and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.seal_chunk(true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn seal_chunk(&mut self, last: bool) -> io::Result<()> {
+        let counter = self
+            .counter
+            .ok_or_else(|| io::Error::other("maximum number of chunks for this stream exceeded"))?;
+        let nonce = chunk_nonce(&self.header, counter, last);
+
+        let mut ciphertext = vec![0u8; self.buffer.len()];
+        let mut tag = [0u8; 16];
+        XChaCha20Poly1305::try_encrypt(
+            &self.key.borrow(),
+            &nonce,
+            &self.header,
+            &self.buffer,
+            &mut ciphertext,
+            &mut tag,
+        )
+        .map_err(crypto_error)?;
+        self.buffer.clear();
+
+        self.inner.write_all(&ciphertext)?;
+        self.inner.write_all(&tag)?;
+        self.counter = counter.checked_add(1);
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Write for AeadStreamEncryptor<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        // A full chunk is only sealed once more data follows, since the last chunk has to be marked as such.
+        if self.buffer.len() == self.chunk_size {
+            self.seal_chunk(false)?;
+        }
+        let n = buf.len().min(self.chunk_size - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    /// Flushes the inner writer. Buffered plaintext of an incomplete chunk is not written before
+    /// the chunk is complete or [`AeadStreamEncryptor::finish`] is called.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// [`Read`] adapter decrypting the stream read from the inner reader.
+pub struct AeadStreamDecryptor<'a, R: Read> {
+    key: &'a Buffer<u8>,
+    header: [u8; HEADER_LENGTH],
+    counter: Option<u32>,
+    chunk_size: usize,
+    plaintext: Zeroizing<Vec<u8>>,
+    position: usize,
+    peeked: Option<u8>,
+    finished: bool,
+    inner: R,
+}
+
+impl<'a, R: Read> AeadStreamDecryptor<'a, R> {
+    /// Returns the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn open_chunk(&mut self) -> io::Result<()> {
+        let tag_length = XChaCha20Poly1305::TAG_LENGTH;
+        let mut chunk = vec![0u8; self.chunk_size + tag_length];
+        let mut filled = 0;
+        if let Some(byte) = self.peeked.take() {
+            chunk[0] = byte;
+            filled = 1;
+        }
+        filled += read_full(&mut self.inner, &mut chunk[filled..])?;
+
+        // The last chunk is identified by the end of the stream. For full chunks, one byte is read ahead to find out.
+        let last = if filled < chunk.len() {
+            true
+        } else {
+            let mut byte = [0u8; 1];
+            match read_full(&mut self.inner, &mut byte)? {
+                0 => true,
+                _ => {
+                    self.peeked = Some(byte[0]);
+                    false
+                }
+            }
+        };
+
+        if filled < tag_length {
+            return Err(io::Error::new(ErrorKind::InvalidData, "stream is truncated"));
+        }
+        let counter = self.counter.ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                "maximum number of chunks for this stream exceeded",
+            )
+        })?;
+        let nonce = chunk_nonce(&self.header, counter, last);
+        let (ciphertext, tag) = chunk[..filled].split_at(filled - tag_length);
+
+        self.plaintext.clear();
+        self.plaintext.resize(ciphertext.len(), 0);
+        self.position = 0;
+        if XChaCha20Poly1305::try_decrypt(
+            &self.key.borrow(),
+            &nonce,
+            &self.header,
+            &mut self.plaintext,
+            ciphertext,
+            tag,
+        )
+        .is_err()
+        {
+            self.plaintext.clear();
+            return Err(io::Error::new(ErrorKind::InvalidData, "chunk authentication failed"));
+        }
+
+        self.counter = counter.checked_add(1);
+        self.finished = last;
+        Ok(())
+    }
+}
+
+impl<'a, R: Read> Read for AeadStreamDecryptor<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.plaintext.len() {
+            if self.finished || buf.is_empty() {
+                return Ok(0);
+            }
+            self.open_chunk()?;
+        }
+        let n = buf.len().min(self.plaintext.len() - self.position);
+        buf[..n].copy_from_slice(&self.plaintext[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+fn chunk_nonce(header: &[u8; HEADER_LENGTH], counter: u32, last: bool) -> [u8; 24] {
+    let mut nonce = [0u8; 24];
+    nonce[..NONCE_PREFIX_LENGTH].copy_from_slice(&header[..NONCE_PREFIX_LENGTH]);
+    nonce[NONCE_PREFIX_LENGTH..NONCE_PREFIX_LENGTH + 4].copy_from_slice(&counter.to_be_bytes());
+    if last {
+        nonce[NONCE_PREFIX_LENGTH + 4] = LAST_CHUNK;
+    }
+    nonce
+}
+
+/// Reads until `buf` is full or the end of the stream is reached, returning the number of bytes read.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn crypto_error(e: crypto::Error) -> io::Error {
+    io::Error::other(format!("{:?}", e))
+}
@@ -4,14 +4,17 @@ use crate::{
     procedures::Runner,
     sync::{SnapshotHierarchy, SyncSnapshots, SyncSnapshotsConfig},
     Client, ClientError, ClientState, KeyProvider, LoadFromPath, Location, RemoteMergeError, RemoteVaultError,
-    Snapshot, SnapshotPath, Store, UseKey,
+    Snapshot, SnapshotKdf, SnapshotPath, SnapshotSource, Store, UseKey,
 };
 use crypto::keys::x25519;
 use engine::vault::ClientId;
 use std::{
     collections::{hash_map::Entry, HashMap},
     ops::Deref,
-    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
 };
 use stronghold_utils::GuardDebug;
 use zeroize::Zeroize;
@@ -44,7 +47,8 @@ macro_rules! write_with_clientid {
     }};
 }
 
-/// Load a snapshot from a path
+/// Load a snapshot from a path, falling back to its backup generations
+/// if the newest snapshot file is corrupted. Evaluates to the [`SnapshotSource`]
 /// We use a macro instead of a function due to locks lifetime
 /// ending at the end of a function
 /// # Example
@@ -66,9 +70,12 @@ macro_rules! load_snapshot {
                 .map_err(|e| ClientError::Inner(format!("{:?}", e)))?;
             let buffer_ref = buffer.borrow().deref().try_into().unwrap();
 
-            *($snapshot) = Snapshot::read_from_snapshot(($snapshot_path), buffer_ref, None)
+            let (snapshot, source) = Snapshot::read_from_snapshot_with_backups(($snapshot_path), buffer_ref, None)
                 .map_err(|e| ClientError::Inner(e.to_string()))?;
+            *($snapshot) = snapshot;
             // END CRITICAL SECTION
+
+            source
         }
     }};
 }
@@ -92,6 +99,13 @@ pub struct Stronghold {
 
     /// Optional key location for writing to [`Snapshot`]
     key_location: Arc<RwLock<Option<Location>>>,
+
+    /// Number of previous snapshot files that are kept on commit
+    snapshot_backups: Arc<AtomicUsize>,
+
+    /// Serializes operations of the `AsyncStronghold`s of this instance that replace the snapshot or client state
+    #[cfg(feature = "async")]
+    pub(crate) async_lock: Arc<tokio::sync::RwLock<()>>,
 }
 
 impl Stronghold {
@@ -226,10 +240,76 @@ impl Stronghold {
     /// Load the state of a [`Snapshot`] at given `snapshot_path`. The [`Snapshot`]
     /// is secured in memory.
     ///
+    /// If the snapshot file is corrupted, the backup generations kept by [`Stronghold::set_snapshot_backups`] are
+    /// loaded instead, from newest to oldest. A snapshot file that fails to authenticate, e.g. because of a wrong key,
+    /// is not replaced by a backup.
+    /// The returned [`SnapshotSource`] reports which file has been loaded.
+    ///
     /// # Example
-    pub fn load_snapshot(&self, keyprovider: &KeyProvider, snapshot_path: &SnapshotPath) -> Result<(), ClientError> {
+    pub fn load_snapshot(
+        &self,
+        keyprovider: &KeyProvider,
+        snapshot_path: &SnapshotPath,
+    ) -> Result<SnapshotSource, ClientError> {
         let mut snapshot = self.snapshot.write()?;
-        load_snapshot!(snapshot, snapshot_path, keyprovider);
+        let source = load_snapshot!(snapshot, snapshot_path, keyprovider);
+        Ok(source)
+    }
+
+    /// Sets the number of previous snapshot files that are kept as backup generations
+    /// (`<snapshot>.1`, `<snapshot>.2`, ...) when committing. Defaults to `0`. Committing with another key than
+    /// the existing snapshot file has been written with deletes the backups, use [`Stronghold::rotate_snapshot_key`]
+    /// to keep them.
+    ///
+    /// # Example
+    pub fn set_snapshot_backups(&self, backups: usize) {
+        self.snapshot_backups.store(backups, Ordering::Relaxed);
+    }
+
+    /// Re-encrypts the snapshot file at `snapshot_path` with the key of `new_keyprovider`. The snapshot is
+    /// decrypted with `old_keyprovider`, and the re-encrypted snapshot atomically replaces the existing file.
+    /// The in-memory [`Snapshot`] state is not changed, subsequent commits have to use the new key.
+    /// The backup generations of the snapshot file are re-encrypted as well, backups that can't be decrypted
+    /// with the old key are deleted.
+    ///
+    /// # Example
+    pub fn rotate_snapshot_key(
+        &self,
+        old_keyprovider: &KeyProvider,
+        new_keyprovider: &KeyProvider,
+        snapshot_path: &SnapshotPath,
+    ) -> Result<(), ClientError> {
+        if !snapshot_path.exists() {
+            let path = snapshot_path
+                .as_path()
+                .to_str()
+                .ok_or_else(|| ClientError::Inner("Cannot display path as string".to_string()))?;
+
+            return Err(ClientError::SnapshotFileMissing(path.to_string()));
+        }
+
+        // CRITICAL SECTION
+        let old_buffer = old_keyprovider
+            .try_unlock()
+            .map_err(|e| ClientError::Inner(format!("{:?}", e)))?;
+        let old_buffer_ref = old_buffer.borrow();
+        let old_key = old_buffer_ref.deref().try_into().unwrap();
+        let new_buffer = new_keyprovider
+            .try_unlock()
+            .map_err(|e| ClientError::Inner(format!("{:?}", e)))?;
+        let new_buffer_ref = new_buffer.borrow();
+        let new_key = new_buffer_ref.deref().try_into().unwrap();
+
+        // only the snapshot file itself is rotated, a corrupted file must not be replaced by a backup.
+        let snapshot = Snapshot::read_from_snapshot(snapshot_path, old_key, None)
+            .map_err(|e| ClientError::Inner(e.to_string()))?;
+        snapshot
+            .write_to_snapshot_with_backups(snapshot_path, UseKey::Key(new_key), new_keyprovider.kdf().clone(), 0)
+            .map_err(|e| ClientError::Inner(e.to_string()))?;
+        Snapshot::rekey_backups(snapshot_path, &old_key, &new_key, new_keyprovider.kdf())
+            .map_err(|e| ClientError::Inner(e.to_string()))?;
+        // END CRITICAL SECTION
+
         Ok(())
     }
 
@@ -307,7 +387,12 @@ impl Stronghold {
         let key = buffer_ref.deref();
 
         snapshot
-            .write_to_snapshot(snapshot_path, UseKey::Key(key.try_into().unwrap()))
+            .write_to_snapshot_with_backups(
+                snapshot_path,
+                UseKey::Key(key.try_into().unwrap()),
+                keyprovider.kdf().clone(),
+                self.snapshot_backups.load(Ordering::Relaxed),
+            )
             .map_err(|e| ClientError::Inner(e.to_string()))?;
 
         Ok(())
@@ -345,7 +430,12 @@ impl Stronghold {
         };
 
         snapshot
-            .write_to_snapshot(snapshot_path, UseKey::Stored(key_location.clone()))
+            .write_to_snapshot_with_backups(
+                snapshot_path,
+                UseKey::Stored(key_location.clone()),
+                SnapshotKdf::None,
+                self.snapshot_backups.load(Ordering::Relaxed),
+            )
             .map_err(|e| ClientError::Inner(e.to_string()))?;
 
         Ok(())
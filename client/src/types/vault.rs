@@ -1,8 +1,10 @@
 // Copyright 2020-2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{derive_vault_id, procedures::Runner, Client, ClientError, Location};
-use engine::vault::VaultId;
+use std::convert::Infallible;
+
+use crate::{derive_vault_id, procedures::Runner, Client, ClientError, Location, VaultError};
+use engine::vault::{RecordHint, RecordId, VaultId};
 
 pub const DEFAULT_RANDOM_HINT_SIZE: usize = 24;
 
@@ -71,6 +73,43 @@ impl ClientVault {
         derive_vault_id(self.vault_path.clone())
     }
 
+    /// Returns the ids and decrypted [`RecordHint`]s of all records in the vault. Revoked records are not listed.
+    ///
+    /// # Example
+    pub fn list_records(&self) -> Result<Vec<(RecordId, RecordHint)>, ClientError> {
+        let vault_id = self.id();
+        let keystore = self.client.keystore.read()?;
+        let db = self.client.db.read()?;
+        let key = keystore
+            .get_key(vault_id)
+            .ok_or(VaultError::<Infallible>::VaultNotFound(vault_id))?;
+        let records = db
+            .list_hints_and_ids(&key, vault_id)
+            .into_iter()
+            .filter(|(record_id, _)| db.contains_record(vault_id, *record_id))
+            .collect();
+        Ok(records)
+    }
+
+    /// Returns an iterator over the ids and decrypted [`RecordHint`]s of all records in the vault. The
+    /// records are listed when the iterator is created, records written afterwards are not included.
+    ///
+    /// # Example
+    pub fn records(&self) -> Result<impl Iterator<Item = (RecordId, RecordHint)>, ClientError> {
+        Ok(self.list_records()?.into_iter())
+    }
+
+    /// Recovers the record path of the record with `record_id` from the record path index. Returns `Ok(None)` if the
+    /// record was not written to a [`Location::Generic`] while the index was enabled.
+    ///
+    /// See [`Client::set_record_path_index`].
+    ///
+    /// # Example
+    pub fn record_path(&self, record_id: RecordId) -> Result<Option<Vec<u8>>, ClientError> {
+        let metadata = self.client.record_metadata_by_id(self.id(), record_id)?;
+        Ok(metadata.and_then(|metadata| metadata.record_path))
+    }
+
     /// SECURITY WARNING! THIS IS FOR TESTING PURPOSES ONLY!
     ///
     /// # Security
@@ -3,7 +3,16 @@
 
 use proc_macro2::{Ident, TokenStream};
 use quote::quote;
-use syn::{DataEnum, Fields};
+use syn::{Attribute, DataEnum, Fields};
+
+/// Returns the `#[cfg(..)]` attributes of a variant, so that generated code is only compiled alongside it.
+fn cfg_attrs(attrs: &[Attribute]) -> TokenStream {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("cfg"))
+        .map(|attr| quote! { #attr })
+        .collect()
+}
 
 pub fn build_plain(name: &Ident, data_enum: &DataEnum) -> TokenStream {
     let plain_variants = data_enum
@@ -11,16 +20,80 @@ pub fn build_plain(name: &Ident, data_enum: &DataEnum) -> TokenStream {
         .iter()
         .map(|variant| {
             let ident = variant.ident.clone();
+            let cfg = cfg_attrs(&variant.attrs);
             quote! {
+                    #cfg
                     #ident,
             }
         })
         .collect::<TokenStream>();
+    let names = data_enum
+        .variants
+        .iter()
+        .map(|variant| {
+            let ident = variant.ident.clone();
+            let ident_name = ident.to_string();
+            let cfg = cfg_attrs(&variant.attrs);
+            quote! {
+                #cfg
+                #name::#ident => #ident_name,
+            }
+        })
+        .collect::<TokenStream>();
+    let from_names = data_enum
+        .variants
+        .iter()
+        .map(|variant| {
+            let ident = variant.ident.clone();
+            let ident_name = ident.to_string();
+            let cfg = cfg_attrs(&variant.attrs);
+            quote! {
+                #cfg
+                #ident_name => Some(#name::#ident),
+            }
+        })
+        .collect::<TokenStream>();
+    let variants = data_enum
+        .variants
+        .iter()
+        .map(|variant| {
+            let ident = variant.ident.clone();
+            let cfg = cfg_attrs(&variant.attrs);
+            quote! {
+                #cfg
+                variants.push(#name::#ident);
+            }
+        })
+        .collect::<TokenStream>();
     quote! {
-            #[derive(Debug, Clone)]
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
             pub enum #name {
                 #plain_variants
             }
+
+            impl #name {
+                /// Returns the name of the variant.
+                pub fn name(&self) -> &'static str {
+                    match self {
+                        #names
+                    }
+                }
+
+                /// Returns the variant called `name`.
+                pub fn from_name(name: &str) -> Option<Self> {
+                    match name {
+                        #from_names
+                        _ => None,
+                    }
+                }
+
+                /// Returns all variants in declaration order.
+                pub fn variants() -> Vec<Self> {
+                    let mut variants = Vec::new();
+                    #variants
+                    variants
+                }
+            }
     }
 }
 
@@ -39,7 +112,9 @@ pub fn impl_to_permissioned(input: &Ident, name: &Ident, data_enum: &DataEnum) -
         .map(|variant| {
             let ident = variant.ident.clone();
             let fields = match_fields(&variant.fields);
+            let cfg = cfg_attrs(&variant.attrs);
             quote! {
+                #cfg
                 #input::#ident #fields => #name::#ident,
             }
         })
@@ -57,8 +132,8 @@ pub fn impl_to_permissioned(input: &Ident, name: &Ident, data_enum: &DataEnum) -
 
 pub fn impl_permission(name: &Ident, data_enum: &DataEnum) -> TokenStream {
     assert!(
-        data_enum.variants.len() <= 32,
-        "More then 32 variants on enums are not supported."
+        data_enum.variants.len() <= 64,
+        "More then 64 variants on enums are not supported."
     );
 
     let mut i = 0u8;
@@ -67,7 +142,9 @@ pub fn impl_permission(name: &Ident, data_enum: &DataEnum) -> TokenStream {
         .iter()
         .map(|variant| {
             let ident = variant.ident.clone();
+            let cfg = cfg_attrs(&variant.attrs);
             let p = quote! {
+                #cfg
                 #name::#ident => #i,
             };
             i += 1;
@@ -80,7 +157,7 @@ pub fn impl_permission(name: &Ident, data_enum: &DataEnum) -> TokenStream {
                 let n = match self {
                     #permissions
                 };
-                // Only panics if the enum has more than 32 variants, which has already been checked.
+                // Only panics if the enum has more than 64 variants, which has already been checked.
                 PermissionValue::new(n).unwrap()
             }
         }
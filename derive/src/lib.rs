@@ -35,8 +35,10 @@ pub fn derive_guard_debug(input: TokenStream) -> TokenStream {
 /// For enums, it creates an analogous new enum `<Ident>Permission` with Unit variants, and implements
 /// `VariantPermission` by assigning different `PermissionValue` for each variant. The permission value is the "index"
 /// in the enum as exponent for the power of 2, thus from top to bottom 1, 2, 4, 8...
-/// Additionally, it implements `Borrow` from the new original enum to the new enum, to satisfy the trait bounds of
-/// `StrongholdP2p`.
+/// Additionally, it implements `FwRequest` from the original enum for the new enum, to map a request to its permission.
+/// The permission values depend on the order of the variants, persisted permissions have to refer to the variants
+/// by their name, see `name` and `from_name` of the new enum.
+/// `#[cfg(..)]` attributes of the variants are carried over to the generated code.
 #[proc_macro_derive(RequestPermissions)]
 pub fn permissions(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let derive_input = syn::parse_macro_input!(input as DeriveInput);
@@ -18,6 +18,11 @@
 //! The current version of the format is using X25519 together with an ephemeral
 //! key to derive a shared key for the symmetric XChaCha20 cipher and uses the
 //! Poly1305 message authentication algorithm.
+//!
+//! Since version 3 the file header also carries a [`SnapshotHeader`] with the
+//! key derivation parameters, the creation time, the writer and the contained
+//! clients. The header is stored in plain text and authenticated as associated
+//! data of the snapshot content.
 
 //! Future versions, when the demands for larger snapshot sizes and/or random
 //! access is desired, might consider encrypting smaller chunks (B-trees?) or
@@ -26,6 +31,8 @@
 mod compression;
 pub mod files;
 
+mod header;
 mod logic;
 pub use compression::{compress, decompress, Lz4DecodeError};
+pub use header::*;
 pub use logic::*;
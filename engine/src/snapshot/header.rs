@@ -0,0 +1,115 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::vault::ClientId;
+
+/// Maximum memory cost of [`SnapshotKdf::Argon2`] in KiB, i.e. 2 GiB as in the first recommended option of RFC 9106.
+pub const ARGON2_MAX_MEMORY: u32 = 1 << 21;
+
+/// Maximum number of iterations of [`SnapshotKdf::Argon2`].
+pub const ARGON2_MAX_ITERATIONS: u32 = 64;
+
+/// Maximum degree of parallelism of [`SnapshotKdf::Argon2`].
+pub const ARGON2_MAX_LANES: u32 = 64;
+
+/// Maximum salt length of [`SnapshotKdf::Argon2`] in bytes.
+pub const ARGON2_MAX_SALT_LENGTH: usize = 1024;
+
+/// Key derivation function that was used to derive the snapshot key from a passphrase.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SnapshotKdf {
+    /// The snapshot key was provided directly, it can't be derived from a passphrase.
+    #[default]
+    None,
+
+    /// The passphrase was truncated to the key size.
+    Truncated,
+
+    /// The passphrase was hashed with Blake2b-256.
+    Blake2b,
+
+    /// The passphrase was hashed with Argon2.
+    Argon2 {
+        /// Argon2 variant, see `argon2::Variant::as_u32`.
+        variant: u32,
+        /// Argon2 version, see `argon2::Version::as_u32`.
+        version: u32,
+        /// Memory cost in KiB.
+        memory: u32,
+        /// Number of iterations.
+        iterations: u32,
+        /// Degree of parallelism.
+        lanes: u32,
+        /// Salt that was hashed together with the passphrase.
+        salt: Vec<u8>,
+    },
+}
+
+impl SnapshotKdf {
+    /// Returns `true` if the parameters of [`SnapshotKdf::Argon2`] don't exceed the `ARGON2_MAX_*` bounds. The
+    /// header of a snapshot file is only authenticated after the key has been derived, so the parameters have to be
+    /// checked before deriving the key with them.
+    pub fn is_within_limits(&self) -> bool {
+        match self {
+            SnapshotKdf::Argon2 {
+                memory,
+                iterations,
+                lanes,
+                salt,
+                ..
+            } => {
+                *memory <= ARGON2_MAX_MEMORY
+                    && *iterations <= ARGON2_MAX_ITERATIONS
+                    && *lanes <= ARGON2_MAX_LANES
+                    && salt.len() <= ARGON2_MAX_SALT_LENGTH
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Metadata that is stored in the header of a snapshot file. The header is not encrypted, so that the snapshot
+/// key can be derived from the passphrase alone, but it is authenticated together with the snapshot content.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SnapshotHeader {
+    /// How the snapshot key was derived.
+    pub kdf: SnapshotKdf,
+
+    /// Creation time of the snapshot file in seconds since the unix epoch.
+    pub created: u64,
+
+    /// Name and version of the software that wrote the snapshot.
+    pub writer: String,
+
+    /// Ids of the clients stored in the snapshot.
+    pub clients: Vec<ClientId>,
+}
+
+impl SnapshotHeader {
+    /// Creates a new [`SnapshotHeader`] with the current time as creation time.
+    pub fn new(kdf: SnapshotKdf, writer: String, clients: Vec<ClientId>) -> Self {
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        SnapshotHeader {
+            kdf,
+            created,
+            writer,
+            clients,
+        }
+    }
+
+    /// Returns the header written by [`crate::snapshot::write_to`] for snapshots written directly with the engine.
+    pub(crate) fn engine() -> Self {
+        Self::new(
+            SnapshotKdf::None,
+            format!("stronghold_engine {}", env!("CARGO_PKG_VERSION")),
+            Vec::new(),
+        )
+    }
+}
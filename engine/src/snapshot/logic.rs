@@ -2,9 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    fs::{rename, File, OpenOptions},
+    fs::{copy, hard_link, remove_file, rename, File, OpenOptions},
     io::{Read, Write},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use crypto::{
@@ -15,14 +15,19 @@ use crypto::{
 };
 use thiserror::Error as DeriveError;
 
-use crate::snapshot::{compress, decompress};
+use crate::snapshot::{compress, decompress, SnapshotHeader, SnapshotKdf};
 
 /// Magic bytes (bytes 0-4 in a snapshot file) aka PARTI
 pub const MAGIC: [u8; 5] = [0x50, 0x41, 0x52, 0x54, 0x49];
 
 /// Current version bytes (bytes 5-6 in a snapshot file)
-pub const VERSION: [u8; 2] = [0x2, 0x0];
-// pub const OLD_VERSION: [u8; 2] = [0x2, 0x0];
+pub const VERSION: [u8; 2] = [0x3, 0x0];
+
+/// Version bytes of snapshot files without [`SnapshotHeader`]
+pub const VERSION_2: [u8; 2] = [0x2, 0x0];
+
+/// Maximum size of the serialized [`SnapshotHeader`]
+const MAX_HEADER_SIZE: u32 = 1 << 20;
 
 /// Key size for the ephemeral key
 const KEY_SIZE: usize = 32;
@@ -42,6 +47,9 @@ pub enum ReadError {
     #[error("corrupted file: {0}")]
     CorruptedContent(String),
 
+    #[error("decryption failed: {0}")]
+    DecryptionFailed(String),
+
     #[error("invalid File: not a snapshot")]
     InvalidFile,
 
@@ -49,6 +57,21 @@ pub enum ReadError {
     UnsupportedVersion { expected: [u8; 2], found: [u8; 2] },
 }
 
+impl ReadError {
+    /// Returns `true` if the structure of the file is damaged, i.e. the file is truncated, is not a snapshot, has an
+    /// invalid header or fails to decompress.
+    ///
+    /// A file that fails to decrypt is not considered corrupted, as a wrong key can't be told apart from damaged
+    /// ciphertext.
+    pub fn is_corrupted(&self) -> bool {
+        match self {
+            ReadError::CorruptedContent(_) | ReadError::InvalidFile => true,
+            ReadError::Io(e) => e.kind() == std::io::ErrorKind::UnexpectedEof,
+            ReadError::DecryptionFailed(_) | ReadError::UnsupportedVersion { .. } => false,
+        }
+    }
+}
+
 #[derive(Debug, DeriveError)]
 pub enum WriteError {
     #[error("I/O error: {0}")]
@@ -157,7 +180,7 @@ pub fn read<I: Read>(input: &mut I, key: &Key, associated_data: &[u8]) -> Result
 
     // decrypt the ciphertext into the plain text buffer.
     XChaCha20Poly1305::try_decrypt(&shared.to_bytes(), &nonce, associated_data, &mut pt, &ct, &tag)
-        .map_err(|e| ReadError::CorruptedContent(format!("Decryption failed: {}", e)))?;
+        .map_err(|e| ReadError::DecryptionFailed(e.to_string()))?;
 
     Ok(pt)
 }
@@ -169,6 +192,28 @@ pub fn read<I: Read>(input: &mut I, key: &Key, associated_data: &[u8]) -> Result
 /// filename with a salted suffix). This is currently known to be problematic if the path is a
 /// symlink and/or if the target path resides in a directory without user write permission.
 pub fn write_to(plain: &[u8], path: &Path, key: &Key, associated_data: &[u8]) -> Result<(), WriteError> {
+    write_to_with_backups(plain, path, key, associated_data, &SnapshotHeader::engine(), 0)
+}
+
+/// Like [`write_to`], but writes the specified [`SnapshotHeader`] and keeps up to `backups` previous
+/// generations of the file at the specified path.
+///
+/// The temporary file is synced to disk before the previous generations are rotated (`path.1` becomes
+/// `path.2` and so on, the current file becomes `path.1`) and the temporary file is renamed to the
+/// specified path. The parent directory is synced afterwards so that the rename is persisted. A file
+/// always exists at the specified path, even if the process crashes during the write.
+///
+/// If the current file can't be decrypted with `key`, e.g. because the snapshot is written with a new key, it is not
+/// kept as backup and the previous generations that can't be decrypted with `key` are removed, as
+/// [`read_from_with_backups`] must not restore a generation written with another key.
+pub fn write_to_with_backups(
+    plain: &[u8],
+    path: &Path,
+    key: &Key,
+    associated_data: &[u8],
+    header: &SnapshotHeader,
+    backups: usize,
+) -> Result<(), WriteError> {
     // TODO: if path exists and is a symlink, resolve it and then append the salt
     // TODO: if the sibling tempfile isn't writeable (e.g. directory permissions), write to
 
@@ -182,29 +227,243 @@ pub fn write_to(plain: &[u8], path: &Path, key: &Key, associated_data: &[u8]) ->
     s.push(hex::encode(salt));
     let tmp = Path::new(&s);
 
+    let written = write_tmp(&compressed_plain, tmp, key, associated_data, header)
+        .and_then(|_| rotate_backups(path, key, associated_data, backups))
+        .and_then(|_| rename(tmp, path).map_err(WriteError::from));
+    if written.is_err() {
+        let _ = remove_file(tmp);
+    }
+    written?;
+
+    sync_parent_dir(path)?;
+
+    Ok(())
+}
+
+/// Returns the path of the backup `generation` of the snapshot at the specified path. Generation `0` is the
+/// snapshot itself.
+pub fn backup_path(path: &Path, generation: usize) -> PathBuf {
+    if generation == 0 {
+        return path.to_path_buf();
+    }
+    let mut s = path.as_os_str().to_os_string();
+    s.push(format!(".{}", generation));
+    PathBuf::from(s)
+}
+
+fn write_tmp(
+    compressed_plain: &[u8],
+    tmp: &Path,
+    key: &Key,
+    associated_data: &[u8],
+    header: &SnapshotHeader,
+) -> Result<(), WriteError> {
+    let header = bincode::serialize(header).map_err(|e| WriteError::CorruptedData(e.to_string()))?;
+    let header_len = u32::try_from(header.len())
+        .ok()
+        .filter(|len| *len <= MAX_HEADER_SIZE)
+        .ok_or_else(|| WriteError::CorruptedData("snapshot header too large".into()))?;
+
     let mut f = OpenOptions::new().write(true).create_new(true).open(tmp)?;
     // write magic and version bytes
     f.write_all(&MAGIC)?;
     f.write_all(&VERSION)?;
-    write(&compressed_plain, &mut f, key, associated_data)?;
+    // write the header, it is authenticated together with the associated data.
+    f.write_all(&header_len.to_le_bytes())?;
+    f.write_all(&header)?;
+    write(compressed_plain, &mut f, key, &[&header, associated_data].concat())?;
     f.sync_all()?;
+    Ok(())
+}
 
-    rename(tmp, path)?;
+/// Shifts the existing backup generations of the file at `path` by one and keeps the current file as first
+/// generation. Generations beyond `backups` are overwritten.
+///
+/// If the current file doesn't decrypt with `key`, the generations that don't decrypt with `key` are removed instead
+/// and the current file is not kept.
+fn rotate_backups(path: &Path, key: &Key, associated_data: &[u8], backups: usize) -> Result<(), WriteError> {
+    if backups == 0 || !path.exists() {
+        return Ok(());
+    }
+    if let Err(ReadError::DecryptionFailed(_)) = read_from(path, key, associated_data) {
+        return remove_backups_with_other_key(path, key, associated_data);
+    }
+    for generation in (1..backups).rev() {
+        let from = backup_path(path, generation);
+        if from.exists() {
+            rename(&from, backup_path(path, generation + 1))?;
+        }
+    }
+    // the current file stays in place until it is replaced by the new one.
+    let first = backup_path(path, 1);
+    if first.exists() {
+        remove_file(&first)?;
+    }
+    if hard_link(path, &first).is_err() {
+        copy(path, &first)?;
+        File::open(&first)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Removes the backup generations of the file at `path` that can't be decrypted with `key` and renumbers the
+/// remaining ones.
+fn remove_backups_with_other_key(path: &Path, key: &Key, associated_data: &[u8]) -> Result<(), WriteError> {
+    let mut kept = 0;
+    for generation in 1.. {
+        let backup = backup_path(path, generation);
+        if !backup.exists() {
+            break;
+        }
+        if read_from(&backup, key, associated_data).is_err() {
+            remove_file(&backup)?;
+            continue;
+        }
+        kept += 1;
+        if kept != generation {
+            rename(&backup, backup_path(path, kept))?;
+        }
+    }
+    Ok(())
+}
 
+fn sync_parent_dir(path: &Path) -> std::io::Result<()> {
+    // directories can't be opened as files on windows, renames are persisted by the file system.
+    #[cfg(unix)]
+    {
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        File::open(parent)?.sync_all()?;
+    }
     Ok(())
 }
 
 /// Check the file header, [`read`][self::read], and decompress the ciphertext from the specified path.
+///
+/// Snapshots of version 2, which don't have a [`SnapshotHeader`], are read as well.
 pub fn read_from(path: &Path, key: &Key, associated_data: &[u8]) -> Result<Vec<u8>, ReadError> {
     let mut f: File = OpenOptions::new().read(true).open(path)?;
     check_min_file_len(&mut f)?;
     // check the header for structure.
-    check_header(&mut f)?;
-    let pt = read(&mut f, key, associated_data)?;
+    let pt = match check_header(&mut f)? {
+        VERSION_2 => read(&mut f, key, associated_data)?,
+        _ => {
+            let header = read_header_bytes(&mut f)?;
+            deserialize_header(&header)?;
+            read(&mut f, key, &[&header, associated_data].concat())?
+        }
+    };
 
     decompress(&pt).map_err(|e| ReadError::CorruptedContent(format!("Decompression failed: {}", e)))
 }
 
+/// Reads the [`SnapshotHeader`] of the snapshot at the specified path without decrypting the snapshot.
+/// Returns `Ok(None)` for snapshots of version 2, which don't have a header.
+///
+/// The header is only authenticated once the snapshot is decrypted. It must not be trusted beyond deriving
+/// the key to decrypt the snapshot with, headers with key derivation parameters beyond the bounds of
+/// [`SnapshotKdf::is_within_limits`] are rejected.
+pub fn read_header_from(path: &Path) -> Result<Option<SnapshotHeader>, ReadError> {
+    let mut f: File = OpenOptions::new().read(true).open(path)?;
+    check_min_file_len(&mut f)?;
+    match check_header(&mut f)? {
+        VERSION_2 => Ok(None),
+        _ => {
+            let header = deserialize_header(&read_header_bytes(&mut f)?)?;
+            if !header.kdf.is_within_limits() {
+                return Err(ReadError::CorruptedContent(
+                    "snapshot key derivation parameters out of range".into(),
+                ));
+            }
+            Ok(Some(header))
+        }
+    }
+}
+
+fn deserialize_header(bytes: &[u8]) -> Result<SnapshotHeader, ReadError> {
+    bincode::deserialize(bytes).map_err(|e| ReadError::CorruptedContent(format!("Invalid snapshot header: {}", e)))
+}
+
+fn read_header_bytes<I: Read>(input: &mut I) -> Result<Vec<u8>, ReadError> {
+    let mut len = [0u8; 4];
+    input.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len);
+    if len > MAX_HEADER_SIZE {
+        return Err(ReadError::CorruptedContent("snapshot header too large".into()));
+    }
+    let mut header = vec![0u8; len as usize];
+    input.read_exact(&mut header)?;
+    Ok(header)
+}
+
+/// Like [`read_from`], but falls back to the backup generations written by [`write_to_with_backups`] if the
+/// structure of the file at the specified path is corrupted, see [`ReadError::is_corrupted`]. A file that fails to
+/// decrypt is not replaced by a backup, as the backups may have been written with another key. Returns the
+/// plaintext together with the generation it was read from, `0` being the file at the specified path. Other errors,
+/// and the error of the file at the specified path if no generation can be read, are returned.
+pub fn read_from_with_backups(path: &Path, key: &Key, associated_data: &[u8]) -> Result<(Vec<u8>, usize), ReadError> {
+    let error = match read_from(path, key, associated_data) {
+        Ok(pt) => return Ok((pt, 0)),
+        Err(e) if e.is_corrupted() => e,
+        Err(e) => return Err(e),
+    };
+    let mut generation = 1;
+    loop {
+        let backup = backup_path(path, generation);
+        if !backup.exists() {
+            return Err(error);
+        }
+        if let Ok(pt) = read_from(&backup, key, associated_data) {
+            return Ok((pt, generation));
+        }
+        generation += 1;
+    }
+}
+
+/// Re-encrypts the backup generations of the file at the specified path, that have been written by
+/// [`write_to_with_backups`], from `old_key` to `new_key` and records `kdf` in their [`SnapshotHeader`]s.
+///
+/// Generations that can't be decrypted with `old_key` are removed, as they could not be restored with `new_key`.
+/// The remaining generations are renumbered, so that [`read_from_with_backups`] finds all of them.
+pub fn rekey_backups(
+    path: &Path,
+    old_key: &Key,
+    new_key: &Key,
+    associated_data: &[u8],
+    kdf: &SnapshotKdf,
+) -> Result<(), WriteError> {
+    let mut kept = 0;
+    for generation in 1.. {
+        let backup = backup_path(path, generation);
+        if !backup.exists() {
+            break;
+        }
+        let plain = match read_from(&backup, old_key, associated_data) {
+            Ok(plain) => plain,
+            Err(_) => {
+                remove_file(&backup)?;
+                continue;
+            }
+        };
+        let header = SnapshotHeader {
+            kdf: kdf.clone(),
+            ..read_header_from(&backup)
+                .ok()
+                .flatten()
+                .unwrap_or_else(SnapshotHeader::engine)
+        };
+        kept += 1;
+        write_to_with_backups(&plain, &backup_path(path, kept), new_key, associated_data, &header, 0)?;
+        if kept != generation {
+            remove_file(&backup)?;
+        }
+    }
+    sync_parent_dir(path)?;
+    Ok(())
+}
+
 fn check_min_file_len(input: &mut File) -> Result<(), ReadError> {
     let min = MAGIC.len() + VERSION.len() + x25519::PUBLIC_KEY_LENGTH + XChaCha20Poly1305::TAG_LENGTH;
     if input.metadata()?.len() >= min as u64 {
@@ -214,8 +473,8 @@ fn check_min_file_len(input: &mut File) -> Result<(), ReadError> {
     }
 }
 
-/// Checks the header for a specific structure; explicitly the magic and version bytes.
-fn check_header<I: Read>(input: &mut I) -> Result<(), ReadError> {
+/// Checks the header for a specific structure; explicitly the magic and version bytes. Returns the version.
+fn check_header<I: Read>(input: &mut I) -> Result<[u8; 2], ReadError> {
     // check the magic bytes
     let mut magic = [0u8; 5];
     input.read_exact(&mut magic)?;
@@ -227,19 +486,20 @@ fn check_header<I: Read>(input: &mut I) -> Result<(), ReadError> {
     let mut version = [0u8; 2];
     input.read_exact(&mut version)?;
 
-    if version != VERSION {
+    if version != VERSION && version != VERSION_2 {
         return Err(ReadError::UnsupportedVersion {
             expected: VERSION,
             found: version,
         });
     }
 
-    Ok(())
+    Ok(version)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::vault::ClientId;
     use stronghold_utils::{
         random,
         test_utils::{corrupt, corrupt_file_at},
@@ -257,6 +517,12 @@ mod test {
         key
     }
 
+    /// Damages the structure of the snapshot file at `path` by truncating it after the magic bytes.
+    fn truncate_file_at(path: &Path) {
+        let file = OpenOptions::new().write(true).open(path).unwrap();
+        file.set_len(MAGIC.len() as u64).unwrap();
+    }
+
     #[test]
     fn test_write_read() {
         let key: Key = random_key();
@@ -284,9 +550,8 @@ mod test {
 
     #[test]
     fn test_snapshot() {
-        let f = tempfile::tempdir().unwrap();
-        let mut pb = f.into_path();
-        pb.push("snapshot");
+        let dir = tempfile::tempdir().unwrap();
+        let pb = dir.path().join("snapshot");
 
         let key: Key = random_key();
         let bs0 = random_bytestring();
@@ -300,9 +565,8 @@ mod test {
     #[test]
     #[should_panic]
     fn test_currupted_snapshot() {
-        let f = tempfile::tempdir().unwrap();
-        let mut pb = f.into_path();
-        pb.push("snapshot");
+        let dir = tempfile::tempdir().unwrap();
+        let pb = dir.path().join("snapshot");
 
         let key: Key = random_key();
         let bs0 = random_bytestring();
@@ -315,9 +579,8 @@ mod test {
 
     #[test]
     fn test_snapshot_overwrite() {
-        let f = tempfile::tempdir().unwrap();
-        let mut pb = f.into_path();
-        pb.push("snapshot");
+        let dir = tempfile::tempdir().unwrap();
+        let pb = dir.path().join("snapshot");
 
         write_to(&random_bytestring(), &pb, &random_key(), &random_bytestring()).unwrap();
 
@@ -329,6 +592,209 @@ mod test {
         assert_eq!(bs0, bs1);
     }
 
+    #[test]
+    fn test_snapshot_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let pb = dir.path().join("snapshot");
+
+        let key: Key = random_key();
+        let ad = random_bytestring();
+        let generations: Vec<Vec<u8>> = (0..4).map(|_| random_bytestring()).collect();
+        for bs in &generations {
+            write_to_with_backups(bs, &pb, &key, &ad, &SnapshotHeader::engine(), 2).unwrap();
+        }
+
+        assert_eq!(read_from(&pb, &key, &ad).unwrap(), generations[3]);
+        assert_eq!(read_from(&backup_path(&pb, 1), &key, &ad).unwrap(), generations[2]);
+        assert_eq!(read_from(&backup_path(&pb, 2), &key, &ad).unwrap(), generations[1]);
+        assert!(!backup_path(&pb, 3).exists());
+
+        // no temporary files are left behind.
+        assert_eq!(pb.parent().unwrap().read_dir().unwrap().count(), 3);
+
+        assert_eq!(
+            read_from_with_backups(&pb, &key, &ad).unwrap(),
+            (generations[3].clone(), 0)
+        );
+        truncate_file_at(&pb);
+        assert_eq!(
+            read_from_with_backups(&pb, &key, &ad).unwrap(),
+            (generations[2].clone(), 1)
+        );
+        truncate_file_at(&backup_path(&pb, 1));
+        assert_eq!(
+            read_from_with_backups(&pb, &key, &ad).unwrap(),
+            (generations[1].clone(), 2)
+        );
+        truncate_file_at(&backup_path(&pb, 2));
+        assert!(read_from_with_backups(&pb, &key, &ad).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_backups_only_on_corruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let pb = dir.path().join("snapshot");
+
+        let key: Key = random_key();
+        let ad = random_bytestring();
+        for _ in 0..2 {
+            write_to_with_backups(&random_bytestring(), &pb, &key, &ad, &SnapshotHeader::engine(), 1).unwrap();
+        }
+
+        // a snapshot file that fails to decrypt is not replaced by its backup.
+        assert!(matches!(
+            read_from_with_backups(&pb, &random_key(), &ad),
+            Err(ReadError::DecryptionFailed(_))
+        ));
+
+        // a missing snapshot file is not replaced by its backup.
+        remove_file(&pb).unwrap();
+        assert!(matches!(read_from_with_backups(&pb, &key, &ad), Err(ReadError::Io(_))));
+    }
+
+    #[test]
+    fn test_snapshot_backups_with_new_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let pb = dir.path().join("snapshot");
+
+        let old_key: Key = random_key();
+        let new_key: Key = random_key();
+        let ad = random_bytestring();
+        let generations: Vec<Vec<u8>> = (0..4).map(|_| random_bytestring()).collect();
+        for bs in &generations[..2] {
+            write_to_with_backups(bs, &pb, &old_key, &ad, &SnapshotHeader::engine(), 3).unwrap();
+        }
+
+        // the generations written with the old key are dropped when the snapshot is written with the new key.
+        write_to_with_backups(&generations[2], &pb, &new_key, &ad, &SnapshotHeader::engine(), 3).unwrap();
+        assert_eq!(read_from(&pb, &new_key, &ad).unwrap(), generations[2]);
+        assert!(!backup_path(&pb, 1).exists());
+
+        write_to_with_backups(&generations[3], &pb, &new_key, &ad, &SnapshotHeader::engine(), 3).unwrap();
+        assert_eq!(read_from(&backup_path(&pb, 1), &new_key, &ad).unwrap(), generations[2]);
+        assert!(!backup_path(&pb, 2).exists());
+
+        // the old key can't restore a backup.
+        truncate_file_at(&pb);
+        assert!(read_from_with_backups(&pb, &old_key, &ad).is_err());
+        assert_eq!(
+            read_from_with_backups(&pb, &new_key, &ad).unwrap(),
+            (generations[2].clone(), 1)
+        );
+    }
+
+    #[test]
+    fn test_rekey_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let pb = dir.path().join("snapshot");
+
+        let old_key: Key = random_key();
+        let new_key: Key = random_key();
+        let ad = random_bytestring();
+        let generations: Vec<Vec<u8>> = (0..4).map(|_| random_bytestring()).collect();
+        for bs in &generations {
+            write_to_with_backups(bs, &pb, &old_key, &ad, &SnapshotHeader::engine(), 3).unwrap();
+        }
+        // the first backup can't be decrypted anymore and is dropped.
+        corrupt_file_at(&backup_path(&pb, 1));
+
+        rekey_backups(&pb, &old_key, &new_key, &ad, &SnapshotKdf::Blake2b).unwrap();
+
+        assert_eq!(read_from(&backup_path(&pb, 1), &new_key, &ad).unwrap(), generations[1]);
+        assert_eq!(read_from(&backup_path(&pb, 2), &new_key, &ad).unwrap(), generations[0]);
+        assert!(!backup_path(&pb, 3).exists());
+        assert!(read_from(&backup_path(&pb, 1), &old_key, &ad).is_err());
+        assert_eq!(
+            read_header_from(&backup_path(&pb, 1)).unwrap().unwrap().kdf,
+            SnapshotKdf::Blake2b
+        );
+    }
+
+    #[test]
+    fn test_snapshot_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let pb = dir.path().join("snapshot");
+
+        let key: Key = random_key();
+        let bs0 = random_bytestring();
+        let ad = random_bytestring();
+        let header = SnapshotHeader::new(
+            SnapshotKdf::Argon2 {
+                variant: 1,
+                version: 0x13,
+                memory: 4096,
+                iterations: 3,
+                lanes: 1,
+                salt: random::fixed_bytestring(16),
+            },
+            "test".into(),
+            vec![ClientId::load(&[1; 24]).unwrap()],
+        );
+
+        write_to_with_backups(&bs0, &pb, &key, &ad, &header, 0).unwrap();
+        assert_eq!(read_header_from(&pb).unwrap(), Some(header));
+        assert_eq!(read_from(&pb, &key, &ad).unwrap(), bs0);
+
+        // the header is authenticated with the snapshot content.
+        let mut bytes = std::fs::read(&pb).unwrap();
+        let header_start = MAGIC.len() + VERSION.len() + 4;
+        bytes[header_start + 1] ^= 1;
+        std::fs::write(&pb, &bytes).unwrap();
+        assert!(read_from(&pb, &key, &ad).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_header_kdf_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let pb = dir.path().join("snapshot");
+
+        let key: Key = random_key();
+        let kdf = SnapshotKdf::Argon2 {
+            variant: 2,
+            version: 0x13,
+            memory: 4096,
+            iterations: 3,
+            lanes: 1,
+            salt: random::fixed_bytestring(16),
+        };
+        let header = SnapshotHeader::new(kdf, "test".into(), Vec::new());
+        write_to_with_backups(&random_bytestring(), &pb, &key, &[], &header, 0).unwrap();
+        assert_eq!(read_header_from(&pb).unwrap(), Some(header.clone()));
+
+        // tamper with the memory cost of the unauthenticated header.
+        let mut tampered = header.clone();
+        if let SnapshotKdf::Argon2 { memory, .. } = &mut tampered.kdf {
+            *memory = u32::MAX;
+        }
+        let header_bytes = bincode::serialize(&header).unwrap();
+        let tampered_bytes = bincode::serialize(&tampered).unwrap();
+        assert_eq!(header_bytes.len(), tampered_bytes.len());
+        let mut bytes = std::fs::read(&pb).unwrap();
+        let header_start = MAGIC.len() + VERSION.len() + 4;
+        bytes[header_start..header_start + tampered_bytes.len()].copy_from_slice(&tampered_bytes);
+        std::fs::write(&pb, &bytes).unwrap();
+
+        assert!(!tampered.kdf.is_within_limits());
+        assert!(matches!(read_header_from(&pb), Err(ReadError::CorruptedContent(_))));
+    }
+
+    #[test]
+    fn test_read_version_2() {
+        let dir = tempfile::tempdir().unwrap();
+        let pb = dir.path().join("snapshot");
+
+        let key: Key = random_key();
+        let bs0 = random_bytestring();
+        let ad = random_bytestring();
+
+        let mut bytes = [MAGIC.as_slice(), VERSION_2.as_slice()].concat();
+        write(&compress(&bs0), &mut bytes, &key, &ad).unwrap();
+        std::fs::write(&pb, &bytes).unwrap();
+
+        assert_eq!(read_header_from(&pb).unwrap(), None);
+        assert_eq!(read_from(&pb, &key, &ad).unwrap(), bs0);
+    }
+
     struct TestVector {
         key: &'static str,
         ad: &'static str,
@@ -35,6 +35,8 @@ impl TransactionType {
 }
 
 /// a generic transaction (untyped) in a byte format.
+///
+/// The fixed-size typed transaction may be followed by variable length metadata.
 #[derive(Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Transaction(Vec<u8>);
 
@@ -110,6 +112,19 @@ impl DataTransaction {
         view.record_hint = record_hint;
         transaction
     }
+
+    /// Create a new data transaction like [`DataTransaction::new`], followed by the given `metadata`.
+    pub fn new_with_metadata<L: Into<Val>>(
+        id: ChainId,
+        len: L,
+        blob: BlobId,
+        record_hint: RecordHint,
+        metadata: &[u8],
+    ) -> Transaction {
+        let mut transaction = Self::new(id, len, blob, record_hint);
+        transaction.0.extend_from_slice(metadata);
+        transaction
+    }
 }
 
 impl TypedTransaction for DataTransaction {
@@ -149,20 +164,26 @@ impl Transaction {
             _ => None,
         }
     }
+
+    /// Get the metadata following the typed transaction. Empty, if the transaction has no metadata.
+    pub fn metadata(&self) -> &[u8] {
+        &self.0[TRANSACTION_BYTES..]
+    }
 }
 
-const TRANSACTION_MAX_BYTES: usize = 112;
+/// Size of the fixed-size part of a transaction.
+const TRANSACTION_BYTES: usize = 112;
 
 impl Default for Transaction {
     fn default() -> Self {
-        Self(vec![0; TRANSACTION_MAX_BYTES])
+        Self(vec![0; TRANSACTION_BYTES])
     }
 }
 impl TryFrom<Vec<u8>> for Transaction {
     type Error = ();
     fn try_from(vec: Vec<u8>) -> Result<Self, Self::Error> {
         match vec.len() {
-            TRANSACTION_MAX_BYTES => Ok(Self(vec)),
+            len if len >= TRANSACTION_BYTES => Ok(Self(vec)),
             _ => Err(()),
         }
     }
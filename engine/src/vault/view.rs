@@ -137,6 +137,31 @@ impl<P: BoxProvider> DbView<P> {
         Ok(blob_id)
     }
 
+    /// Get the metadata of the specified [`Record`]. The metadata is stored alongside the [`RecordHint`], it can be
+    /// read without decrypting the record's blob. Records without metadata return an empty vector.
+    pub fn get_metadata(&self, key: &Key<P>, vid: VaultId, rid: RecordId) -> Result<Vec<u8>, RecordError<P::Error>> {
+        let vault = self.vaults.get(&vid).ok_or(RecordError::RecordNotFound(rid.0))?;
+        vault.get_metadata(key, rid.0)
+    }
+
+    /// Get the [`RecordHint`] of the specified [`Record`] without decrypting the record's blob.
+    pub fn get_hint(&self, key: &Key<P>, vid: VaultId, rid: RecordId) -> Result<RecordHint, RecordError<P::Error>> {
+        let vault = self.vaults.get(&vid).ok_or(RecordError::RecordNotFound(rid.0))?;
+        vault.get_hint(key, rid.0)
+    }
+
+    /// Replace the metadata of the specified [`Record`]. The content of the record is not changed.
+    pub fn set_metadata(
+        &mut self,
+        key: &Key<P>,
+        vid: VaultId,
+        rid: RecordId,
+        metadata: &[u8],
+    ) -> Result<(), RecordError<P::Error>> {
+        let vault = self.vaults.get_mut(&vid).ok_or(RecordError::RecordNotFound(rid.0))?;
+        vault.set_metadata(key, rid.0, metadata)
+    }
+
     /// Get the buffers for the given records, using the provided key for access.
     fn get_buffers<E, const N: usize>(
         &self,
@@ -229,6 +254,18 @@ impl<P: BoxProvider> DbView<P> {
         }
     }
 
+    /// Re-encrypt all records of a [`Vault`] with `new_key`. The [`RecordId`] and [`BlobId`] of each record are kept.
+    /// Revoked records are re-encrypted as well, so that they can still be garbage collected.
+    pub fn rotate_vault_key(
+        &mut self,
+        old_key: &Key<P>,
+        new_key: &Key<P>,
+        vid: VaultId,
+    ) -> Result<(), VaultError<P::Error>> {
+        let vault = self.vaults.get_mut(&vid).ok_or(VaultError::VaultNotFound(vid))?;
+        vault.rotate_key(old_key, new_key).map_err(VaultError::Record)
+    }
+
     /// Clears the entire [`Vault`] from memory.
     pub fn clear(&mut self) {
         self.vaults.clear();
@@ -429,6 +466,49 @@ impl<P: BoxProvider> Vault<P> {
             .and_then(|r| r.get_blob_id(key, id))
     }
 
+    /// Gets the metadata of the record with the given [`ChainId`].
+    pub fn get_metadata(&self, key: &Key<P>, id: ChainId) -> Result<Vec<u8>, RecordError<P::Error>> {
+        self.check_key(key)?;
+        self.entries
+            .get(&id)
+            .ok_or(RecordError::RecordNotFound(id))
+            .and_then(|r| r.get_metadata(key, id))
+    }
+
+    /// Gets the [`RecordHint`] of the record with the given [`ChainId`].
+    pub fn get_hint(&self, key: &Key<P>, id: ChainId) -> Result<RecordHint, RecordError<P::Error>> {
+        self.check_key(key)?;
+        self.entries
+            .get(&id)
+            .ok_or(RecordError::RecordNotFound(id))
+            .and_then(|r| r.get_hint_and_id(key))
+            .map(|(_, hint)| hint)
+    }
+
+    /// Replaces the metadata of the record with the given [`ChainId`].
+    pub fn set_metadata(&mut self, key: &Key<P>, id: ChainId, metadata: &[u8]) -> Result<(), RecordError<P::Error>> {
+        self.check_key(key)?;
+        self.entries
+            .get_mut(&id)
+            .ok_or(RecordError::RecordNotFound(id))
+            .and_then(|r| r.set_metadata(key, id, metadata))
+    }
+
+    /// Re-encrypts all entries with `new_key` and replaces the key of the [`Vault`]. The entries are only replaced
+    /// once all of them have been re-encrypted successfully.
+    pub fn rotate_key(&mut self, old_key: &Key<P>, new_key: &Key<P>) -> Result<(), RecordError<P::Error>> {
+        self.check_key(old_key)?;
+        let mut entries = HashMap::with_capacity(self.entries.len());
+        for (&id, entry) in self.entries.iter() {
+            let mut entry = entry.clone();
+            entry.rotate_key(old_key, new_key)?;
+            entries.insert(id, entry);
+        }
+        self.entries = entries;
+        self.key = new_key.clone();
+        Ok(())
+    }
+
     fn check_key(&self, key: &Key<P>) -> Result<(), RecordError<P::Error>> {
         if key == &self.key {
             Ok(())
@@ -539,6 +619,39 @@ impl Record {
         Ok(tx.blob)
     }
 
+    /// Get the metadata of a record.
+    fn get_metadata<P: BoxProvider>(&self, key: &Key<P>, id: ChainId) -> Result<Vec<u8>, RecordError<P::Error>> {
+        // check if ids match
+        if self.id != id {
+            return Err(RecordError::RecordNotFound(id));
+        }
+        let tx = self.get_transaction(key)?;
+        Ok(tx.metadata().to_vec())
+    }
+
+    /// Replace the metadata of a record.
+    fn set_metadata<P: BoxProvider>(
+        &mut self,
+        key: &Key<P>,
+        id: ChainId,
+        metadata: &[u8],
+    ) -> Result<(), RecordError<P::Error>> {
+        // check if ids match
+        if self.id != id {
+            return Err(RecordError::RecordNotFound(id));
+        }
+
+        let tx = self.get_transaction(key)?;
+        let tx = tx.typed::<DataTransaction>().ok_or_else(|| {
+            RecordError::CorruptedContent("Could not type decrypted transaction as data-transaction".into())
+        })?;
+
+        let dtx = DataTransaction::new_with_metadata(tx.id, tx.len, tx.blob, tx.record_hint, metadata);
+        self.data = dtx.encrypt(key, tx.id).map_err(RecordError::Provider)?;
+
+        Ok(())
+    }
+
     /// Update the data in an existing [`Record`].
     fn update_data<P: BoxProvider>(
         &mut self,
@@ -552,16 +665,22 @@ impl Record {
             return Err(RecordError::RecordNotFound(id));
         }
 
-        let tx = self.get_transaction(key)?;
-        let tx = tx.typed::<DataTransaction>().ok_or_else(|| {
+        let transaction = self.get_transaction(key)?;
+        let tx = transaction.typed::<DataTransaction>().ok_or_else(|| {
             RecordError::CorruptedContent("Could not type decrypted transaction as data-transaction".into())
         })?;
 
         // create a new sealed blob with the new_data.
         let blob: SealedBlob = new_data.encrypt(key, new_blob).map_err(RecordError::Provider)?;
 
-        // create a new sealed transaction with the new_data length.
-        let dtx = DataTransaction::new(tx.id, new_data.len() as u64, new_blob, tx.record_hint);
+        // create a new sealed transaction with the new_data length, keeping the metadata.
+        let dtx = DataTransaction::new_with_metadata(
+            tx.id,
+            new_data.len() as u64,
+            new_blob,
+            tx.record_hint,
+            transaction.metadata(),
+        );
         let data = dtx.encrypt(key, tx.id).map_err(RecordError::Provider)?;
 
         self.blob = blob;
@@ -599,9 +718,15 @@ impl Record {
             .map_err(RecordError::Provider)?;
 
         // Re-encrypt meta data with new key.
-        let updated_data = DataTransaction::new(new_id, typed_tx.len, typed_tx.blob, typed_tx.record_hint)
-            .encrypt(new_key, new_id)
-            .map_err(RecordError::Provider)?;
+        let updated_data = DataTransaction::new_with_metadata(
+            new_id,
+            typed_tx.len,
+            typed_tx.blob,
+            typed_tx.record_hint,
+            tx.metadata(),
+        )
+        .encrypt(new_key, new_id)
+        .map_err(RecordError::Provider)?;
 
         self.blob = updated_blob;
         self.data = updated_data;
@@ -610,6 +735,45 @@ impl Record {
         Ok(())
     }
 
+    /// Re-encrypt the blob and the transactions of the [`Record`] with `new_key`. Unlike [`Record::update_meta`] this
+    /// also applies to revoked records, and the [`ChainId`] and [`BlobId`] of the record are kept.
+    fn rotate_key<P: BoxProvider>(&mut self, old_key: &Key<P>, new_key: &Key<P>) -> Result<(), RecordError<P::Error>> {
+        let map_decrypt_err = |e| match e {
+            DecryptError::Invalid => {
+                RecordError::CorruptedContent("Could not convert bytes into transaction structure".into())
+            }
+            DecryptError::Provider(e) => RecordError::Provider(e),
+        };
+
+        let tx: Transaction = self.data.decrypt(old_key, self.id).map_err(map_decrypt_err)?;
+        let typed_tx = tx.typed::<DataTransaction>().ok_or_else(|| {
+            RecordError::CorruptedContent("Could not type decrypted transaction as data-transaction".into())
+        })?;
+
+        let blob = SealedBlob::from(self.blob.as_ref())
+            .decrypt(old_key, typed_tx.blob)
+            .map_err(|e| match e {
+                DecryptError::Provider(e) => RecordError::Provider(e),
+                DecryptError::Invalid => unreachable!("Vec<u8>: TryFrom<Vec<u8>> is infallible."),
+            })?
+            .encrypt(new_key, typed_tx.blob)
+            .map_err(RecordError::Provider)?;
+        let data = tx.encrypt(new_key, self.id).map_err(RecordError::Provider)?;
+        let revoke = match &self.revoke {
+            Some(revoke) => {
+                let revoke_tx: Transaction = revoke.decrypt(old_key, self.id).map_err(map_decrypt_err)?;
+                Some(revoke_tx.encrypt(new_key, self.id).map_err(RecordError::Provider)?)
+            }
+            None => None,
+        };
+
+        self.blob = blob;
+        self.data = data;
+        self.revoke = revoke;
+
+        Ok(())
+    }
+
     // add a revocation transaction to the [`Record`].
     fn revoke<P: BoxProvider>(&mut self, key: &Key<P>, id: ChainId) -> Result<(), RecordError<P::Error>> {
         // check if id and id match.
@@ -0,0 +1,35 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::Path;
+
+use crypto::hashes::{blake2b::Blake2b256, Digest};
+use engine::snapshot::{read_from, read_header_from, write_to, Key, MAGIC, VERSION, VERSION_2};
+
+const LOREM_STR: &str = include_str!("lorem.txt");
+
+/// Snapshot of `lorem.txt` written by the `write_to` of stronghold_engine 1.1, with the Blake2b-256 hash of
+/// `passphrase-v2` as key.
+const V2_FIXTURE: &str = "tests/fixtures/snapshot_v2.stronghold";
+
+#[test]
+fn test_read_v2_snapshot() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(V2_FIXTURE);
+    assert_eq!(
+        std::fs::read(&path).unwrap()[..7],
+        [&MAGIC[..], &VERSION_2[..]].concat()
+    );
+
+    let key: Key = Blake2b256::digest(b"passphrase-v2").into();
+    assert_eq!(read_header_from(&path).unwrap(), None);
+    assert_eq!(read_from(&path, &key, &[]).unwrap(), LOREM_STR.as_bytes());
+    assert!(read_from(&path, &[0u8; 32], &[]).is_err());
+
+    // the snapshot is written in the current version
+    let dir = tempfile::tempdir().unwrap();
+    let upgraded = dir.path().join("upgraded.stronghold");
+    write_to(LOREM_STR.as_bytes(), &upgraded, &key, &[]).unwrap();
+    assert_eq!(std::fs::read(&upgraded).unwrap()[5..7], VERSION);
+    assert!(read_header_from(&upgraded).unwrap().is_some());
+    assert_eq!(read_from(&upgraded, &key, &[]).unwrap(), LOREM_STR.as_bytes());
+}
@@ -140,3 +140,46 @@ fn test_vaults() {
     })
     .unwrap();
 }
+
+#[test]
+fn test_record_metadata() {
+    let mut view: DbView<Provider> = DbView::new();
+
+    let key0 = Key::random();
+    let vid0 = VaultId::random::<Provider>().unwrap();
+    let rid0 = RecordId::random::<Provider>().unwrap();
+
+    let key1 = Key::random();
+    let vid1 = VaultId::random::<Provider>().unwrap();
+
+    view.write(&key0, vid0, rid0, b"test", RecordHint::new(b"hint").unwrap())
+        .unwrap();
+
+    // records are written without metadata.
+    assert!(view.get_metadata(&key0, vid0, rid0).unwrap().is_empty());
+
+    view.set_metadata(&key0, vid0, rid0, b"metadata").unwrap();
+    assert_eq!(view.get_metadata(&key0, vid0, rid0).unwrap(), b"metadata");
+
+    // updating the record keeps hint and metadata.
+    view.write(&key0, vid0, rid0, b"updated", RecordHint::new(b"other").unwrap())
+        .unwrap();
+    assert_eq!(view.get_metadata(&key0, vid0, rid0).unwrap(), b"metadata");
+    let list0 = view.list_hints_and_ids(&key0, vid0);
+    assert_eq!(list0, vec![(rid0, RecordHint::new(b"hint").unwrap())]);
+    view.get_guard::<Infallible, _>(&key0, vid0, rid0, |g| {
+        assert_eq!(b"updated", &(*g.borrow()));
+
+        Ok(())
+    })
+    .unwrap();
+
+    // metadata can't be accessed with the wrong key.
+    assert!(view.get_metadata(&key1, vid0, rid0).is_err());
+    assert!(view.set_metadata(&key1, vid0, rid0, b"other").is_err());
+
+    // re-encrypting the record for another vault keeps the metadata.
+    let exported = view.export_records(vid0, [rid0]).unwrap();
+    view.import_records(&key0, &key1, vid1, exported).unwrap();
+    assert_eq!(view.get_metadata(&key1, vid1, rid0).unwrap(), b"metadata");
+}